@@ -1,7 +1,5 @@
-#!/usr/bin/env rustr
-
 #[allow(unused_imports)]
-use aoc::{AdventOfCode, friends::*};
+use aoc::friends::*;
 use std::fmt::Display;
 use std::collections::VecDeque;
 
@@ -326,7 +324,6 @@ fn points_circle(marbles: u32) -> Vec<u32> {
 }
 
 enum DS {
-    #[allow(unused)]
     VecDeque,
     LinkedList,
 }
@@ -345,18 +342,35 @@ fn winning_score(players: u32, marbles: u32, approach: DS) -> u32 {
     *scores.iter().max().unwrap()
 }
 
-#[allow(unused_must_use)]
-fn main() {
-    let mut aoc = AdventOfCode::new(2018, 09);
-    let input: String = aoc.get_input();
+#[aoc::solution(year = 2018, day = 9)]
+mod solution {
+    use super::*;
+
+    fn parse(input: &str) -> (u32, u32) {
+        let (players, marbles) = scan_fmt!(input.lines().next().unwrap(),
+                "{} players; last marble is worth {} points",
+                u32, u32);
 
-    let (players, marbles) = scan_fmt!(input.lines().next().unwrap(),
-            "{} players; last marble is worth {} points",
-            u32, u32);
-    let (players, marbles) = (players.unwrap(), marbles.unwrap());
+        (players.unwrap(), marbles.unwrap())
+    }
+
+    fn p1(&(players, marbles): &(u32, u32)) -> u32 {
+        winning_score(players, marbles, DS::LinkedList)
+    }
 
-    aoc.submit_p1(winning_score(players, marbles, DS::LinkedList));
-    aoc.submit_p2(winning_score(players, 100 * marbles, DS::LinkedList));
+    #[variant(p1)]
+    fn p1_naive(&(players, marbles): &(u32, u32)) -> u32 {
+        winning_score(players, marbles, DS::VecDeque)
+    }
+
+    fn p2(&(players, marbles): &(u32, u32)) -> u32 {
+        winning_score(players, 100 * marbles, DS::LinkedList)
+    }
+
+    #[variant(p2)]
+    fn p2_naive(&(players, marbles): &(u32, u32)) -> u32 {
+        winning_score(players, 100 * marbles, DS::VecDeque)
+    }
 }
 
 #[cfg(test)]
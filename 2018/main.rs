@@ -0,0 +1,11 @@
+//! Hosts the days that are registered with `#[aoc::solution]`; see
+//! `aoc::registry`.
+//!
+//! ```text
+//! cargo run --bin aoc18 -- <DAY> [options...]
+//! ```
+
+#[path = "09/marble_mania.rs"]
+mod day09;
+
+aoc::main!();
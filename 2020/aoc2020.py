@@ -10,6 +10,7 @@ The .env file in this folder adds it.
 import sys
 import os
 import glob
+import shlex
 
 sys.path.append(
     os.path.join(
@@ -31,4 +32,6 @@ if __name__ == "__main__" and not __IMPORTED:
     year_dir = os.path.dirname(os.path.abspath(__file__))
     # sys.path.append(year_dir)
 
-    os.system(f"""export PYTHONPATH="${{PYTHON_PATH}}:{year_dir}"; {script}""")
+    # Extra args (e.g. `-o stdout`, `--stdin`) are passed along to the `aoc` crate.
+    args = " ".join(shlex.quote(a) for a in sys.argv[2:])
+    os.system(f"""export PYTHONPATH="${{PYTHON_PATH}}:{year_dir}"; {script} {args}""")
@@ -42,21 +42,11 @@ fn main() {
         plt.show()
     }
     #[cfg(feature = "plot")]
-    {
-        use plotters::prelude::*;
-
-        let root =
-            BitMapBackend::new("lanternfish_population.png", (1920, 1080)).into_drawing_area();
-        root.fill(&WHITE).unwrap();
-        let mut chart = ChartBuilder::on(&root)
-            .build_cartesian_2d(0..points.len(), 0..points.last().unwrap().1 + 1)
-            .unwrap();
-        chart.configure_mesh().draw().unwrap();
-
-        chart.draw_series(LineSeries::new(points, &RED)).unwrap();
-        // .label("y = x^2")
-        // .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
-    }
+    aoc::plot::Chart::new("lanternfish_population.png")
+        .title("lanternfish population")
+        .size(1920, 1080)
+        .line(points.iter().copied())
+        .unwrap();
 
     //  0 | (1 0) | 8
     //  1 | (1 0) | 7
@@ -657,159 +657,92 @@ impl<
 
 #[cfg(feature = "llvm")]
 mod llvm {
-    use std::{collections::HashMap, fmt::Display, hash::Hash, mem, ops::Deref};
-
-    use inkwell::{
-        builder::Builder,
-        context::Context as LlvmContext,
-        execution_engine::{ExecutionEngine, JitFunction},
-        module::Module,
-        types::{IntType, StringRadix},
-        values::IntValue,
-        IntPredicate, OptimizationLevel,
-    };
-    use num_traits::{One, Zero};
+    use std::collections::HashMap;
+
+    use aoc::expr::BinOp;
+    use aoc::jit::{Compiled, Loc, Operand, Program};
 
     use crate::Reg;
 
     use super::InpRef;
     use super::{Expr, ExprMapper};
 
-    pub(crate) struct Context<'ctx> {
-        context: &'ctx LlvmContext,
-        module: Module<'ctx>,
-        builder: Builder<'ctx>,
-        execution_engine: ExecutionEngine<'ctx>,
+    /// Lowers a stage to an `aoc::jit` program: `z` is register 0 (in and
+    /// out) and the digit is input 0.
+    struct Lower<'p> {
+        prog: &'p mut Program,
+        cache: HashMap<Expr<isize>, Operand>,
     }
 
-    impl<'ctx> Context<'ctx> {
-        pub(crate) fn new(ctx: &'ctx LlvmContext) -> Self {
-            let module = ctx.create_module("alu");
-            Self {
-                context: ctx,
-                builder: ctx.create_builder(),
-                execution_engine: module
-                    .create_jit_execution_engine(OptimizationLevel::Aggressive)
-                    .unwrap(),
-                module,
-            }
+    impl Lower<'_> {
+        fn bin(&mut self, op: BinOp, lhs: Operand, rhs: Operand) -> Operand {
+            let t = self.prog.tmp();
+            self.prog.bin(t, op, lhs, rhs);
+            t.into()
         }
     }
 
-    // (old_z, digit) -> z
-    type StageFunction<Imm> = unsafe extern "C" fn(Imm, Imm) -> Imm;
+    impl ExprMapper<isize> for Lower<'_> {
+        type Out = Operand;
 
-    pub(crate) struct TrustMe<I>(I);
-    unsafe impl<I> Send for TrustMe<I> {}
-    unsafe impl<I> Sync for TrustMe<I> {}
-    impl<I> Deref for TrustMe<I> {
-        type Target = I;
-        fn deref(&self) -> &I {
-            &self.0
+        fn early_return_hook(&mut self, e: &Expr<isize>) -> Option<Operand> {
+            self.cache.get(e).copied()
         }
-    }
-
-    impl<Imm: 'static + Display + One + Zero + Clone + Eq + Hash> Expr<Imm> {
-        pub fn jit<'a>(
-            &self,
-            ctx: &'a Context<'a>,
-            id: usize,
-        ) -> TrustMe<JitFunction<'a, StageFunction<Imm>>> {
-            let fn_name = format!("stage{}", id);
-            dbg!(&fn_name);
-
-            let imm_type = ctx
-                .context
-                .custom_width_int_type(mem::size_of::<Imm>() as u32 * 8);
-            let fn_type = imm_type.fn_type(&[imm_type.into(), imm_type.into()], false);
-            let function = ctx.module.add_function(&fn_name, fn_type, None);
-            let basic_block = ctx.context.append_basic_block(function, "main");
-
-            ctx.builder.position_at_end(basic_block);
-
-            let z = function.get_nth_param(0).unwrap().into_int_value();
-            let n = function.get_nth_param(1).unwrap().into_int_value();
-
-            let mut table = HashMap::from_iter(
-                [
-                    (Expr::Input(InpRef { n: 0 }), n),
-                    (Expr::ExistingReg(Reg::Z), z),
-                ]
-                .into_iter(),
-            );
-            struct Jit<'a, 'b, I> {
-                context: &'a Context<'a>,
-                table: &'b mut HashMap<Expr<I>, IntValue<'a>>,
-                imm_type: IntType<'a>,
-            }
-
-            impl<'a, 'b, I: 'a + One + Zero + Clone + Display + Eq + Hash> ExprMapper<I> for Jit<'a, 'b, I> {
-                type Out = IntValue<'a>;
-
-                fn early_return_hook(&mut self, e: &Expr<I>) -> Option<IntValue<'a>> {
-                    self.table.get(e).copied()
-                }
 
-                fn return_hook(&mut self, e: &Expr<I>, ret: &IntValue<'a>) {
-                    self.table.insert(e.clone(), *ret);
-                }
+        fn return_hook(&mut self, e: &Expr<isize>, ret: &Operand) {
+            self.cache.insert(e.clone(), *ret);
+        }
 
-                fn input(&mut self, inp: InpRef) -> IntValue<'a> {
-                    panic!("unhandled input! ({})", inp)
-                }
+        fn input(&mut self, inp: InpRef) -> Operand {
+            assert_eq!(inp.n, 0, "unhandled input! ({})", inp);
+            Operand::Input(0)
+        }
 
-                fn existing_reg(&mut self, reg: Reg) -> IntValue<'a> {
-                    panic!("unprovided initial register! ({})", reg)
-                }
+        fn existing_reg(&mut self, reg: Reg) -> Operand {
+            assert_eq!(reg, Reg::Z, "unprovided initial register! ({})", reg);
+            Loc::Reg(0).into()
+        }
 
-                fn imm(&mut self, imm: &I) -> IntValue<'a> {
-                    self.imm_type
-                        .const_int_from_string(&format!("{}", imm), StringRadix::Decimal)
-                        .unwrap()
-                }
+        fn imm(&mut self, imm: &isize) -> Operand {
+            Operand::Imm(*imm as i64)
+        }
 
-                fn add(&mut self, lhs: IntValue<'a>, rhs: IntValue<'a>) -> IntValue<'a> {
-                    self.context.builder.build_int_nsw_add(lhs, rhs, "add")
-                }
+        fn add(&mut self, lhs: Operand, rhs: Operand) -> Operand {
+            self.bin(BinOp::Add, lhs, rhs)
+        }
 
-                fn mul(&mut self, lhs: IntValue<'a>, rhs: IntValue<'a>) -> IntValue<'a> {
-                    self.context.builder.build_int_nsw_mul(lhs, rhs, "mul")
-                }
+        fn mul(&mut self, lhs: Operand, rhs: Operand) -> Operand {
+            self.bin(BinOp::Mul, lhs, rhs)
+        }
 
-                fn div(&mut self, lhs: IntValue<'a>, rhs: IntValue<'a>) -> IntValue<'a> {
-                    self.context.builder.build_int_signed_div(lhs, rhs, "div")
-                }
+        fn div(&mut self, lhs: Operand, rhs: Operand) -> Operand {
+            self.bin(BinOp::Div, lhs, rhs)
+        }
 
-                fn rem(&mut self, lhs: IntValue<'a>, rhs: IntValue<'a>) -> IntValue<'a> {
-                    self.context.builder.build_int_signed_rem(lhs, rhs, "rem")
-                }
+        fn rem(&mut self, lhs: Operand, rhs: Operand) -> Operand {
+            self.bin(BinOp::Rem, lhs, rhs)
+        }
 
-                fn cmp(&mut self, lhs: IntValue<'a>, rhs: IntValue<'a>) -> IntValue<'a> {
-                    let c =
-                        self.context
-                            .builder
-                            .build_int_compare(IntPredicate::EQ, lhs, rhs, "cmp");
-                    self.context
-                        .builder
-                        .build_int_z_extend(c, self.imm_type, "zext")
-                }
-            }
+        fn cmp(&mut self, lhs: Operand, rhs: Operand) -> Operand {
+            self.bin(BinOp::Eql, lhs, rhs)
+        }
+    }
 
-            let mut j = Jit {
-                context: ctx,
-                table: &mut table,
-                imm_type,
-            };
+    impl Expr<isize> {
+        // (old_z, digit) -> z
+        pub fn jit(&self) -> Compiled {
+            let mut prog = Program::new();
             // no table since we expect to only be dealing with inlined exprs!
-            //
-            // if we weren't in a rust we'd make a `InlinedExpr` newtype that's only
-            // produced by the inline function that is porcelain over this function
-            // which we'd then update to take an option
-            // let ret = self.map(None, &mut e);
-            let ret = self.map(None, &mut j);
-            ctx.builder.build_return(Some(&ret));
-
-            TrustMe(unsafe { ctx.execution_engine.get_function(&fn_name) }.unwrap())
+            let out = self.map(
+                None,
+                &mut Lower {
+                    prog: &mut prog,
+                    cache: HashMap::new(),
+                },
+            );
+            prog.mov(Loc::Reg(0), out);
+
+            prog.compile()
         }
     }
 }
@@ -924,18 +857,7 @@ fn main() {
     //     DashSet::<_, BuildHasherDefault<fxhash::FxHasher64>>::from_iter([0].into_iter());
 
     #[cfg(feature = "llvm")]
-    let ctx = inkwell::context::Context::create();
-    // #[cfg(feature = "llvm")]
-    // let ctx = Box::leak(Box::new());
-    #[cfg(feature = "llvm")]
-    let fns = stages
-        .iter()
-        .enumerate()
-        .map(|(idx, e)| {
-            let ctx = Box::leak(Box::new(llvm::Context::new(&ctx)));
-            e.jit(ctx, idx)
-        })
-        .collect_vec();
+    let fns = stages.iter().map(|e| e.jit()).collect_vec();
 
     // llvm, parallel:    1m 33s
     // llvm, single:     12m 26s
@@ -968,7 +890,11 @@ fn main() {
                     );
 
                     #[cfg(feature = "llvm")]
-                    let new_z = unsafe { func.call(z, inp as isize) };
+                    let new_z = {
+                        let mut regs = [z as i64];
+                        func.call(&mut regs, &[inp as i64]);
+                        regs[0] as isize
+                    };
 
                     (z, new_z, inp)
                 })
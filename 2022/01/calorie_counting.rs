@@ -1,18 +1,20 @@
-#!/usr/bin/env rustr
-
 use aoc::*;
 
-fn main() {
-    let mut aoc = AdventOfCode::new(2022, 1);
-    let inp = aoc.get_input();
+#[aoc::solution(year = 2022, day = 1)]
+mod solution {
+    use super::*;
 
-    let elves = inp
-        .split("\n\n")
-        .map(|m| m.lines().map(|c| c.parse::<usize>().unwrap()).sum());
+    fn parse(inp: &str) -> Vec<usize> {
+        inp.split("\n\n")
+            .map(|m| m.lines().map(|c| c.parse::<usize>().unwrap()).sum())
+            .collect()
+    }
 
-    let p1: usize = elves.clone().max().unwrap();
-    aoc.submit_p1(p1).unwrap();
+    fn p1(elves: &[usize]) -> usize {
+        *elves.iter().max().unwrap()
+    }
 
-    let p2: usize = elves.sorted().rev().take(3).sum();
-    aoc.submit_p2(p2).unwrap();
+    fn p2(elves: &[usize]) -> usize {
+        elves.iter().sorted().rev().take(3).sum()
+    }
 }
@@ -1,5 +1,3 @@
-#!/usr/bin/env rustr
-
 use std::cmp::Ordering;
 
 use aoc::*;
@@ -87,39 +85,109 @@ impl FromStr for Outcome {
     }
 }
 
-fn main() {
-    let mut aoc = AdventOfCode::new(2022, 2);
-    let input = aoc.get_input();
-    let games = input.lines().map(|l| l.split_once(' ').unwrap());
+#[aoc::solution(year = 2022, day = 2)]
+mod solution {
+    use super::*;
 
-    let score = |(opp, you): (Move, Move)| {
+    fn score((opp, you): (Move, Move)) -> u64 {
         (you as u64)
             + match you.cmp(&opp) {
                 Ordering::Greater => 6,
                 Ordering::Equal => 3,
                 _ => 0,
             }
-    };
-    let p1: u64 = games
-        .clone()
-        .map(|(opp, you)| (opp.parse().unwrap(), you.parse().unwrap()))
-        .map(score)
-        .sum();
-    aoc.submit_p1(p1).unwrap();
-
-    let p2: u64 = games
-        .clone()
-        .map(|(opp, you)| (opp.parse().unwrap(), you.parse().unwrap()))
-        .map(|(opp, out): (Move, Outcome)| {
-            let you = match out {
-                Outcome::Lose => opp.le(),
-                Outcome::Draw => opp,
-                Outcome::Win => opp.gt(),
-            };
-
-            (opp, you)
-        })
-        .map(score)
-        .sum();
-    aoc.submit_p2(p2).unwrap();
+    }
+
+    fn games(input: &str) -> impl Iterator<Item = (&str, &str)> {
+        input.lines().map(|l| l.split_once(' ').unwrap())
+    }
+
+    fn p1(input: &str) -> u64 {
+        games(input)
+            .map(|(opp, you)| (opp.parse().unwrap(), you.parse().unwrap()))
+            .map(score)
+            .sum()
+    }
+
+    fn p2(input: &str) -> u64 {
+        games(input)
+            .map(|(opp, you)| (opp.parse().unwrap(), you.parse().unwrap()))
+            .map(|(opp, out): (Move, Outcome)| {
+                let you = match out {
+                    Outcome::Lose => opp.le(),
+                    Outcome::Draw => opp,
+                    Outcome::Win => opp.gt(),
+                };
+
+                (opp, you)
+            })
+            .map(score)
+            .sum()
+    }
+
+    // The `_small` variants do the scoring arithmetically:
+    //
+    // R < P < S < R; wrap-around
+    //
+    // # |  R  |  P  |  S
+    // R |  0  | -1  | -2*
+    // P |  1* |  0  | -1
+    // S |  2  |  1* |  0
+    //
+    // with `+3 % 3`:
+    // # |  R  |  P  |  S
+    // R |  0  |  2  |  1*
+    // P |  1* |  0  |  2
+    // S |  2  |  1* |  0
+    //
+    // 0 => draw => 3
+    // 1 => win  => 6
+    // 2 => loss => 0
+    //
+    // this is just a rotate and then a multiply:
+    // 0 -> 1 * 3 -> 3
+    // 1 -> 2 * 3 -> 6
+    // 2 -> 0 * 3 -> 0
+    //
+    // i.e.: +1 % 3 * 3
+    //
+    // altogether that's:
+    // (((((you - opp) + 3) % 3) + 1) % 3) * 3
+    //
+    // which we can simplify to:
+    // ((you - opp + 4) % 3) * 3
+
+    // part 2 is essentially asking us to index into the table above
+    // 1 -> lose -> 2 (i.e. - 1 (aka +2 % 3) to opp's move)
+    // 2 -> draw -> 0 (i.e. + 0 to opp's move)
+    // 3 -> win  -> 1 (i.e. + 1 to opp's move)
+    //
+    // taking advantage of the circular nature of mod, we get this nice flat
+    // mapping of what number to add:
+    // 1 -> lose -> +2 % 3
+    // 2 -> draw -> +3 % 3
+    // 3 -> win  -> +4 % 3
+    //
+    // which collapses into: `(outcome + (opp - 1) + 1) % 3` for the move to play
+    // to which we add 1 to get it's score
+    // to which we add `(outcome - 1) * 3` for the game score
+    //
+    // all together that's: `(opp + outcome) % 3 + 1 + (outcome - 1) * 3`
+    // or: `(o + r) % 3 + 1 + 3*r - 3`
+    // ->  `(o + r) % 3 + 3*r - 2`
+
+    #[rustfmt::skip]
+    fn g(i: &str, f: fn((u8, u8)) -> u64) -> u64 {
+        i.line_bytes_try_map(|[a,_,b]:[_;3]|(a-b'@',b-b'W')).map(f).sum()
+    }
+
+    #[variant(p1)]
+    fn p1_small(i: &str) -> u64 {
+        g(i, |(o, y)| (y + ((y + 4 - o) % 3) * 3) as _)
+    }
+
+    #[variant(p2)]
+    fn p2_small(i: &str) -> u64 {
+        g(i, |(o, r)| ((o + r) % 3 + 3 * r - 2) as _)
+    }
 }
@@ -1,6 +1,5 @@
 // #![rustfmt::skip]
 
-use aoc::*;
 use std::ops::RangeInclusive;
 
 fn is_subset<T>(a: &RangeInclusive<T>, b: &RangeInclusive<T>) -> bool
@@ -17,29 +16,40 @@ where
     b.start() > a.end()
 }
 
-#[rustfmt::skip]
-fn main(){sub!(|i| -> (usize, usize) {
-
-    let inp = i.lines().map(|l| {
-        let [ one, two ] = &l.split(',').map(|r| {
-            let (a, b) = r.split_once('-').unwrap();
-
-            a.parse::<u32>().unwrap()..=b.parse().unwrap()
-        }).collect::<Vec<_>>()[..] else {
-            panic!()
-        };
-
-        (one.clone(), two.clone())
-    }).map(|(a, b)| {
-        if a.start() > b.start() {
-            (b, a)
-        } else {
-            (a, b)
-        }
-    });
-
-    let p1 = inp.clone().filter(|(a, b)| is_subset::<u32>(&a, &b) || is_subset::<u32>(&b, &a)).count();
-    let p2 = inp.clone().filter(|(a, b)| !is_disjoint(&a, &b)).count();
-
-    (p1, p2)
-});}
+type Pair = (RangeInclusive<u32>, RangeInclusive<u32>);
+
+#[aoc::solution(year = 2022, day = 4)]
+mod solution {
+    use super::*;
+
+    #[rustfmt::skip]
+    fn parse(i: &str) -> Vec<Pair> {
+        i.lines().map(|l| {
+            let [ one, two ] = &l.split(',').map(|r| {
+                let (a, b) = r.split_once('-').unwrap();
+
+                a.parse::<u32>().unwrap()..=b.parse().unwrap()
+            }).collect::<Vec<_>>()[..] else {
+                panic!()
+            };
+
+            (one.clone(), two.clone())
+        }).map(|(a, b)| {
+            if a.start() > b.start() {
+                (b, a)
+            } else {
+                (a, b)
+            }
+        }).collect()
+    }
+
+    fn p1(inp: &[Pair]) -> usize {
+        inp.iter()
+            .filter(|(a, b)| is_subset(a, b) || is_subset(b, a))
+            .count()
+    }
+
+    fn p2(inp: &[Pair]) -> usize {
+        inp.iter().filter(|(a, b)| !is_disjoint(a, b)).count()
+    }
+}
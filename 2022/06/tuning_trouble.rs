@@ -1,5 +1,3 @@
-use aoc::*;
-
 use std::collections::HashSet;
 
 fn find_start(seq: &[char], len: usize) -> Option<usize> {
@@ -14,19 +12,27 @@ fn find_start(seq: &[char], len: usize) -> Option<usize> {
         .map(|(i, _)| i + len)
 }
 
-fn main() {
-    let mut aoc = AdventOfCode::new(2022, 6);
-    let inp = aoc.get_input();
-    let inp = inp.chars().collect_vec();
-    let find_start = |len| find_start(&inp, len).unwrap();
+#[aoc::solution(year = 2022, day = 6)]
+mod solution {
+    use super::*;
+
+    fn parse(inp: &str) -> Vec<char> {
+        inp.chars().collect()
+    }
+
+    fn p1(inp: &[char]) -> usize {
+        find_start(inp, 4).unwrap()
+    }
 
-    aoc.submit_p1(find_start(4)).unwrap();
-    aoc.submit_p2(find_start(14)).unwrap();
+    fn p2(inp: &[char]) -> usize {
+        find_start(inp, 14).unwrap()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{find_start, Itertools};
+    use super::find_start;
+    use aoc::Itertools;
 
     fn test(inp: &str, len: usize, exp: usize) {
         let chars = inp.chars().collect_vec();
@@ -200,10 +200,12 @@ fn main() {
     let p2 = {
         let mut state = State::<10>::default();
         let mut visited = HashSet::new();
+        let mut rec = viz::Recorder::new();
         state.drive(moves, |s| {
-            // eprintln!("\n{}", s.display(-12..14, -6..15));
+            rec.frame(s.display(-12..14, -6..15));
             visited.insert(*s.knots.last().unwrap());
         });
+        rec.finish().unwrap();
 
         visited.len()
     };
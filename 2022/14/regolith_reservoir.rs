@@ -198,15 +198,17 @@ fn main() {
         .map(|l| l.parse::<Path>().unwrap())
         .collect_vec();
     let mut cave = Cave::new(paths.iter());
+    let mut rec = viz::Recorder::new();
 
     let p1 = (1..)
         .take_while(|_| {
-            // eprintln!("{cave}");
+            rec.frame(&cave);
             cave.place(Pos { x: 500, y: 0 }).is_ok()
         })
         .last()
         .unwrap();
     eprintln!("{cave}");
+    rec.finish().unwrap();
     aoc.submit_p1(dbg!(p1)).unwrap();
 
     // Now, place the floor and continue..
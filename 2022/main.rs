@@ -0,0 +1,17 @@
+//! Hosts the days that are registered with `#[aoc::solution]`; see
+//! `aoc::registry`.
+//!
+//! ```text
+//! cargo run --bin aoc22 -- <DAY> [options...]
+//! ```
+
+#[path = "01/calorie_counting.rs"]
+mod day01;
+#[path = "02/rps.rs"]
+mod day02;
+#[path = "04/camp_cleanup.rs"]
+mod day04;
+#[path = "06/tuning_trouble.rs"]
+mod day06;
+
+aoc::main!();
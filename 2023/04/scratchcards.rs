@@ -47,51 +47,75 @@ impl ScratchCard {
     }
 }
 
-fn p2_old(cards: &[ScratchCard]) -> usize {
-    let mut processed = HashMap::with_capacity(cards.len());
-    let mut to_process = HashMap::with_capacity(cards.len());
-
-    for (idx, card) in cards.iter().enumerate() {
-        // let idx = idx + 1;
-        let multiplier = to_process.remove(&idx).unwrap_or(1);
-        // eprintln!("card {}: {} times", idx + 1, multiplier);
-        for matching_child_idx in (idx + 1)..(idx + 1 + card.num_matching()) {
-            // eprintln!("  - +card {}", matching_child_idx + 1);
-            *to_process.entry(matching_child_idx).or_insert(1) += multiplier;
-        }
+#[aoc::solution(year = 2023, day = 4)]
+mod solution {
+    use super::*;
 
-        processed.insert(idx, multiplier);
+    fn parse(inp: &str) -> Vec<ScratchCard> {
+        inp.lines().map_parse::<ScratchCard>().collect_vec()
     }
 
-    processed.values().sum()
-}
-
-fn p2(cards: &[ScratchCard]) -> usize {
-    let mut card_counts = cards.iter().map(|_| 1).collect_vec();
+    fn p1(cards: &[ScratchCard]) -> usize {
+        cards.iter().map(|s| s.points()).sum()
+    }
 
-    for (idx, card) in cards.iter().enumerate() {
-        let multiplier = card_counts[idx];
-        for matching_child_idx in (idx + 1)..(idx + 1 + card.num_matching()) {
-            card_counts[matching_child_idx] += multiplier;
+    #[variant(p2)]
+    fn p2_old(cards: &[ScratchCard]) -> usize {
+        let mut processed = HashMap::with_capacity(cards.len());
+        let mut to_process = HashMap::with_capacity(cards.len());
+
+        for (idx, card) in cards.iter().enumerate() {
+            // let idx = idx + 1;
+            let multiplier = to_process.remove(&idx).unwrap_or(1);
+            // eprintln!("card {}: {} times", idx + 1, multiplier);
+            for matching_child_idx in (idx + 1)..(idx + 1 + card.num_matching()) {
+                // eprintln!("  - +card {}", matching_child_idx + 1);
+                *to_process.entry(matching_child_idx).or_insert(1) += multiplier;
+            }
+
+            processed.insert(idx, multiplier);
         }
+
+        processed.values().sum()
     }
 
-    // if we go backwards, the "child" counts are already "frozen"...
-    //
-    // but it's really just the same thing? propagating the multiplier upwards
-    // instead of downwards
+    fn p2(cards: &[ScratchCard]) -> usize {
+        let mut card_counts = cards.iter().map(|_| 1).collect_vec();
 
-    card_counts.iter().sum()
-}
+        for (idx, card) in cards.iter().enumerate() {
+            let multiplier = card_counts[idx];
+            for matching_child_idx in (idx + 1)..(idx + 1 + card.num_matching()) {
+                card_counts[matching_child_idx] += multiplier;
+            }
+        }
 
-fn main() {
-    let mut aoc = AdventOfCode::new(2023, 4);
-    let inp = aoc.get_input();
-    let scorecards = inp.lines().map_parse::<ScratchCard>().collect_vec();
+        // if we go backwards, the "child" counts are already "frozen"...
+        //
+        // but it's really just the same thing? propagating the multiplier upwards
+        // instead of downwards
 
-    let p1: usize = scorecards.iter().map(|s| s.points()).sum();
-    aoc.submit_p1(p1).unwrap();
+        card_counts.iter().sum()
+    }
+}
 
-    let p2 = p2(&scorecards);
-    aoc.submit_p2(p2).unwrap();
+#[cfg(test)]
+mod tests {
+    use super::INP;
+    use aoc::registry::{self, Variants};
+
+    #[test]
+    fn example() {
+        let all = Variants {
+            all: true,
+            ..Default::default()
+        };
+        let run = registry::find(2023, 4)
+            .unwrap()
+            .run_with(INP, &all)
+            .unwrap();
+
+        assert_eq!(run.p1[0].answer, "13");
+        assert_eq!(run.p2.len(), 2);
+        assert!(run.p2.iter().all(|a| a.answer == "30"));
+    }
 }
@@ -0,0 +1,11 @@
+//! Hosts the days that are registered with `#[aoc::solution]`; see
+//! `aoc::registry`.
+//!
+//! ```text
+//! cargo run --bin aoc23 -- <DAY> [options...]
+//! ```
+
+#[path = "04/scratchcards.rs"]
+mod day04;
+
+aoc::main!();
@@ -0,0 +1,64 @@
+"""Type stubs for the `aoc_rs` extension module (see `src/lib.rs`)."""
+
+from typing import Any, Callable, List, Literal, Optional, Tuple
+
+class CorrectSubmission:
+    """A successful submission."""
+
+    resubmitted: bool
+    """Whether this part had already been solved."""
+    message: Optional[str]
+    """The server's message, for new submissions."""
+    answer: Optional[str]
+    """The answer, for resubmissions and `--output print`."""
+
+class Aoc:
+    """A Python wrapper for the Rust `AdventOfCode` client."""
+
+    def __init__(self, year: int, day: int, /) -> None: ...
+    def get_input(self) -> str:
+        """Grabs the input for the problem."""
+    def get_examples(self) -> List[str]:
+        """Grabs the example blocks from the puzzle description."""
+    def get_answer(self, part: Literal[1, 2]) -> Optional[str]:
+        """Grabs the accepted answer for a part; `None` if it hasn't been solved yet."""
+    def submit_p1(self, answer: Any) -> CorrectSubmission:
+        """Submits part 1. Prints the results on stderr; raises an `AocError` on failure."""
+    def submit_p2(self, answer: Any) -> CorrectSubmission:
+        """Submits part 2. Prints the results on stderr; raises an `AocError` on failure."""
+
+def sub(year: int, day: int, func: Callable[[str], Tuple[Any, Any]], /) -> None:
+    """
+    Calls `func` with the input and submits the two answers it returns.
+
+    Does nothing when not configured to submit (i.e. `-o stdout`); raises an
+    `AocError` if a submission wasn't accepted.
+    """
+
+class AocError(Exception): ...
+class CannotSubmitAutomatically(AocError): ...
+class AuthError(AocError): ...
+class NotFound(AocError): ...
+class InvalidToken(AocError): ...
+
+class WrongAnswer(AocError):
+    attempts: Optional[int]
+    """How many wrong answers have been given so far, if known."""
+    timeout: Optional[int]
+    """Minutes to wait before trying again, if known."""
+    direction: Optional[Literal["too_high", "too_low"]]
+
+class IncorrectResubmission(WrongAnswer):
+    correct: str
+    got: str
+
+class InvalidAnswer(AocError): ...
+class LevelIssue(AocError): ...
+
+class Timeout(AocError):
+    minutes: Optional[int]
+    seconds: Optional[int]
+
+class RequestError(AocError): ...
+class UnknownError(AocError): ...
+class UnexpectedResponse(AocError): ...
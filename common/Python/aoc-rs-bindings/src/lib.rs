@@ -1,8 +1,173 @@
-use aoc::{client::Config, AdventOfCode};
+use aoc::client::{self, AocClient, Config, CorrectSubmission as Correct, ErrDirection, Part};
+use aoc::AdventOfCode;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyType;
 
 use std::env::args_os;
 
+create_exception!(aoc_rs, AocError, PyException);
+create_exception!(aoc_rs, CannotSubmitAutomatically, AocError);
+create_exception!(aoc_rs, AuthError, AocError);
+create_exception!(aoc_rs, NotFound, AocError);
+create_exception!(aoc_rs, InvalidToken, AocError);
+create_exception!(aoc_rs, WrongAnswer, AocError);
+create_exception!(aoc_rs, IncorrectResubmission, WrongAnswer);
+create_exception!(aoc_rs, InvalidAnswer, AocError);
+create_exception!(aoc_rs, LevelIssue, AocError);
+create_exception!(aoc_rs, Timeout, AocError);
+create_exception!(aoc_rs, RequestError, AocError);
+create_exception!(aoc_rs, UnknownError, AocError);
+create_exception!(aoc_rs, UnexpectedResponse, AocError);
+
+/// Makes an instance of the exception type `ty` with the given attributes set.
+fn raise(ty: &PyType, msg: String, attrs: &[(&str, PyObject)]) -> PyErr {
+    let make = || -> PyResult<PyErr> {
+        let exc = ty.call1((msg,))?;
+        for (name, val) in attrs {
+            exc.setattr(*name, val)?;
+        }
+
+        Ok(PyErr::from_instance(exc))
+    };
+
+    make().unwrap_or_else(|err| err)
+}
+
+/// Maps [`client::AocError`]s onto the Python exception hierarchy.
+fn aoc_err(py: Python, err: client::AocError) -> PyErr {
+    use client::AocError as E;
+    match err {
+        E::AuthError(e) => raise(
+            py.get_type::<self::AuthError>(),
+            format!("Authentication failed; check your token? ({e})"),
+            &[],
+        ),
+        E::NotFound(e) => raise(
+            py.get_type::<self::NotFound>(),
+            format!("Got a 404; maybe it's too early? ({e})"),
+            &[],
+        ),
+        E::InvalidToken(msg) => raise(py.get_type::<self::InvalidToken>(), msg, &[]),
+        E::WrongAnswer(attempts, timeout, dir) => {
+            let direction = match dir {
+                ErrDirection::TooHigh => Some("too_high"),
+                ErrDirection::TooLow => Some("too_low"),
+                ErrDirection::Unknown => None,
+            };
+
+            raise(
+                py.get_type::<self::WrongAnswer>(),
+                format!("Wrong answer! {}", dir.to_string()),
+                &[
+                    ("attempts", attempts.into_py(py)),
+                    ("timeout", timeout.into_py(py)),
+                    ("direction", direction.into_py(py)),
+                ],
+            )
+        }
+        E::IncorrectResubmission { correct, got } => raise(
+            py.get_type::<self::IncorrectResubmission>(),
+            format!("Wrong answer! This part was already solved with `{correct}` (not `{got}`)."),
+            &[
+                ("attempts", py.None()),
+                ("timeout", py.None()),
+                ("direction", py.None()),
+                ("correct", correct.into_py(py)),
+                ("got", got.into_py(py)),
+            ],
+        ),
+        E::InvalidAnswer => raise(
+            py.get_type::<self::InvalidAnswer>(),
+            "The server didn't reply to our submission.".into(),
+            &[],
+        ),
+        E::LevelIssue(msg) => raise(py.get_type::<self::LevelIssue>(), msg, &[]),
+        E::Timeout(minutes, seconds) => raise(
+            py.get_type::<self::Timeout>(),
+            format!("Slow down! Hit a timeout: {minutes:?} minutes and {seconds:?} seconds."),
+            &[
+                ("minutes", minutes.into_py(py)),
+                ("seconds", seconds.into_py(py)),
+            ],
+        ),
+        E::RequestError(e) => raise(py.get_type::<self::RequestError>(), e.to_string(), &[]),
+        E::UnknownError(msg) => raise(py.get_type::<self::UnknownError>(), msg, &[]),
+        E::UnexpectedResponse(msg) => raise(py.get_type::<self::UnexpectedResponse>(), msg, &[]),
+    }
+}
+
+fn submit_err(py: Python, err: client::Error) -> PyErr {
+    match err {
+        client::Error::CannotSubmitAutomatically => raise(
+            py.get_type::<self::CannotSubmitAutomatically>(),
+            "Not configured to submit automatically.".into(),
+            &[],
+        ),
+        client::Error::AutoSubmitError(err) => aoc_err(py, err),
+    }
+}
+
+fn part(part: u8) -> PyResult<Part> {
+    match part {
+        1 => Ok(Part::One),
+        2 => Ok(Part::Two),
+        p => Err(PyValueError::new_err(format!("Invalid part: {p}"))),
+    }
+}
+
+/// A successful submission.
+#[pyclass]
+#[derive(Debug, Clone)]
+struct CorrectSubmission {
+    /// Whether this part had already been solved.
+    #[pyo3(get)]
+    resubmitted: bool,
+    /// The server's message, for new submissions.
+    #[pyo3(get)]
+    message: Option<String>,
+    /// The answer, for resubmissions and `--output print`.
+    #[pyo3(get)]
+    answer: Option<String>,
+}
+
+#[pymethods]
+impl CorrectSubmission {
+    fn __repr__(&self) -> String {
+        match (&self.message, &self.answer) {
+            (Some(msg), _) => format!("CorrectSubmission(message={msg:?})"),
+            (_, Some(ans)) => format!(
+                "CorrectSubmission(resubmitted={}, answer={ans:?})",
+                if self.resubmitted { "True" } else { "False" },
+            ),
+            _ => "CorrectSubmission()".into(),
+        }
+    }
+}
+
+impl From<Correct> for CorrectSubmission {
+    fn from(c: Correct) -> Self {
+        match c {
+            Correct::New { message } => Self {
+                resubmitted: false,
+                message: Some(message),
+                answer: None,
+            },
+            Correct::Resubmitted { answer } => Self {
+                resubmitted: true,
+                message: None,
+                answer: Some(answer),
+            },
+            Correct::Unchecked { answer } => Self {
+                resubmitted: false,
+                message: None,
+                answer: Some(answer),
+            },
+        }
+    }
+}
+
 /// A Python wrapper for [`AdventOfCode`].
 #[pyclass]
 #[pyo3(text_signature = "(year, day, /)")]
@@ -10,6 +175,18 @@ struct Aoc {
     inner: AdventOfCode,
 }
 
+impl Aoc {
+    fn client(&self, py: Python) -> PyResult<&AocClient> {
+        self.inner.client().ok_or_else(|| {
+            raise(
+                py.get_type::<self::InvalidToken>(),
+                "We need a token to talk to adventofcode.com.".into(),
+                &[],
+            )
+        })
+    }
+}
+
 #[pymethods]
 impl Aoc {
     /// Constructs a new [`Aoc`] instance.
@@ -31,24 +208,89 @@ impl Aoc {
         self.inner.get_input()
     }
 
+    /// Grabs the example blocks from the puzzle description.
+    #[pyo3(text_signature = "($self)")]
+    fn get_examples(&self, py: Python) -> PyResult<Vec<String>> {
+        self.client(py)?.get_examples().map_err(|e| aoc_err(py, e))
+    }
+
+    /// Grabs the accepted answer for a part (1 or 2); `None` if it hasn't
+    /// been solved yet.
+    #[pyo3(text_signature = "($self, part)")]
+    fn get_answer(&self, py: Python, part: u8) -> PyResult<Option<String>> {
+        self.client(py)?
+            .get_answer(self::part(part)?)
+            .map_err(|e| aoc_err(py, e))
+    }
+
     /// Submits part 1. Prints the results on stderr.
     ///
-    /// Returns `True` or `False` to indicate success or failure.
+    /// Raises an `AocError` if the submission wasn't accepted.
     #[pyo3(text_signature = "($self, answer)")]
-    fn submit_p1(&mut self, answer: &PyAny) -> bool {
-        self.inner.submit_p1(answer).is_ok()
+    fn submit_p1(&mut self, py: Python, answer: &PyAny) -> PyResult<CorrectSubmission> {
+        self.inner
+            .submit_p1(answer)
+            .map(Into::into)
+            .map_err(|e| submit_err(py, e))
     }
 
     /// Submits part 2. Prints the results on stderr.
     ///
-    /// Returns `True` or `False` to indicate success or failure.
+    /// Raises an `AocError` if the submission wasn't accepted.
     #[pyo3(text_signature = "($self, answer)")]
-    fn submit_p2(&mut self, answer: &PyAny) -> bool {
-        self.inner.submit_p2(answer).is_ok()
+    fn submit_p2(&mut self, py: Python, answer: &PyAny) -> PyResult<CorrectSubmission> {
+        self.inner
+            .submit_p2(answer)
+            .map(Into::into)
+            .map_err(|e| submit_err(py, e))
     }
 }
 
+/// Calls `func` with the input and submits the two answers it returns, like
+/// `AdventOfCode::sub`.
+#[pyfunction]
+#[pyo3(text_signature = "(year, day, func, /)")]
+fn sub(py: Python, year: u16, day: u8, func: &PyAny) -> PyResult<()> {
+    let mut aoc = Aoc::new(year, day);
+    let inp = aoc.get_input();
+
+    let (p1, p2): (&PyAny, &PyAny) = func.call1((inp,))?.extract()?;
+
+    // Like `p1`/`p2` on the Python side, not being set up to submit (i.e.
+    // `-o stdout`) isn't an error.
+    let check = |res| match res {
+        Ok(_) | Err(client::Error::CannotSubmitAutomatically) => Ok(()),
+        Err(err) => Err(submit_err(py, err)),
+    };
+    check(aoc.inner.submit_p1(p1))?;
+    check(aoc.inner.submit_p2(p2))
+}
+
 #[pymodule]
-fn aoc_rs(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_class::<Aoc>()
+fn aoc_rs(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Aoc>()?;
+    m.add_class::<CorrectSubmission>()?;
+    m.add_function(wrap_pyfunction!(sub, m)?)?;
+
+    m.add("AocError", py.get_type::<AocError>())?;
+    m.add(
+        "CannotSubmitAutomatically",
+        py.get_type::<CannotSubmitAutomatically>(),
+    )?;
+    m.add("AuthError", py.get_type::<AuthError>())?;
+    m.add("NotFound", py.get_type::<NotFound>())?;
+    m.add("InvalidToken", py.get_type::<InvalidToken>())?;
+    m.add("WrongAnswer", py.get_type::<WrongAnswer>())?;
+    m.add(
+        "IncorrectResubmission",
+        py.get_type::<IncorrectResubmission>(),
+    )?;
+    m.add("InvalidAnswer", py.get_type::<InvalidAnswer>())?;
+    m.add("LevelIssue", py.get_type::<LevelIssue>())?;
+    m.add("Timeout", py.get_type::<Timeout>())?;
+    m.add("RequestError", py.get_type::<RequestError>())?;
+    m.add("UnknownError", py.get_type::<UnknownError>())?;
+    m.add("UnexpectedResponse", py.get_type::<UnexpectedResponse>())?;
+
+    Ok(())
 }
@@ -1,68 +1,69 @@
-from typing import Any, Tuple
+"""
+Helpers for Python solutions, backed by the `aoc` Rust crate through the
+`aoc_rs` extension module (`common/Python/aoc-rs-bindings`).
 
-AOC_REBUILD_ENV_VAR: str = "AOC_REBUILD"
-
-
-def _get_aoc_so(release_build: bool = True) -> str:
-    from glob import glob
-    from os.path import dirname, abspath, join, exists
-    from os import pardir, environ
-    from functools import reduce
+`aoc_rs` is a regular wheel built with maturin; install it into the
+interpreter you're using once with:
 
-    mod_path = dirname(abspath(__file__))
-    up = lambda p: abspath(join(p, pardir))
-    applyN = lambda func, start, N: reduce(lambda acc, _i: func(acc), range(N), start)
+    pip install common/Python/aoc-rs-bindings
 
-    crate = join(up(mod_path), "aoc-rs-bindings")
-    build_type = "release" if release_build else "debug"
-    target_dir = join(join(applyN(up, mod_path, 3), "target"), build_type)
+Dev mode: set `AOC_REBUILD` to rebuild the bindings in place (with
+`maturin develop`, which needs an active virtualenv) before they're
+imported. `AOC_REBUILD=debug` makes a debug build; any other value makes a
+release build.
+"""
 
-    find_so = lambda: glob(f"{target_dir}/libaoc_rs.[a-z][a-z]*")
+from typing import Any, Callable, List, Optional, Tuple
 
-    # dylib, dll, or so
-    if (
-        exists(target_dir)
-        and (path := find_so())
-        and AOC_REBUILD_ENV_VAR not in environ
-    ):
-        return path[0]
-    else:
-        import subprocess
-
-        p = subprocess.Popen(
-            ["cargo", "build"] + ["--release"] if release_build else [], cwd=crate
-        )
-        p.wait()
-        assert p.returncode == 0, "Failed to build `aoc-rs-bindings`."
-
-        p = find_so()
-        assert len(p) == 1, "Cannot find `libaoc_rs`."
+AOC_REBUILD_ENV_VAR: str = "AOC_REBUILD"
 
-        return p[0]
 
+def _rebuild(release_build: bool = True):
+    from os.path import dirname, abspath, join, pardir
+    import subprocess
 
-def _get_aoc_module():
-    import importlib.util
+    crate = abspath(join(dirname(abspath(__file__)), pardir, "aoc-rs-bindings"))
+    cmd = ["maturin", "develop"] + (["--release"] if release_build else [])
 
-    so = _get_aoc_so()
-    if so.endswith(".dylib"):
-        import pathlib, os
+    p = subprocess.run(cmd, cwd=crate)
+    assert p.returncode == 0, "Failed to build `aoc-rs-bindings`."
 
-        renamed = pathlib.Path(so).with_suffix(".so")
-        if not renamed.exists():
-            os.symlink(so, renamed)
 
-        so = renamed
+def _import_aoc_rs():
+    from os import environ
+    import importlib
 
-    spec = importlib.util.spec_from_file_location("aoc_rs", so)
-    aoc = importlib.util.module_from_spec(spec)
-    spec.loader.exec_module(aoc)
+    if (mode := environ.get(AOC_REBUILD_ENV_VAR)) is not None:
+        _rebuild(release_build=mode != "debug")
+        importlib.invalidate_caches()
 
-    return aoc
+    try:
+        return importlib.import_module("aoc_rs")
+    except ImportError as e:
+        raise ImportError(
+            "`aoc_rs` isn't installed; run `pip install common/Python/aoc-rs-bindings` "
+            f"or set `{AOC_REBUILD_ENV_VAR}` (in a virtualenv) to build it in place."
+        ) from e
 
 
-aoc_rs = _get_aoc_module()
-from aoc_rs import Aoc
+aoc_rs = _import_aoc_rs()
+from aoc_rs import (
+    Aoc,
+    CorrectSubmission,
+    AocError,
+    CannotSubmitAutomatically,
+    AuthError,
+    NotFound,
+    InvalidToken,
+    WrongAnswer,
+    IncorrectResubmission,
+    InvalidAnswer,
+    LevelIssue,
+    Timeout,
+    RequestError,
+    UnknownError,
+    UnexpectedResponse,
+)
 
 
 def _infer_year_and_day() -> Tuple[int, int]:
@@ -89,20 +90,59 @@ def inp() -> str:
     return Aoc(*_infer_year_and_day()).get_input().strip()
 
 
-def p1(ans: Any) -> bool:
+def examples() -> List[str]:
+    """
+    Infers the Year and Day from the file name of the file being
+    run and grabs the example blocks from its puzzle description.
+    """
+    return Aoc(*_infer_year_and_day()).get_examples()
+
+
+def answer(part: int) -> Optional[str]:
+    """
+    Infers the Year and Day from the file name of the file being
+    run and grabs the accepted answer for the given part, if any.
+    """
+    return Aoc(*_infer_year_and_day()).get_answer(part)
+
+
+def p1(ans: Any) -> Optional[CorrectSubmission]:
     """
     Infers the Year and Day from the file name of the file being
     run and submits P1 for it.
+
+    Returns `None` when not configured to submit (i.e. `-o stdout`);
+    raises an `AocError` if the submission wasn't accepted.
     """
-    return Aoc(*_infer_year_and_day()).submit_p1(ans)
+    try:
+        return Aoc(*_infer_year_and_day()).submit_p1(ans)
+    except CannotSubmitAutomatically:
+        return None
 
 
-def p2(ans: Any) -> bool:
+def p2(ans: Any) -> Optional[CorrectSubmission]:
     """
     Infers the Year and Day from the file name of the file being
     run and submits P2 for it.
+
+    Returns `None` when not configured to submit (i.e. `-o stdout`);
+    raises an `AocError` if the submission wasn't accepted.
+    """
+    try:
+        return Aoc(*_infer_year_and_day()).submit_p2(ans)
+    except CannotSubmitAutomatically:
+        return None
+
+
+def sub(func: Callable[[str], Tuple[Any, Any]]) -> None:
+    """
+    Infers the Year and Day from the file name of the file being
+    run, calls `func` with its input and submits both answers.
+
+    Mirrors `AdventOfCode::sub` (and `sub!`) in the Rust crate: like `p1`
+    and `p2`, nothing is submitted (or raised) under `-o stdout`.
     """
-    return Aoc(*_infer_year_and_day()).submit_p2(ans)
+    aoc_rs.sub(*_infer_year_and_day(), func)
 
 
 import itertools
@@ -0,0 +1,153 @@
+//! Helpers for working with every day (and language) at once.
+//!
+//! ```text
+//! aoc check <YEAR> <DAY> [input options...]
+//! aoc fetch <YEAR> [DAYS...] [--token TOKEN]
+//! ```
+//!
+//! `check` runs every implementation of a day (in any language) and checks
+//! that they agree; see `aoc::polyglot`. The input is found the same way as for
+//! solutions (cached file, `-i FILE`, `--stdin`, the web, ...); any options
+//! after the day go to `Config`.
+//!
+//! `fetch` caches the inputs and the accepted answers for the given days (or
+//! the whole year) so that the regression tests made by `#[aoc::solution]` have
+//! something to check against; see `aoc::registry`. Things that are already
+//! cached aren't fetched again.
+
+use std::ffi::OsString;
+use std::iter::once;
+use std::path::PathBuf;
+use std::process::exit;
+
+use aoc::client::{AocError, Config, Part};
+use aoc::polyglot;
+use aoc::AdventOfCode;
+use cargo_metadata::MetadataCommand;
+
+const USAGE: &str = "usage: aoc check <YEAR> <DAY> [input options...]
+       aoc fetch <YEAR> [DAYS...] [--token TOKEN]";
+
+fn main() {
+    let mut args = std::env::args_os().skip(1);
+    let mut arg = |what: &str| -> String {
+        args.next()
+            .and_then(|a| a.into_string().ok())
+            .unwrap_or_else(|| {
+                eprintln!("missing {what}\n{USAGE}");
+                exit(2)
+            })
+    };
+
+    let command = arg("command");
+    let year = arg("year").parse().unwrap_or_else(|_| {
+        eprintln!("invalid year\n{USAGE}");
+        exit(2)
+    });
+    match &*command {
+        "check" => {
+            let day = arg("day").parse().unwrap_or_else(|_| {
+                eprintln!("invalid day\n{USAGE}");
+                exit(2)
+            });
+            check(year, day, args)
+        }
+        "fetch" => fetch(year, args),
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2)
+        }
+    }
+}
+
+fn check(year: u16, day: u8, args: impl Iterator<Item = OsString>) {
+    // We're not submitting anything, so no need to insist on a token.
+    let client_args = once(OsString::from("aoc check"))
+        .chain(["--output", "print"].map(OsString::from))
+        .chain(args);
+    let config = Config::get_config_with_custom_args(year, day, None, client_args);
+    let input = AdventOfCode::new_from_config(config).get_input();
+
+    let root = MetadataCommand::new()
+        .no_deps()
+        .exec()
+        .map(|m| m.workspace_root.into())
+        .or_else(|_| std::env::current_dir())
+        .unwrap_or_else(|_| PathBuf::from("."));
+
+    let report = polyglot::check(&root, year, day, &input, polyglot::TIMEOUT);
+    print!("{report}");
+    if !report.is_consistent() {
+        exit(1);
+    }
+}
+
+fn fetch(year: u16, args: impl Iterator<Item = OsString>) {
+    let mut args = args.peekable();
+    let mut days = vec![];
+    while let Some(day) = args.peek().and_then(|a| a.to_str()?.parse::<u8>().ok()) {
+        days.push(day);
+        args.next();
+    }
+    if days.is_empty() {
+        days.extend(1..=25);
+    }
+    let args: Vec<_> = args.collect();
+
+    let mut failed = false;
+    for day in days {
+        // We only want the client; `--stdin` keeps `Config` from looking for
+        // (or insisting on a token for) the input.
+        let client_args = once(OsString::from("aoc fetch"))
+            .chain(["--output", "print", "--stdin"].map(OsString::from))
+            .chain(args.iter().cloned());
+        let config = Config::get_config_with_custom_args(year, day, None, client_args);
+        let Some(client) = config.client() else {
+            eprintln!("we need a token to fetch things (`--token` or `common/creds`)");
+            exit(2)
+        };
+
+        match client.get_cached_input() {
+            Ok(_) => {}
+            // Days that haven't been unlocked yet; there's nothing after them.
+            Err(AocError::NotFound(_)) => {
+                println!("{year}/{day:02}: not available yet");
+                break;
+            }
+            Err(err) => {
+                eprintln!("{year}/{day:02}: couldn't get the input: {err:?}");
+                failed = true;
+                continue;
+            }
+        }
+
+        // (day 25 only has one part)
+        let parts = if day == 25 {
+            &[Part::One][..]
+        } else {
+            &[Part::One, Part::Two]
+        };
+        let mut status = vec![];
+        for &part in parts {
+            let p = part.number();
+            if client.has_cached_answer(part) {
+                status.push(format!("p{p}: cached"));
+                continue;
+            }
+
+            status.push(match client.get_answer(part) {
+                Ok(Some(answer)) => format!("p{p}: `{answer}`"),
+                Ok(None) => format!("p{p}: unsolved"),
+                Err(err) => {
+                    failed = true;
+                    format!("p{p}: {err:?}")
+                }
+            });
+        }
+        println!("{year}/{day:02}: {}", status.join(", "));
+    }
+
+    if failed {
+        exit(1);
+    }
+}
@@ -1,17 +1,17 @@
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, ffi::OsString};
 use std::{
     env::ArgsOs,
     fs::{self, File},
 };
 
-use cargo_metadata::camino::Utf8PathBuf;
 use cargo_metadata::MetadataCommand;
 use clap::{App, Arg, ArgGroup};
 use http::StatusCode;
 use indoc::indoc;
+use itertools::Itertools;
 use once_cell::sync::OnceCell;
 use reqwest::{blocking::Client, header, Error as RequestError, Url};
 use scan_fmt::scan_fmt_some;
@@ -21,6 +21,8 @@ use select::{
 };
 use tap::tap::Tap;
 
+use crate::registry::{Run, Solution, Variants};
+
 static LOG_OUTPUT: OnceCell<bool> = OnceCell::new();
 
 macro_rules! dprintln {
@@ -83,12 +85,21 @@ impl Part {
             Part::Two => "two",
         }
     }
+
+    pub fn number(&self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum CorrectSubmission {
     New { message: String },
     Resubmitted { answer: String },
+    /// Printed instead of submitted (`--output print`).
+    Unchecked { answer: String },
 }
 
 pub type AocResult<T> = Result<T, AocError>;
@@ -147,18 +158,64 @@ impl AocClient {
         get_input(self.year, self.day, &self.token)
     }
 
+    /// Like [`AocClient::get_input`] but uses (and fills in) the input cache.
+    pub fn get_cached_input(&self) -> AocResult<String> {
+        let f = get_cached_file_path(self.year, self.day, Some(&self.token));
+        if let Ok(input) = fs::read_to_string(&f) {
+            return Ok(input);
+        }
+
+        let input = self.get_input()?;
+
+        // If we successfully got input, let's take this opportunity to cache
+        // the input to be nice to the Advent of Code servers:
+        fs::create_dir_all(f.parent().unwrap()).unwrap();
+        fs::write(&f, &input)
+            .unwrap_or_else(|_| panic!("Couldn't write to file `{}`.", f.display()));
+
+        Ok(input)
+    }
+
     pub fn submit_answer(&self, part: Part, answer: &str) -> AocResult<CorrectSubmission> {
-        submit_answer(self.year, self.day, &self.token, part, answer)
+        let res = submit_answer(self.year, self.day, &self.token, part, answer);
+        match &res {
+            Ok(CorrectSubmission::New { .. }) => self.record_answer(part, answer),
+            Ok(CorrectSubmission::Resubmitted { answer })
+            | Err(AocError::IncorrectResubmission {
+                correct: answer, ..
+            }) => self.record_answer(part, answer),
+            _ => {}
+        }
+
+        res
     }
 
     // Tries to get the answer; the inner option will be empty if the given part
     // has not yet been solved.
     pub fn get_answer(&self, part: Part) -> AocResult<Option<String>> {
-        get_answer(self.year, self.day, &self.token, part)
+        let res = get_answer(self.year, self.day, &self.token, part);
+        if let Ok(Some(answer)) = &res {
+            self.record_answer(part, answer);
+        }
+
+        res
     }
 
-    pub(crate) fn get_token(&self) -> &String {
-        &self.token
+    /// Whether we've recorded the answer to `part` already (see
+    /// [`get_cached_input_and_answer`]).
+    pub fn has_cached_answer(&self, part: Part) -> bool {
+        get_cached_answer_path(self.year, self.day, Some(&self.token), part).exists()
+    }
+
+    fn record_answer(&self, part: Part, answer: &str) {
+        let f = get_cached_answer_path(self.year, self.day, Some(&self.token), part);
+
+        // Not being able to record an answer isn't worth failing over.
+        let _ = write_cached_answer(&f, answer);
+    }
+
+    pub fn get_examples(&self) -> AocResult<Vec<String>> {
+        get_examples(self.year, self.day, &self.token)
     }
 }
 
@@ -427,6 +484,31 @@ pub fn get_answer(year: u16, day: u8, token: &str, part: Part) -> AocResult<Opti
     Ok(ans)
 }
 
+/// Grabs the example blocks (the `<pre>`s) from the puzzle description, for
+/// both parts once the second is unlocked.
+pub fn get_examples(year: u16, day: u8, token: &str) -> AocResult<Vec<String>> {
+    let client = get_client(token)?;
+    let url = Url::parse(&base(year, day)).map_err(|e| AocError::UnknownError(e.to_string()))?;
+
+    let resp = client
+        .get(url)
+        .send()
+        .map_err(err_mapper)?
+        .error_for_status()
+        .map_err(err_mapper)?;
+
+    let body = resp.text().map_err(AocError::RequestError)?;
+    Ok(parse_examples(&body))
+}
+
+fn parse_examples(body: &str) -> Vec<String> {
+    Document::from(body)
+        .find(Class("day-desc"))
+        .flat_map(|desc| desc.find(Name("pre")))
+        .map(|pre| pre.text())
+        .collect()
+}
+
 #[derive(Debug, PartialEq)]
 enum InputSource {
     File(String),
@@ -437,6 +519,7 @@ enum InputSource {
 #[derive(Debug, PartialEq)]
 enum OutputSink {
     StdOut,
+    Print,
     Web(AocClient),
 }
 
@@ -446,6 +529,10 @@ pub struct Config {
     day: u8,
     input: InputSource,
     output: OutputSink,
+    client: Option<AocClient>,
+    variants: Variants,
+    /// For complaining about arguments after they've been parsed.
+    usage: String,
 }
 
 macro_rules! cargo_env {
@@ -454,21 +541,28 @@ macro_rules! cargo_env {
     };
 }
 
-fn get_cached_file_path(year: u16, day: u8, tok: Option<&str>) -> PathBuf {
+/// The directory the `.aoc` cache lives in.
+fn cache_root() -> PathBuf {
     // We use the target directory if we're run in a Cargo project; otherwise we
     // just use the current directory.
     // let mut path = PathBuf::from(env!("CARGO_TARGET_DIR"));
-    let mut path = if let Ok(target_dir) = std::env::var("AOC_CACHE_DIR") {
-        Utf8PathBuf::from(target_dir)
+    if let Ok(target_dir) = std::env::var("AOC_CACHE_DIR") {
+        PathBuf::from(target_dir)
     } else {
         MetadataCommand::new()
             .exec()
-            .map(|m| m.target_directory)
-            .unwrap_or_else(|_| Utf8PathBuf::from("./"))
-    };
+            .map(|m| m.target_directory.into())
+            .unwrap_or_else(|_| PathBuf::from("./"))
+    }
+}
 
+fn get_cached_file_path(year: u16, day: u8, tok: Option<&str>) -> PathBuf {
+    get_cached_file_path_in(&cache_root(), year, day, tok)
+}
+
+fn get_cached_file_path_in(root: &Path, year: u16, day: u8, tok: Option<&str>) -> PathBuf {
     // No matter the base path, we use the `.aoc` dir:
-    path.push(".aoc");
+    let mut path = root.join(".aoc");
 
     // If we have a token, we use a subdirectory with the token's name.
     //
@@ -484,7 +578,87 @@ fn get_cached_file_path(year: u16, day: u8, tok: Option<&str>) -> PathBuf {
     path.push(format!("{}", year));
 
     // Finally, the file name:
-    path.tap_mut(|p| p.push(format!("{}.input", day))).into()
+    path.tap_mut(|p| p.push(format!("{}.input", day)))
+}
+
+/// Where we record the answer adventofcode.com accepted for a part; next to
+/// the cached input.
+fn get_cached_answer_path(year: u16, day: u8, tok: Option<&str>, part: Part) -> PathBuf {
+    get_cached_answer_path_in(&cache_root(), year, day, tok, part)
+}
+
+fn get_cached_answer_path_in(
+    root: &Path,
+    year: u16,
+    day: u8,
+    tok: Option<&str>,
+    part: Part,
+) -> PathBuf {
+    get_cached_file_path_in(root, year, day, tok).with_file_name(format!(
+        "{}.p{}.answer",
+        day,
+        part.number()
+    ))
+}
+
+/// Writes the answer to a temporary file first so that regression tests
+/// running at the same time never see half of one.
+fn write_cached_answer(path: &Path, answer: &str) -> std::io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    fs::write(&tmp, answer)?;
+    fs::rename(tmp, path)
+}
+
+/// The cached input and the recorded answer for a part, if we have both; for
+/// regression tests (see `aoc::registry::regression_test`).
+///
+/// Answers are recorded whenever the server tells us what the right answer is
+/// (i.e. on a successful submission or a call to [`get_answer`]).
+pub fn get_cached_input_and_answer(year: u16, day: u8, part: Part) -> Option<(String, String)> {
+    let token = find_creds();
+    get_cached_input_and_answer_in(&cache_root(), token.as_deref(), year, day, part)
+}
+
+/// Looks under the token's directory first and then for inputs that aren't
+/// under one.
+fn get_cached_input_and_answer_in(
+    root: &Path,
+    token: Option<&str>,
+    year: u16,
+    day: u8,
+    part: Part,
+) -> Option<(String, String)> {
+    [token, None].into_iter().find_map(|tok| {
+        let input = fs::read_to_string(get_cached_file_path_in(root, year, day, tok)).ok()?;
+        let answer = get_cached_answer_path_in(root, year, day, tok, part);
+        Some((input, fs::read_to_string(answer).ok()?))
+    })
+}
+
+fn read_token_from_file(cred_file_path: impl AsRef<Path>) -> Option<String> {
+    let mut file = File::open(cred_file_path.as_ref()).ok()?;
+    let mut token = String::new();
+
+    file.read_to_string(&mut token)
+        .unwrap_or_else(|_| panic!("Unable to read `{}`.", cred_file_path.as_ref().display()));
+
+    Some(
+        token
+            .lines()
+            .next()
+            .expect("A token file that's *not* empty.")
+            .to_owned(),
+    )
+}
+
+/// Looks for `common/creds` in the current directory or a few parent
+/// directories.
+fn find_creds() -> Option<String> {
+    read_token_from_file("common/creds")
+        .or_else(|| read_token_from_file("../common/creds"))
+        .or_else(|| read_token_from_file("../../common/creds"))
+        .or_else(|| read_token_from_file("../../../common/creds"))
 }
 
 impl Config {
@@ -505,6 +679,11 @@ impl Config {
         Self::get_config_internal(year, day, tok, Some(args))
     }
 
+    /// The client for adventofcode.com, if we have a token.
+    pub fn client(&self) -> Option<&AocClient> {
+        self.client.as_ref()
+    }
+
     fn get_config_internal<T, I>(
         year: u16,
         day: u8,
@@ -596,8 +775,17 @@ impl Config {
                     .long("output")
                     .possible_value("web")
                     .possible_value("stdout")
+                    .possible_value("print")
                     .case_insensitive(true)
                     .default_value("web")
+                    .long_help(indoc! {"
+                `web` submits answers to adventofcode.com and `stdout` just
+                shows them (and reports that they weren't submitted).
+
+                `print` writes `p1: <answer>` and `p2: <answer>` lines to
+                stdout and treats the answers as accepted; this is what the
+                `aoc` runner uses to compare implementations.
+                    "})
                     .display_order(4),
             )
             .arg(
@@ -626,6 +814,48 @@ impl Config {
                     .number_of_values(1)
                     .display_order(7),
             )
+            .arg(
+                Arg::with_name("record")
+                    .long("record")
+                    .help("Record frames from `aoc::viz::Recorder`s")
+                    .long_help(indoc! {"
+                Without a file, recorded frames are played back in the terminal
+                once the program is done. With a `.cast` file they're written
+                as an asciinema recording; with a `.gif` file, as an animated
+                GIF (needs the `gif` feature).
+                    "})
+                    .value_names(&["FILE"])
+                    .min_values(0)
+                    .max_values(1)
+                    .require_equals(true)
+                    .display_order(8),
+            )
+            .arg(
+                Arg::with_name("variant")
+                    .long("variant")
+                    .help("Submit this variant of a part instead of the default")
+                    .long_help(indoc! {"
+                For solutions registered with `#[aoc::solution]` that have
+                several variants of a part (see `aoc::registry`). Can be given
+                once per part.
+                    "})
+                    .value_names(&["NAME"])
+                    .number_of_values(1)
+                    .multiple(true)
+                    .display_order(9),
+            )
+            .arg(
+                Arg::with_name("all-variants")
+                    .long("all-variants")
+                    .help("Run every variant of each part and check that they agree")
+                    .long_help(indoc! {"
+                Runs every variant of each part (of a solution registered with
+                `#[aoc::solution]`), shows their answers and how long each one
+                took, and stops before submitting anything if they disagree.
+                    "})
+                    .takes_value(false)
+                    .display_order(10),
+            )
             .arg(
                 Arg::with_name("verbose")
                     .short("v")
@@ -649,21 +879,10 @@ impl Config {
             LOG_OUTPUT.set(true).unwrap()
         }
 
-        fn read_token_from_file(cred_file_path: impl AsRef<Path>) -> Option<String> {
-            let mut file = File::open(cred_file_path.as_ref()).ok()?;
-            let mut token = String::new();
-
-            file.read_to_string(&mut token).unwrap_or_else(|_| {
-                panic!("Unable to read `{}`.", cred_file_path.as_ref().display())
-            });
-
-            Some(
-                token
-                    .lines()
-                    .next()
-                    .expect("A token file that's *not* empty.")
-                    .to_owned(),
-            )
+        if matches.is_present("record") {
+            let target = crate::viz::Target::from_arg(matches.value_of("record"))
+                .unwrap_or_else(|e| panic!("Invalid `--record` option: {}", e));
+            crate::viz::set_target(target);
         }
 
         // Check if we've been given a token:
@@ -683,10 +902,7 @@ impl Config {
             token_was_explicitly_specified = false;
 
             // Try to use "common/creds" as a last resort:
-            read_token_from_file("common/creds")
-                .or_else(|| read_token_from_file("../common/creds"))
-                .or_else(|| read_token_from_file("../../common/creds"))
-                .or_else(|| read_token_from_file("../../../common/creds"))
+            find_creds()
         };
 
         let client = token
@@ -701,6 +917,7 @@ impl Config {
                     .expect("We need a token to submit outputs online."),
             ),
             "stdout" => OutputSink::StdOut,
+            "print" => OutputSink::Print,
             _ => unreachable!(),
         };
 
@@ -748,7 +965,11 @@ impl Config {
                 dprintln!("Note: Using cached input file.");
                 InputSource::File(f.to_str().unwrap().to_string())
             } else {
-                InputSource::Web(client.expect("We need a token to get inputs from the web."))
+                InputSource::Web(
+                    client
+                        .clone()
+                        .expect("We need a token to get inputs from the web."),
+                )
             }
         };
 
@@ -762,11 +983,22 @@ impl Config {
             dprintln!("Warning: The given token is not being used for anything.")
         }
 
+        let variants = Variants {
+            all: matches.is_present("all-variants"),
+            chosen: matches
+                .values_of("variant")
+                .map(|v| v.map(String::from).collect())
+                .unwrap_or_default(),
+        };
+
         Config {
             year,
             day,
             input,
             output,
+            client,
+            variants,
+            usage: matches.usage().to_string(),
         }
     }
 }
@@ -809,6 +1041,12 @@ impl AdventOfCode {
         }
     }
 
+    /// The client for talking to adventofcode.com; `None` if we weren't able
+    /// to find a token.
+    pub fn client(&self) -> Option<&AocClient> {
+        self.config.client.as_ref()
+    }
+
     pub fn get_input(&mut self) -> String {
         let ret = self.get_input_inner();
         if self.last_event.is_none() {
@@ -850,27 +1088,7 @@ impl AdventOfCode {
                     out
                 }
                 Web(aoc) => {
-                    let f = get_cached_file_path(
-                        self.config.year,
-                        self.config.day,
-                        Some(&aoc.get_token()),
-                    );
-                    let out = if f.exists() {
-                        fs::read_to_string(f).unwrap() // error handling?
-                    } else {
-                        let input = aoc.get_input().unwrap();
-
-                        // If we successfully got input, let's take this opportunity
-                        // to cache the input to be nice to the Advent of Code
-                        // servers:
-                        fs::create_dir_all(f.parent().unwrap()).unwrap();
-                        fs::write(&f, &input).unwrap_or_else(|_| {
-                            panic!("Couldn't write to file `{}`.", f.display())
-                        });
-
-                        input
-                    };
-
+                    let out = aoc.get_cached_input().unwrap();
                     self.input = Some(out.clone());
                     out
                 }
@@ -878,13 +1096,19 @@ impl AdventOfCode {
         }
     }
 
-    fn submit<T: ToString>(&mut self, part: Part, answer: T) -> Result<CorrectSubmission, Error> {
+    /// `time` is how long the answer took; if not given this is the time since
+    /// the input was fetched or the last part was submitted.
+    fn submit<T: ToString>(
+        &mut self,
+        part: Part,
+        answer: T,
+        time: Option<Duration>,
+    ) -> Result<CorrectSubmission, Error> {
         let answer = answer.to_string();
-        if let Some(last) = self.last_event {
+        if let Some(time) = time.or_else(|| self.last_event.map(|l| l.elapsed())) {
             eprintln!(
-                "{GREY}Part {}: `{answer}` [{:?}]{RESET}",
+                "{GREY}Part {}: `{answer}` [{time:?}]{RESET}",
                 part.to_string(),
-                last.elapsed(),
                 GREY = "\u{001b}[0;37m",
                 RESET = "\u{001b}[0m",
             );
@@ -896,6 +1120,10 @@ impl AdventOfCode {
                 eprintln!("{answer}");
                 Err(Error::CannotSubmitAutomatically)
             }
+            Print => {
+                println!("p{}: {answer}", part.number());
+                Ok(CorrectSubmission::Unchecked { answer })
+            }
             Web(aoc) => aoc
                 .submit_answer(part, &answer)
                 .map_err(Error::AutoSubmitError),
@@ -909,8 +1137,9 @@ impl AdventOfCode {
         &mut self,
         part: Part,
         answer: T,
+        time: Option<Duration>,
     ) -> Result<CorrectSubmission, Error> {
-        let res = self.submit(part, answer);
+        let res = self.submit(part, answer, time);
 
         use AocError::*;
         use CorrectSubmission::*;
@@ -923,6 +1152,7 @@ impl AdventOfCode {
                 eprintln!("🌠 Still correct! Part {} was already solved but {} \
                     is indeed the correct answer!", part.to_string(), answer)
             }
+            Ok(Unchecked { .. }) => {}
             Err(ref err) => match err {
                 CannotSubmitAutomatically => {
                     eprintln!("🌐 Not configured to submit automatically.");
@@ -982,11 +1212,66 @@ impl AdventOfCode {
     }
 
     pub fn submit_p1<T: ToString>(&mut self, answer: T) -> Result<CorrectSubmission, Error> {
-        self.submit_with_feedback(Part::One, answer)
+        self.submit_with_feedback(Part::One, answer, None)
     }
 
     pub fn submit_p2<T: ToString>(&mut self, answer: T) -> Result<CorrectSubmission, Error> {
-        self.submit_with_feedback(Part::Two, answer)
+        self.submit_with_feedback(Part::Two, answer, None)
+    }
+
+    /// Submits the answers from running a registered solution, with the times
+    /// it measured; see [`crate::registry`].
+    pub fn submit_run(&mut self, run: &Run) -> Result<(), Error> {
+        if run.parse > Duration::ZERO {
+            eprintln!(
+                "{GREY}Parsing: [{:?}]{RESET}",
+                run.parse,
+                GREY = "\u{001b}[0;37m",
+                RESET = "\u{001b}[0m"
+            );
+        }
+
+        // Answers that can't be submitted automatically have still been
+        // printed, so we carry on to the next part.
+        for part in [Part::One, Part::Two] {
+            let Some(ans) = run.answer(part) else {
+                continue;
+            };
+            match self.submit_with_feedback(part, &ans.answer, Some(ans.time)) {
+                Ok(_) | Err(Error::CannotSubmitAutomatically) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a registered solution on the input and submits its answers; see
+    /// [`crate::registry`].
+    ///
+    /// With `--all-variants`, this shows every variant's answer and timing
+    /// and panics if the variants of a part disagree. Exits (with the usage)
+    /// if `--variant` names a variant that doesn't exist.
+    pub fn run(&mut self, solution: &Solution) -> Result<(), Error> {
+        let input = self.get_input();
+        let run = solution
+            .run_with(&input, &self.config.variants)
+            .unwrap_or_else(|err| {
+                eprintln!("error: {err}\n\n{}", self.config.usage);
+                std::process::exit(2)
+            });
+
+        if self.config.variants.all {
+            eprint!("{run}");
+            let parts = run.disagreements();
+            assert!(
+                parts.is_empty(),
+                "the variants of part {} disagree!",
+                parts.iter().map(|p| p.to_string()).join(" and "),
+            );
+        }
+
+        self.submit_run(&run)
     }
 
     pub fn sub<P1: ToString, P2: ToString>(
@@ -1120,6 +1405,83 @@ mod response_message_tests {
     }
 }
 
+#[cfg(test)]
+mod example_tests {
+    use super::parse_examples;
+
+    #[test]
+    fn pre_blocks() {
+        let body = "
+            <main>
+            <article class=\"day-desc\"><h2>--- Day 1 ---</h2>
+            <p>For example:</p>
+            <pre><code>1abc2
+pqr3stu8vwx
+</code></pre>
+            <p>In this example, <code>12</code> and <code>38</code>...</p>
+            </article>
+            <p>Your puzzle answer was <code>55</code>.</p>
+            <article class=\"day-desc\"><h2>--- Part Two ---</h2>
+            <pre><code>two1nine
+&lt;eight&gt;
+</code></pre>
+            </article>
+            <pre><code>not an example</code></pre>
+            </main>
+        ";
+
+        assert_eq!(
+            parse_examples(body),
+            ["1abc2\npqr3stu8vwx\n", "two1nine\n<eight>\n"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod cache_tests {
+    use super::*;
+
+    #[test]
+    fn answers() {
+        let dir = std::env::temp_dir().join(format!("aoc-cache-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let answer = |tok, part| get_cached_answer_path_in(&dir, 2015, 3, tok, part);
+        let cached = |tok, part| get_cached_input_and_answer_in(&dir, tok, 2015, 3, part);
+        let write_input = |tok| {
+            let f = get_cached_file_path_in(&dir, 2015, 3, tok);
+            fs::create_dir_all(f.parent().unwrap()).unwrap();
+            fs::write(f, "input").unwrap();
+        };
+
+        write_cached_answer(&answer(Some("tok"), Part::One), "42").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join(".aoc/tok/2015/3.p1.answer")).unwrap(),
+            "42"
+        );
+        assert_eq!(cached(Some("tok"), Part::One), None);
+
+        write_input(Some("tok"));
+        assert_eq!(
+            cached(Some("tok"), Part::One),
+            Some(("input".into(), "42".into()))
+        );
+        assert_eq!(cached(Some("tok"), Part::Two), None);
+
+        // Inputs and answers that aren't under a token are used too:
+        assert_eq!(cached(Some("other"), Part::One), None);
+        write_input(None);
+        write_cached_answer(&answer(None, Part::Two), "7").unwrap();
+        assert_eq!(
+            cached(Some("other"), Part::Two),
+            Some(("input".into(), "7".into()))
+        );
+        assert_eq!(cached(None, Part::Two), Some(("input".into(), "7".into())));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
 // TODO: example input mode?
 //   - `--ex`
 //   - attempts to scrape example code block/prints result on stdout instead of
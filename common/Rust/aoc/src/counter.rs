@@ -0,0 +1,610 @@
+//! Multisets: counting things.
+//!
+//! [`Counter`] works for any hashable key; [`ArrayCounter`] is a faster
+//! drop-in for small alphabets (`u8`s and ASCII `char`s) backed by an array.
+//!
+//! ```
+//! use aoc::counter::Counter;
+//!
+//! let c: Counter<char> = "KTJJT".chars().collect();
+//! assert_eq!(c.counts_sorted(), [(&'J', 2), (&'T', 2), (&'K', 1)]);
+//! assert_eq!(c.modes(), [&'J', &'T']);
+//! ```
+
+use std::collections::hash_map::{self, HashMap, RandomState};
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::ops::{Add, AddAssign, BitAnd, BitOr, Index, Sub, SubAssign};
+
+/// `(key, count)` pairs sorted by count (descending) and then key.
+fn sorted<K: Ord, I: IntoIterator<Item = (K, usize)>>(it: I) -> Vec<(K, usize)> {
+    let mut v: Vec<_> = it.into_iter().collect();
+    v.sort_unstable_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+    v
+}
+
+/// The keys with the highest count, in order.
+fn modes<K: Ord, I: IntoIterator<Item = (K, usize)>>(it: I) -> Vec<K> {
+    let mut max = 0;
+    let mut keys = vec![];
+    for (k, n) in it {
+        if n > max {
+            max = n;
+            keys.clear();
+        }
+        if n == max {
+            keys.push(k);
+        }
+    }
+
+    keys.sort_unstable();
+    keys
+}
+
+/// A multiset of `T`s.
+///
+/// Keys with a count of zero are never stored.
+#[derive(Clone)]
+pub struct Counter<T, S = RandomState> {
+    map: HashMap<T, usize, S>,
+    total: usize,
+}
+
+impl<T, S: Default> Default for Counter<T, S> {
+    fn default() -> Self {
+        Self {
+            map: HashMap::default(),
+            total: 0,
+        }
+    }
+}
+
+impl<T: Debug, S> Debug for Counter<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.map.iter()).finish()
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher> PartialEq for Counter<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher> Eq for Counter<T, S> {}
+
+impl<T: Eq + Hash> Counter<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> Counter<T, S> {
+    pub fn add(&mut self, item: T) {
+        self.add_n(item, 1)
+    }
+
+    pub fn add_n(&mut self, item: T, n: usize) {
+        if n != 0 {
+            *self.map.entry(item).or_default() += n;
+            self.total += n;
+        }
+    }
+
+    /// Removes up to `n` of `item`; returns how many were actually removed.
+    pub fn remove_n(&mut self, item: &T, n: usize) -> usize {
+        let Some(count) = self.map.get_mut(item) else {
+            return 0;
+        };
+
+        let removed = n.min(*count);
+        *count -= removed;
+        if *count == 0 {
+            self.map.remove(item);
+        }
+
+        self.total -= removed;
+        removed
+    }
+
+    pub fn get(&self, item: &T) -> usize {
+        self.map.get(item).copied().unwrap_or(0)
+    }
+
+    /// The number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The sum of all the counts.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Keys and counts, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> + '_ {
+        self.map.iter().map(|(k, &n)| (k, n))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &T> + '_ {
+        self.map.keys()
+    }
+
+    /// The largest count (0 if empty).
+    pub fn max_count(&self) -> usize {
+        self.map.values().copied().max().unwrap_or(0)
+    }
+
+    /// The smaller of the two counts for every key.
+    pub fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        self.iter()
+            .map(|(k, n)| (k.clone(), n.min(other.get(k))))
+            .collect()
+    }
+
+    /// Keys and counts, most common first; ties are broken by key.
+    pub fn counts_sorted(&self) -> Vec<(&T, usize)>
+    where
+        T: Ord,
+    {
+        sorted(self.iter())
+    }
+
+    /// Like [`Counter::counts_sorted`] but just the counts, i.e. the "shape"
+    /// of the multiset (`[3, 2]` for a full house).
+    pub fn shape(&self) -> Vec<usize> {
+        let mut v: Vec<_> = self.map.values().copied().collect();
+        v.sort_unstable_by(|a, b| b.cmp(a));
+        v
+    }
+
+    /// The `k` most common keys (fewer if there aren't `k` keys).
+    pub fn top_k(&self, k: usize) -> Vec<(&T, usize)>
+    where
+        T: Ord,
+    {
+        let mut v = self.counts_sorted();
+        v.truncate(k);
+        v
+    }
+
+    /// Every key with the highest count, sorted.
+    pub fn modes(&self) -> Vec<&T>
+    where
+        T: Ord,
+    {
+        modes(self.iter())
+    }
+
+    /// The key with the highest count, if there's exactly one.
+    pub fn mode(&self) -> Option<&T>
+    where
+        T: Ord,
+    {
+        match &*self.modes() {
+            [one] => Some(*one),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher> Counter<T, S> {
+    /// Makes room for the new keys an iterator might bring, like
+    /// `HashMap::extend` does: all of them if we're empty, otherwise half
+    /// (repeats are likely).
+    fn reserve_for(&mut self, (lower, _): (usize, Option<usize>)) {
+        let additional = if self.map.is_empty() {
+            lower
+        } else {
+            lower.div_ceil(2)
+        };
+        self.map.reserve(additional);
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> Extend<T> for Counter<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve_for(iter.size_hint());
+        for x in iter {
+            self.add(x);
+        }
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> Extend<(T, usize)> for Counter<T, S> {
+    fn extend<I: IntoIterator<Item = (T, usize)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve_for(iter.size_hint());
+        for (x, n) in iter {
+            self.add_n(x, n);
+        }
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> FromIterator<T> for Counter<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut c = Self::default();
+        c.extend(iter);
+        c
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> FromIterator<(T, usize)> for Counter<T, S> {
+    fn from_iter<I: IntoIterator<Item = (T, usize)>>(iter: I) -> Self {
+        let mut c = Self::default();
+        c.extend(iter);
+        c
+    }
+}
+
+impl<T, S> IntoIterator for Counter<T, S> {
+    type Item = (T, usize);
+    type IntoIter = hash_map::IntoIter<T, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher> Index<&T> for Counter<T, S> {
+    type Output = usize;
+
+    /// 0 for missing keys.
+    fn index(&self, item: &T) -> &usize {
+        self.map.get(item).unwrap_or(&0)
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> AddAssign for Counter<T, S> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.extend(rhs.map);
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> Add for Counter<T, S> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+/// Counts saturate at zero.
+impl<T: Eq + Hash, S: BuildHasher + Default> SubAssign for Counter<T, S> {
+    fn sub_assign(&mut self, rhs: Self) {
+        for (k, n) in rhs.map {
+            self.remove_n(&k, n);
+        }
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> Sub for Counter<T, S> {
+    type Output = Self;
+
+    fn sub(mut self, rhs: Self) -> Self {
+        self -= rhs;
+        self
+    }
+}
+
+/// Intersection: the smaller of the two counts.
+impl<T: Eq + Hash, S: BuildHasher + Default> BitAnd for Counter<T, S> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        self.map
+            .into_iter()
+            .map(|(k, n)| {
+                let m = rhs.get(&k);
+                (k, n.min(m))
+            })
+            .collect()
+    }
+}
+
+/// Union: the larger of the two counts.
+impl<T: Eq + Hash, S: BuildHasher + Default> BitOr for Counter<T, S> {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        for (k, n) in rhs.map {
+            let have = self.get(&k);
+            self.add_n(k, n.saturating_sub(have));
+        }
+        self
+    }
+}
+
+/// Keys that can index into an [`ArrayCounter`].
+pub trait SmallKey: Copy + Ord {
+    /// `None` for values outside the alphabet.
+    fn to_index(self) -> Option<u8>;
+    fn from_index(idx: u8) -> Self;
+}
+
+impl SmallKey for u8 {
+    fn to_index(self) -> Option<u8> {
+        Some(self)
+    }
+
+    fn from_index(idx: u8) -> Self {
+        idx
+    }
+}
+
+/// ASCII only.
+impl SmallKey for char {
+    fn to_index(self) -> Option<u8> {
+        self.is_ascii().then_some(self as u8)
+    }
+
+    fn from_index(idx: u8) -> Self {
+        idx as char
+    }
+}
+
+/// A [`Counter`] for `u8`s or ASCII `char`s that doesn't need to hash.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ArrayCounter<T> {
+    counts: [usize; 256],
+    total: usize,
+    _key: std::marker::PhantomData<T>,
+}
+
+impl<T> Default for ArrayCounter<T> {
+    fn default() -> Self {
+        Self {
+            counts: [0; 256],
+            total: 0,
+            _key: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: SmallKey + Debug> Debug for ArrayCounter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T: SmallKey> ArrayCounter<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn idx(item: T) -> usize {
+        match item.to_index() {
+            Some(i) => i as usize,
+            None => panic!("key isn't in the `ArrayCounter`'s alphabet"),
+        }
+    }
+
+    /// Panics if `item` isn't in the alphabet (i.e. a non-ASCII `char`).
+    pub fn add(&mut self, item: T) {
+        self.add_n(item, 1)
+    }
+
+    pub fn add_n(&mut self, item: T, n: usize) {
+        self.counts[Self::idx(item)] += n;
+        self.total += n;
+    }
+
+    /// Removes up to `n` of `item`; returns how many were actually removed.
+    pub fn remove_n(&mut self, item: T, n: usize) -> usize {
+        let c = &mut self.counts[Self::idx(item)];
+        let removed = n.min(*c);
+        *c -= removed;
+        self.total -= removed;
+        removed
+    }
+
+    pub fn get(&self, item: T) -> usize {
+        item.to_index().map_or(0, |i| self.counts[i as usize])
+    }
+
+    /// The number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.counts.iter().filter(|&&n| n != 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Keys (with non-zero counts) and counts, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (T, usize)> + '_ {
+        (0..=255u8)
+            .zip(self.counts)
+            .filter(|&(_, n)| n != 0)
+            .map(|(i, n)| (T::from_index(i), n))
+    }
+
+    pub fn max_count(&self) -> usize {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn counts_sorted(&self) -> Vec<(T, usize)> {
+        sorted(self.iter())
+    }
+
+    pub fn shape(&self) -> Vec<usize> {
+        let mut v: Vec<_> = self.iter().map(|(_, n)| n).collect();
+        v.sort_unstable_by(|a, b| b.cmp(a));
+        v
+    }
+
+    pub fn top_k(&self, k: usize) -> Vec<(T, usize)> {
+        let mut v = self.counts_sorted();
+        v.truncate(k);
+        v
+    }
+
+    pub fn modes(&self) -> Vec<T> {
+        modes(self.iter())
+    }
+
+    pub fn mode(&self) -> Option<T> {
+        match &*self.modes() {
+            [one] => Some(*one),
+            _ => None,
+        }
+    }
+
+    fn zip_with(mut self, rhs: &Self, f: impl Fn(usize, usize) -> usize) -> Self {
+        for (a, &b) in self.counts.iter_mut().zip(rhs.counts.iter()) {
+            *a = f(*a, b);
+        }
+        self.total = self.counts.iter().sum();
+        self
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.clone().zip_with(other, usize::min)
+    }
+
+    pub fn to_counter(&self) -> Counter<T>
+    where
+        T: Hash,
+    {
+        self.iter().collect()
+    }
+}
+
+impl<T: SmallKey> Extend<T> for ArrayCounter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for x in iter {
+            self.add(x);
+        }
+    }
+}
+
+impl<T: SmallKey> FromIterator<T> for ArrayCounter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut c = Self::default();
+        c.extend(iter);
+        c
+    }
+}
+
+impl<T: SmallKey> Index<T> for ArrayCounter<T> {
+    type Output = usize;
+
+    fn index(&self, item: T) -> &usize {
+        item.to_index().map_or(&0, |i| &self.counts[i as usize])
+    }
+}
+
+impl<T: SmallKey> AddAssign for ArrayCounter<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = self.clone().zip_with(&rhs, |a, b| a + b);
+    }
+}
+
+impl<T: SmallKey> Add for ArrayCounter<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.zip_with(&rhs, |a, b| a + b)
+    }
+}
+
+/// Counts saturate at zero.
+impl<T: SmallKey> SubAssign for ArrayCounter<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.clone().zip_with(&rhs, usize::saturating_sub);
+    }
+}
+
+impl<T: SmallKey> Sub for ArrayCounter<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.zip_with(&rhs, usize::saturating_sub)
+    }
+}
+
+impl<T: SmallKey> BitAnd for ArrayCounter<T> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        self.zip_with(&rhs, usize::min)
+    }
+}
+
+impl<T: SmallKey> BitOr for ArrayCounter<T> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.zip_with(&rhs, usize::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iterator_freq_ext::IterFreqExt;
+
+    #[test]
+    fn counting() {
+        let c = "abracadabra".chars().counter();
+        assert_eq!((c.len(), c.total(), c[&'a'], c[&'z']), (5, 11, 5, 0));
+        assert_eq!(c.top_k(2), [(&'a', 5), (&'b', 2)]);
+        assert_eq!(c.shape(), [5, 2, 2, 1, 1]);
+        assert_eq!(c.modes(), [&'a']);
+        assert_eq!(c.mode(), Some(&'a'));
+
+        let ties: Counter<_> = [3, 1, 3, 1, 2].into_iter().collect();
+        assert_eq!(ties.modes(), [&1, &3]);
+        assert_eq!(ties.mode(), None);
+        assert_eq!(ties.counts_sorted(), [(&1, 2), (&3, 2), (&2, 1)]);
+
+        let a = "abracadabra".bytes().array_counter();
+        assert_eq!(a.counts_sorted()[..2], [(b'a', 5), (b'b', 2)]);
+        assert_eq!(a.to_counter(), "abracadabra".bytes().counter());
+        assert_eq!(a.shape(), [5, 2, 2, 1, 1]);
+        assert_eq!(a.modes(), [b'a']);
+    }
+
+    #[test]
+    fn multisets() {
+        let a: Counter<char> = "aaabbc".chars().collect();
+        let b: Counter<char> = "abbbd".chars().collect();
+
+        let sum = a.clone() + b.clone();
+        assert_eq!((sum[&'a'], sum[&'b'], sum.total()), (4, 5, 11));
+
+        let diff = a.clone() - b.clone();
+        assert_eq!(diff, "aac".chars().collect());
+        assert_eq!(diff.total(), 3);
+
+        assert_eq!(a.clone() & b.clone(), "abb".chars().collect());
+        assert_eq!(a.intersection(&b), "abb".chars().collect());
+        assert_eq!(a.clone() | b.clone(), "aaabbbcd".chars().collect());
+
+        let (x, y) = ("aaabbc".chars(), "abbbd".chars());
+        let (x, y): (ArrayCounter<char>, ArrayCounter<char>) = (x.collect(), y.collect());
+        assert_eq!((x.clone() - y.clone()).to_counter(), diff);
+        assert_eq!((x.clone() & y.clone()).total(), 3);
+        assert_eq!((x | y).total(), 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "alphabet")]
+    fn non_ascii() {
+        "naïve".chars().array_counter();
+    }
+}
@@ -0,0 +1,263 @@
+//! Cycle detection for simulations that eventually repeat.
+//!
+//! Everything here drives a `step` function that maps one state to the next.
+//! The `_by_key` variants compare states by a "fingerprint" instead of by the
+//! states themselves; this is useful when the full state is large or carries
+//! along things that don't affect the future (a running total, the tower
+//! height in 2022/17). The fingerprint must still capture everything that
+//! determines future fingerprints or the detected cycle will be wrong.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A cycle in a sequence of states: `x[start] == x[start + len]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    /// Index of the first state that is part of the cycle (μ).
+    pub start: usize,
+    /// Length of the cycle (λ); always at least 1.
+    pub len: usize,
+}
+
+impl Cycle {
+    /// The smallest index whose state is the same as the state at `n`.
+    pub fn equivalent(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.len
+        }
+    }
+
+    /// How many full trips around the cycle there are between the start of
+    /// the cycle and `n`.
+    ///
+    /// Handy for extrapolating values that grow by a fixed amount each trip:
+    /// `value(n) == value(equivalent(n)) + laps(n) * (value(start + len) -
+    /// value(start))`.
+    pub fn laps(&self, n: usize) -> usize {
+        n.saturating_sub(self.start) / self.len
+    }
+}
+
+/// Brent's algorithm; uses O(1) memory but steps states more than the
+/// hash map based [`find`].
+///
+/// Loops forever if the sequence never repeats.
+pub fn brent<S: Clone + Eq>(initial: S, step: impl FnMut(S) -> S) -> Cycle {
+    brent_by_key(initial, step, S::clone)
+}
+
+pub fn brent_by_key<S: Clone, K: Eq>(
+    initial: S,
+    mut step: impl FnMut(S) -> S,
+    mut key: impl FnMut(&S) -> K,
+) -> Cycle {
+    let mut power = 1;
+    let mut len = 1;
+    let mut tortoise = key(&initial);
+    let mut hare = step(initial.clone());
+
+    while tortoise != key(&hare) {
+        if power == len {
+            tortoise = key(&hare);
+            power *= 2;
+            len = 0;
+        }
+
+        hare = step(hare);
+        len += 1;
+    }
+
+    let mut tortoise = initial.clone();
+    let mut hare = (0..len).fold(initial, |s, _| step(s));
+
+    let mut start = 0;
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(tortoise);
+        hare = step(hare);
+        start += 1;
+    }
+
+    Cycle { start, len }
+}
+
+/// Floyd's "tortoise and hare"; uses O(1) memory.
+///
+/// Loops forever if the sequence never repeats.
+pub fn floyd<S: Clone + Eq>(initial: S, step: impl FnMut(S) -> S) -> Cycle {
+    floyd_by_key(initial, step, S::clone)
+}
+
+pub fn floyd_by_key<S: Clone, K: Eq>(
+    initial: S,
+    mut step: impl FnMut(S) -> S,
+    mut key: impl FnMut(&S) -> K,
+) -> Cycle {
+    let mut tortoise = step(initial.clone());
+    let mut hare = step(tortoise.clone());
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(tortoise);
+        hare = step(hare);
+        hare = step(hare);
+    }
+
+    let mut start = 0;
+    let mut tortoise = initial;
+    while key(&tortoise) != key(&hare) {
+        tortoise = step(tortoise);
+        hare = step(hare);
+        start += 1;
+    }
+
+    let mut len = 1;
+    let target = key(&tortoise);
+    let mut hare = step(tortoise);
+    while target != key(&hare) {
+        hare = step(hare);
+        len += 1;
+    }
+
+    Cycle { start, len }
+}
+
+/// Remembers every state (by fingerprint) until one repeats. Steps each state
+/// exactly once.
+///
+/// Returns the cycle along with the state at index `start + len` (which is
+/// the same as the state at `start`).
+///
+/// Loops forever if the sequence never repeats.
+pub fn find<S: Clone + Eq + Hash>(initial: S, step: impl FnMut(S) -> S) -> (Cycle, S) {
+    find_by_key(initial, step, S::clone)
+}
+
+pub fn find_by_key<S, K: Eq + Hash>(
+    initial: S,
+    mut step: impl FnMut(S) -> S,
+    mut key: impl FnMut(&S) -> K,
+) -> (Cycle, S) {
+    let mut seen = HashMap::new();
+    let mut state = initial;
+
+    for idx in 0.. {
+        if let Some(start) = seen.insert(key(&state), idx) {
+            return (
+                Cycle {
+                    start,
+                    len: idx - start,
+                },
+                state,
+            );
+        }
+
+        state = step(state);
+    }
+
+    unreachable!()
+}
+
+/// The state after `n` steps, skipping over whole trips around a cycle once
+/// one is found.
+pub fn fast_forward<S: Clone + Eq + Hash>(state: S, n: usize, step: impl FnMut(S) -> S) -> S {
+    fast_forward_by_key(state, n, step, S::clone)
+}
+
+/// Like [`fast_forward`] but states are compared by fingerprint.
+///
+/// The parts of the state that the fingerprint leaves out are simply stepped
+/// along; they are _not_ extrapolated across the skipped cycles. Use
+/// [`find_by_key`] and [`Cycle::laps`] for that.
+pub fn fast_forward_by_key<S, K: Eq + Hash>(
+    mut state: S,
+    n: usize,
+    mut step: impl FnMut(S) -> S,
+    mut key: impl FnMut(&S) -> K,
+) -> S {
+    let mut seen = HashMap::new();
+
+    let mut idx = 0;
+    while idx < n {
+        if let Some(start) = seen.insert(key(&state), idx) {
+            let remaining = (n - idx) % (idx - start);
+            return (0..remaining).fold(state, |s, _| step(s));
+        }
+
+        state = step(state);
+        idx += 1;
+    }
+
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0, 1, 2, 3, 4, 5, 6, 7, 8, 3, 4, ...
+    fn rho(x: u32) -> u32 {
+        if x == 8 {
+            3
+        } else {
+            x + 1
+        }
+    }
+
+    const RHO: Cycle = Cycle { start: 3, len: 6 };
+
+    #[test]
+    fn detectors_agree() {
+        assert_eq!(brent(0, rho), RHO);
+        assert_eq!(floyd(0, rho), RHO);
+        assert_eq!(find(0, rho), (RHO, 3));
+
+        // Pure cycle:
+        let c = Cycle { start: 0, len: 7 };
+        assert_eq!(brent(0, |x| (x + 1) % 7), c);
+        assert_eq!(floyd(0, |x| (x + 1) % 7), c);
+        assert_eq!(find(0, |x| (x + 1) % 7).0, c);
+
+        // Fixed point:
+        let c = Cycle { start: 2, len: 1 };
+        assert_eq!(brent(0, |x: u8| x.saturating_add(1).min(2)), c);
+        assert_eq!(floyd(0, |x: u8| x.saturating_add(1).min(2)), c);
+    }
+
+    #[test]
+    fn equivalence() {
+        assert_eq!(RHO.equivalent(2), 2);
+        assert_eq!(RHO.equivalent(9), 3);
+        assert_eq!(RHO.equivalent(1_000_000_000), 3 + (1_000_000_000 - 3) % 6);
+        assert_eq!(RHO.laps(2), 0);
+        assert_eq!(RHO.laps(15), 2);
+    }
+
+    #[test]
+    fn fast_forwarding() {
+        for n in 0..40 {
+            let naive = (0..n).fold(0, |s, _| rho(s));
+            assert_eq!(fast_forward(0, n, rho), naive);
+        }
+
+        let (c, _) = find(0, rho);
+        assert_eq!(fast_forward(0, 1 << 40, rho), c.equivalent(1 << 40) as u32);
+    }
+
+    #[test]
+    fn fingerprints() {
+        // The counter doesn't affect what comes next so leaving it out of the
+        // fingerprint still finds the cycle:
+        let step = |(x, count): (u32, u64)| (rho(x), count + u64::from(x));
+
+        assert_eq!(brent_by_key((0, 0), step, |s| s.0), RHO);
+        assert_eq!(floyd_by_key((0, 0), step, |s| s.0), RHO);
+
+        // Extrapolate the counter using the laps:
+        let (c, _) = find_by_key((0, 0), step, |s| s.0);
+        let nth = |n| (0..n).fold((0, 0), |s, _| step(s)).1;
+        let per_lap = nth(c.start + c.len) - nth(c.start);
+
+        let n = 1_000_003;
+        assert_eq!(nth(n), nth(c.equivalent(n)) + c.laps(n) as u64 * per_lap);
+    }
+}
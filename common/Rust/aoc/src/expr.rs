@@ -0,0 +1,974 @@
+//! Integer expressions as a hash-consed DAG.
+//!
+//! An [`Exprs`] owns every node; [`ExprId`]s are handles into it. Building
+//! the same node twice gives back the same id, so common subexpressions are
+//! shared and comparing ids is a (conservative) equality check. Children
+//! always have smaller ids than their parents which means the nodes are
+//! stored in topological order.
+//!
+//! Arithmetic follows Rust's (and the 2021/24 ALU's) semantics: division
+//! truncates towards zero, `%` takes the sign of the left hand side and
+//! [`BinOp::Eql`] is 1 or 0. Operations that would overflow or divide by zero
+//! are left unfolded.
+//!
+//! On top of construction-time constant folding there's:
+//!   - [`Exprs::bounds`]: interval propagation given ranges for the variables
+//!   - [`Exprs::simplify`]: uses the bounds to fold away nodes whose value is
+//!     pinned (i.e. an `eql` that can never be true); this is the trick that
+//!     makes 2021/24 tractable
+//!   - [`Exprs::linear`] and [`Exprs::solve_for`]: for expressions that are
+//!     linear in one unknown (2022/21)
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+
+use num_traits::{CheckedNeg, PrimInt, Signed};
+
+use crate::num::gcd;
+
+/// The value type of an expression.
+pub trait Value: PrimInt + Signed + CheckedNeg + Hash + Debug + Display {}
+impl<T: PrimInt + Signed + CheckedNeg + Hash + Debug + Display> Value for T {}
+
+/// An interned variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Var(u32);
+
+impl Var {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A node in an [`Exprs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExprId(u32);
+
+impl ExprId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eql,
+}
+
+impl BinOp {
+    pub const ALL: [Self; 6] = [
+        BinOp::Add,
+        BinOp::Sub,
+        BinOp::Mul,
+        BinOp::Div,
+        BinOp::Rem,
+        BinOp::Eql,
+    ];
+
+    /// `None` on overflow or division by zero.
+    pub fn apply<T: Value>(self, a: T, b: T) -> Option<T> {
+        use BinOp::*;
+        match self {
+            Add => a.checked_add(&b),
+            Sub => a.checked_sub(&b),
+            Mul => a.checked_mul(&b),
+            Div => a.checked_div(&b),
+            Rem => {
+                if b.is_zero() {
+                    None
+                } else if b == -T::one() {
+                    Some(T::zero())
+                } else {
+                    Some(a % b)
+                }
+            }
+            Eql => Some(if a == b { T::one() } else { T::zero() }),
+        }
+    }
+
+    pub fn is_commutative(self) -> bool {
+        matches!(self, BinOp::Add | BinOp::Mul | BinOp::Eql)
+    }
+
+    pub fn symbol(self) -> &'static str {
+        use BinOp::*;
+        match self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Rem => "%",
+            Eql => "==",
+        }
+    }
+}
+
+impl Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Node<T> {
+    Const(T),
+    Var(Var),
+    Neg(ExprId),
+    Bin(BinOp, ExprId, ExprId),
+}
+
+impl<T> Node<T> {
+    pub fn children(&self) -> impl Iterator<Item = ExprId> {
+        let (a, b) = match *self {
+            Node::Const(_) | Node::Var(_) => (None, None),
+            Node::Neg(a) => (Some(a), None),
+            Node::Bin(_, a, b) => (Some(a), Some(b)),
+        };
+
+        a.into_iter().chain(b)
+    }
+}
+
+/// `(coef * x + constant) / denom` for some unknown `x`; `denom` is always
+/// positive and the three are kept coprime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Linear<T> {
+    pub coef: T,
+    pub constant: T,
+    pub denom: T,
+}
+
+impl<T: Value> Linear<T> {
+    /// Panics for `T::min_value()`.
+    pub fn constant(c: T) -> Self {
+        Self::new(T::zero(), c, T::one()).unwrap()
+    }
+
+    pub fn unknown() -> Self {
+        Self::new(T::one(), T::zero(), T::one()).unwrap()
+    }
+
+    /// `None` for a zero `denom` or if any of the three is `T::min_value()`
+    /// (which we can't negate or take the gcd of).
+    fn new(coef: T, constant: T, denom: T) -> Option<Self> {
+        if denom.is_zero() || [coef, constant, denom].contains(&T::min_value()) {
+            return None;
+        }
+
+        let sign = if denom < T::zero() {
+            -T::one()
+        } else {
+            T::one()
+        };
+        let g = gcd(gcd(coef, constant), denom);
+
+        Some(Self {
+            coef: coef / g * sign,
+            constant: constant / g * sign,
+            denom: denom / g * sign,
+        })
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.coef.is_zero()
+    }
+
+    fn add(self, o: Self) -> Option<Self> {
+        let c =
+            (self.coef.checked_mul(&o.denom)?).checked_add(&o.coef.checked_mul(&self.denom)?)?;
+        let k = (self.constant.checked_mul(&o.denom)?)
+            .checked_add(&o.constant.checked_mul(&self.denom)?)?;
+        Self::new(c, k, self.denom.checked_mul(&o.denom)?)
+    }
+
+    fn neg(self) -> Option<Self> {
+        Some(Self {
+            coef: self.coef.checked_neg()?,
+            constant: self.constant.checked_neg()?,
+            denom: self.denom,
+        })
+    }
+
+    fn mul(self, o: Self) -> Option<Self> {
+        let (l, c) = match (self.is_constant(), o.is_constant()) {
+            (_, true) => (self, o),
+            (true, false) => (o, self),
+            (false, false) => return None,
+        };
+
+        Self::new(
+            l.coef.checked_mul(&c.constant)?,
+            l.constant.checked_mul(&c.constant)?,
+            l.denom.checked_mul(&c.denom)?,
+        )
+    }
+
+    fn div(self, o: Self) -> Option<Self> {
+        if !o.is_constant() || o.constant.is_zero() {
+            return None;
+        }
+
+        Self::new(
+            self.coef.checked_mul(&o.denom)?,
+            self.constant.checked_mul(&o.denom)?,
+            self.denom.checked_mul(&o.constant)?,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// The equation isn't linear in the unknown (or mentions other unknowns,
+    /// or the unknown only cancels out because of truncating division).
+    NotLinear,
+    /// The unknown cancels out and the equation never holds (or the only
+    /// rational solution doesn't hold with truncating division).
+    NoSolution,
+    /// The unknown cancels out and the equation always holds.
+    AnyValue,
+    /// The solution isn't an integer; `num / denom`.
+    NotIntegral { num: String, denom: String },
+}
+
+/// A collection of expression nodes. See the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct Exprs<T = i64> {
+    nodes: Vec<Node<T>>,
+    dedup: HashMap<Node<T>, ExprId>,
+    var_names: Vec<String>,
+    vars: HashMap<String, Var>,
+}
+
+impl<T: Value> Default for Exprs<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Value> Exprs<T> {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            dedup: HashMap::new(),
+            var_names: vec![],
+            vars: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, e: ExprId) -> Node<T> {
+        self.nodes[e.index()]
+    }
+
+    pub fn as_const(&self, e: ExprId) -> Option<T> {
+        match self.node(e) {
+            Node::Const(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Interns a variable name.
+    pub fn var(&mut self, name: &str) -> Var {
+        if let Some(v) = self.vars.get(name) {
+            return *v;
+        }
+
+        let v = Var(self.var_names.len() as u32);
+        self.var_names.push(name.to_string());
+        self.vars.insert(name.to_string(), v);
+        v
+    }
+
+    pub fn lookup_var(&self, name: &str) -> Option<Var> {
+        self.vars.get(name).copied()
+    }
+
+    pub fn var_name(&self, v: Var) -> &str {
+        &self.var_names[v.index()]
+    }
+
+    fn intern(&mut self, n: Node<T>) -> ExprId {
+        if let Some(id) = self.dedup.get(&n) {
+            return *id;
+        }
+
+        let id = ExprId(self.nodes.len() as u32);
+        self.nodes.push(n);
+        self.dedup.insert(n, id);
+        id
+    }
+
+    pub fn constant(&mut self, c: T) -> ExprId {
+        self.intern(Node::Const(c))
+    }
+
+    /// An expression that's just the variable `v`.
+    pub fn leaf(&mut self, v: Var) -> ExprId {
+        self.intern(Node::Var(v))
+    }
+
+    /// Shorthand for interning `name` and making a leaf for it.
+    pub fn sym(&mut self, name: &str) -> ExprId {
+        let v = self.var(name);
+        self.leaf(v)
+    }
+
+    pub fn neg(&mut self, a: ExprId) -> ExprId {
+        match self.node(a) {
+            Node::Const(c) if c != T::min_value() => self.constant(-c),
+            Node::Neg(inner) => inner,
+            _ => self.intern(Node::Neg(a)),
+        }
+    }
+
+    /// Builds `a op b`, folding constants and trivial identities.
+    pub fn bin(&mut self, op: BinOp, a: ExprId, b: ExprId) -> ExprId {
+        use BinOp::*;
+
+        let (ca, cb) = (self.as_const(a), self.as_const(b));
+        if let (Some(x), Some(y)) = (ca, cb) {
+            if let Some(r) = op.apply(x, y) {
+                return self.constant(r);
+            }
+        }
+
+        let (zero, one) = (Some(T::zero()), Some(T::one()));
+        match op {
+            Add if ca == zero => return b,
+            Add | Sub if cb == zero => return a,
+            Sub if a == b => return self.constant(T::zero()),
+            Sub if ca == zero => return self.neg(b),
+            Mul if ca == zero || cb == zero => return self.constant(T::zero()),
+            Mul if ca == one => return b,
+            Mul | Div if cb == one => return a,
+            Rem if cb == one => return self.constant(T::zero()),
+            Eql if a == b => return self.constant(T::one()),
+            _ => {}
+        }
+
+        let (a, b) = if op.is_commutative() && b < a {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        self.intern(Node::Bin(op, a, b))
+    }
+
+    pub fn add(&mut self, a: ExprId, b: ExprId) -> ExprId {
+        self.bin(BinOp::Add, a, b)
+    }
+
+    pub fn sub(&mut self, a: ExprId, b: ExprId) -> ExprId {
+        self.bin(BinOp::Sub, a, b)
+    }
+
+    pub fn mul(&mut self, a: ExprId, b: ExprId) -> ExprId {
+        self.bin(BinOp::Mul, a, b)
+    }
+
+    pub fn div(&mut self, a: ExprId, b: ExprId) -> ExprId {
+        self.bin(BinOp::Div, a, b)
+    }
+
+    pub fn rem(&mut self, a: ExprId, b: ExprId) -> ExprId {
+        self.bin(BinOp::Rem, a, b)
+    }
+
+    pub fn eql(&mut self, a: ExprId, b: ExprId) -> ExprId {
+        self.bin(BinOp::Eql, a, b)
+    }
+
+    /// Every node reachable from `root` (including `root`) that `skip`
+    /// doesn't reject, in topological (ascending id) order.
+    pub(crate) fn reachable(
+        &self,
+        root: ExprId,
+        mut skip: impl FnMut(ExprId) -> bool,
+    ) -> Vec<ExprId> {
+        let mut seen = vec![false; root.index() + 1];
+        let mut stack = vec![root];
+        let mut out = vec![];
+
+        while let Some(e) = stack.pop() {
+            if seen[e.index()] || skip(e) {
+                continue;
+            }
+
+            seen[e.index()] = true;
+            out.push(e);
+            stack.extend(self.node(e).children());
+        }
+
+        out.sort_unstable();
+        out
+    }
+
+    /// Every variable `e` depends on.
+    pub fn vars_in(&self, e: ExprId) -> Vec<Var> {
+        let mut vars: Vec<_> = self
+            .reachable(e, |_| false)
+            .into_iter()
+            .filter_map(|e| match self.node(e) {
+                Node::Var(v) => Some(v),
+                _ => None,
+            })
+            .collect();
+
+        vars.sort_unstable();
+        vars
+    }
+
+    /// Evaluates `e`; `None` if a variable is unbound or an operation
+    /// overflows or divides by zero.
+    pub fn eval(&self, e: ExprId, mut env: impl FnMut(Var) -> Option<T>) -> Option<T> {
+        let mut vals: HashMap<ExprId, T> = HashMap::new();
+        for id in self.reachable(e, |_| false) {
+            let v = match self.node(id) {
+                Node::Const(c) => c,
+                Node::Var(v) => env(v)?,
+                Node::Neg(a) => vals[&a].checked_neg()?,
+                Node::Bin(op, a, b) => op.apply(vals[&a], vals[&b])?,
+            };
+
+            vals.insert(id, v);
+        }
+
+        vals.get(&e).copied()
+    }
+
+    /// The range of values `e` can take when every variable is within the
+    /// range given by `env` (unbounded if `env` returns `None`).
+    pub fn bounds(
+        &self,
+        e: ExprId,
+        env: impl FnMut(Var) -> Option<RangeInclusive<T>>,
+    ) -> RangeInclusive<T> {
+        let mut memo = HashMap::new();
+        self.bounds_memo(e, env, &mut memo)
+    }
+
+    fn bounds_memo(
+        &self,
+        e: ExprId,
+        mut env: impl FnMut(Var) -> Option<RangeInclusive<T>>,
+        memo: &mut HashMap<ExprId, (T, T)>,
+    ) -> RangeInclusive<T> {
+        let full = (T::min_value(), T::max_value());
+
+        for id in self.reachable(e, |id| memo.contains_key(&id)) {
+            let b = match self.node(id) {
+                Node::Const(c) => (c, c),
+                Node::Var(v) => env(v).map(|r| (*r.start(), *r.end())).unwrap_or(full),
+                Node::Neg(a) => {
+                    let (lo, hi) = memo[&a];
+                    match (hi.checked_neg(), lo.checked_neg()) {
+                        (Some(l), Some(h)) => (l, h),
+                        _ => full,
+                    }
+                }
+                Node::Bin(op, a, b) => bin_bounds(op, memo[&a], memo[&b]).unwrap_or(full),
+            };
+
+            memo.insert(id, b);
+        }
+
+        let (lo, hi) = memo[&e];
+        lo..=hi
+    }
+
+    /// Rebuilds `e`, replacing any node whose bounds (see [`Exprs::bounds`])
+    /// pin it to a single value with that value and dropping `%`s that can't
+    /// have an effect.
+    pub fn simplify(
+        &mut self,
+        e: ExprId,
+        mut env: impl FnMut(Var) -> Option<RangeInclusive<T>>,
+    ) -> ExprId {
+        let mut memo = HashMap::new();
+        let mut map: HashMap<ExprId, ExprId> = HashMap::new();
+
+        for id in self.reachable(e, |_| false) {
+            let new = match self.node(id) {
+                n @ (Node::Const(_) | Node::Var(_)) => self.intern(n),
+                Node::Neg(a) => self.neg(map[&a]),
+                Node::Bin(op, a, b) => {
+                    let (a, b) = (map[&a], map[&b]);
+                    let (ba, bb) = (
+                        self.bounds_memo(a, &mut env, &mut memo),
+                        self.bounds_memo(b, &mut env, &mut memo),
+                    );
+
+                    match op {
+                        // `a % m` where `0 <= a < m` is just `a`:
+                        BinOp::Rem
+                            if *ba.start() >= T::zero()
+                                && *bb.start() == *bb.end()
+                                && *ba.end() < *bb.start() =>
+                        {
+                            a
+                        }
+                        _ => self.bin(op, a, b),
+                    }
+                }
+            };
+
+            let b = self.bounds_memo(new, &mut env, &mut memo);
+            let new = if b.start() == b.end() {
+                self.constant(*b.start())
+            } else {
+                new
+            };
+
+            map.insert(id, new);
+        }
+
+        map[&e]
+    }
+
+    /// Rebuilds `e` with the variables for which `f` returns `Some`
+    /// replaced.
+    pub fn subst(&mut self, e: ExprId, mut f: impl FnMut(Var) -> Option<ExprId>) -> ExprId {
+        let mut map: HashMap<ExprId, ExprId> = HashMap::new();
+        for id in self.reachable(e, |_| false) {
+            let new = match self.node(id) {
+                Node::Var(v) => f(v).unwrap_or(id),
+                Node::Const(_) => id,
+                Node::Neg(a) => self.neg(map[&a]),
+                Node::Bin(op, a, b) => self.bin(op, map[&a], map[&b]),
+            };
+
+            map.insert(id, new);
+        }
+
+        map[&e]
+    }
+
+    /// `e` as a linear function of `unknown`; `None` if `e` isn't linear in it
+    /// or depends on any other variable.
+    ///
+    /// Division is treated as exact (rational) division here, not truncating
+    /// division; [`Exprs::solve_for`] makes up for this by checking its
+    /// answers.
+    pub fn linear(&self, e: ExprId, unknown: Var) -> Option<Linear<T>> {
+        self.linear_and_exactness(e, unknown).map(|(l, _)| l)
+    }
+
+    /// [`Exprs::linear`] and whether every division in `e` is exact for all
+    /// values of the unknown (i.e. `(2x + 4) / 2` but not `x / 2`).
+    fn linear_and_exactness(&self, e: ExprId, unknown: Var) -> Option<(Linear<T>, bool)> {
+        let mut exact = true;
+        let mut forms: HashMap<ExprId, Linear<T>> = HashMap::new();
+        for id in self.reachable(e, |_| false) {
+            let l = match self.node(id) {
+                Node::Const(c) => Linear::new(T::zero(), c, T::one())?,
+                Node::Var(v) if v == unknown => Linear::unknown(),
+                Node::Var(_) => return None,
+                Node::Neg(a) => forms[&a].neg()?,
+                Node::Bin(op, a, b) => {
+                    let (a, b) = (forms[&a], forms[&b]);
+                    match op {
+                        BinOp::Add => a.add(b)?,
+                        BinOp::Sub => a.add(b.neg()?)?,
+                        BinOp::Mul => a.mul(b)?,
+                        BinOp::Div => {
+                            let q = a.div(b)?;
+                            exact &= a.denom.is_one() && b.denom.is_one() && q.denom.is_one();
+                            q
+                        }
+                        BinOp::Rem | BinOp::Eql => {
+                            let both_int = a.is_constant()
+                                && b.is_constant()
+                                && a.denom.is_one()
+                                && b.denom.is_one();
+                            if !both_int {
+                                return None;
+                            }
+
+                            Linear::constant(op.apply(a.constant, b.constant)?)
+                        }
+                    }
+                }
+            };
+
+            forms.insert(id, l);
+        }
+
+        forms.get(&e).map(|&l| (l, exact))
+    }
+
+    /// Solves for the value of `unknown` that makes `eq` hold.
+    ///
+    /// If `eq` is an [`BinOp::Eql`] node its two sides are set equal;
+    /// otherwise `eq` is set equal to zero.
+    ///
+    /// The answer is checked with [`Exprs::eval`]: when the only root of the
+    /// rational equation doesn't survive truncating division the equation has
+    /// no (integer) solution. If the unknown only cancels out thanks to
+    /// division that isn't exact (i.e. `(x / 2) * 2 - x`) the equation isn't
+    /// really linear and we give up.
+    pub fn solve_for(&self, unknown: Var, eq: ExprId) -> Result<T, SolveError> {
+        let linear = |e| {
+            self.linear_and_exactness(e, unknown)
+                .ok_or(SolveError::NotLinear)
+        };
+        let ((l, l_exact), (r, r_exact), target) = match self.node(eq) {
+            Node::Bin(BinOp::Eql, l, r) => (linear(l)?, linear(r)?, T::one()),
+            _ => (linear(eq)?, (Linear::constant(T::zero()), true), T::zero()),
+        };
+        let exact = l_exact && r_exact;
+
+        // (coef * x + constant) / denom == 0 => x = -constant / coef
+        let diff = r
+            .neg()
+            .and_then(|r| l.add(r))
+            .ok_or(SolveError::NotLinear)?;
+        match (diff.coef.is_zero(), diff.constant.is_zero()) {
+            (true, _) if !exact => Err(SolveError::NotLinear),
+            (true, true) => Err(SolveError::AnyValue),
+            (true, false) => Err(SolveError::NoSolution),
+            (false, _) => {
+                let num = diff.constant.checked_neg().ok_or(SolveError::NotLinear)?;
+                let x = num.checked_div(&diff.coef).ok_or(SolveError::NotLinear)?;
+                if (num % diff.coef).is_zero() {
+                    match self.eval(eq, |v| (v == unknown).then_some(x)) {
+                        Some(v) if v == target => Ok(x),
+                        _ => Err(SolveError::NoSolution),
+                    }
+                } else {
+                    Err(SolveError::NotIntegral {
+                        num: num.to_string(),
+                        denom: diff.coef.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Something that prints `e` fully parenthesised.
+    ///
+    /// Subexpressions that are used more than once are printed once, on their
+    /// own line (`#3 = (x + y)`), and referred to by id (`#3`) after that so
+    /// that deep DAGs don't blow up.
+    pub fn display(&self, e: ExprId) -> DisplayExpr<'_, T> {
+        DisplayExpr(self, e)
+    }
+}
+
+fn bin_bounds<T: Value>(op: BinOp, (a_lo, a_hi): (T, T), (b_lo, b_hi): (T, T)) -> Option<(T, T)> {
+    use BinOp::*;
+
+    let corners = |f: fn(T, T) -> Option<T>| -> Option<(T, T)> {
+        let c = [
+            f(a_lo, b_lo)?,
+            f(a_lo, b_hi)?,
+            f(a_hi, b_lo)?,
+            f(a_hi, b_hi)?,
+        ];
+        Some((
+            c.iter().copied().min().unwrap(),
+            c.iter().copied().max().unwrap(),
+        ))
+    };
+
+    match op {
+        Add => Some((a_lo.checked_add(&b_lo)?, a_hi.checked_add(&b_hi)?)),
+        Sub => Some((a_lo.checked_sub(&b_hi)?, a_hi.checked_sub(&b_lo)?)),
+        Mul => corners(|x, y| x.checked_mul(&y)),
+        Div if b_lo <= T::zero() && T::zero() <= b_hi => None,
+        Div => corners(|x, y| x.checked_div(&y)),
+        Rem if b_lo <= T::zero() && T::zero() <= b_hi || b_lo == T::min_value() => None,
+        Rem => {
+            // |a % b| < |b| and the result has the sign of `a`:
+            let max_mag = b_lo.abs().max(b_hi.abs()) - T::one();
+            let min_mag = b_lo.abs().min(b_hi.abs());
+
+            if -min_mag < a_lo && a_hi < min_mag {
+                Some((a_lo, a_hi))
+            } else {
+                let lo = if a_lo < T::zero() {
+                    a_lo.max(-max_mag)
+                } else {
+                    T::zero()
+                };
+                let hi = if a_hi > T::zero() {
+                    a_hi.min(max_mag)
+                } else {
+                    T::zero()
+                };
+                Some((lo, hi))
+            }
+        }
+        Eql if a_lo == a_hi && b_lo == b_hi && a_lo == b_lo => Some((T::one(), T::one())),
+        Eql if a_hi < b_lo || b_hi < a_lo => Some((T::zero(), T::zero())),
+        Eql => Some((T::zero(), T::one())),
+    }
+}
+
+pub struct DisplayExpr<'e, T>(&'e Exprs<T>, ExprId);
+
+impl<T: Value> Display for DisplayExpr<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let DisplayExpr(g, e) = *self;
+        let nodes = g.reachable(e, |_| false);
+
+        let mut uses: HashMap<ExprId, usize> = HashMap::new();
+        for &id in &nodes {
+            for c in g.node(id).children() {
+                *uses.entry(c).or_default() += 1;
+            }
+        }
+        let shared = |id: ExprId| {
+            uses.get(&id).copied().unwrap_or(0) > 1
+                && matches!(g.node(id), Node::Neg(_) | Node::Bin(..))
+        };
+
+        // Children come first so everything's been rendered by the time we
+        // get to its parents; nodes that are used once are only needed once.
+        let mut rendered: HashMap<ExprId, String> = HashMap::new();
+        for id in nodes {
+            let mut child = |c: ExprId| {
+                if shared(c) {
+                    format!("#{}", c.index())
+                } else {
+                    rendered.remove(&c).unwrap()
+                }
+            };
+            let s = match g.node(id) {
+                Node::Const(c) => c.to_string(),
+                Node::Var(v) => g.var_name(v).to_string(),
+                Node::Neg(a) => format!("-{}", child(a)),
+                Node::Bin(op, a, b) => {
+                    let a = child(a);
+                    format!("({a} {op} {})", child(b))
+                }
+            };
+
+            if shared(id) {
+                writeln!(f, "#{} = {s}", id.index())?;
+                rendered.insert(id, String::new());
+            } else {
+                rendered.insert(id, s);
+            }
+        }
+
+        f.write_str(&rendered[&e])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_consing_and_folding() {
+        let mut g: Exprs = Exprs::new();
+        let x = g.sym("x");
+        let y = g.sym("y");
+
+        assert_eq!(g.add(x, y), g.add(y, x));
+        assert_eq!(g.sym("x"), x);
+
+        let zero = g.constant(0);
+        let one = g.constant(1);
+        assert_eq!(g.add(x, zero), x);
+        assert_eq!(g.mul(one, x), x);
+        assert_eq!(g.mul(x, zero), zero);
+        assert_eq!(g.sub(x, x), zero);
+        assert_eq!(g.eql(y, y), one);
+
+        let (a, b) = (g.constant(7), g.constant(-2));
+        let d = g.div(a, b);
+        let r = g.rem(a, b);
+        assert_eq!(g.as_const(d), Some(-3));
+        assert_eq!(g.as_const(r), Some(1));
+
+        // Not folded:
+        let div_zero = g.div(a, zero);
+        assert_eq!(g.node(div_zero), Node::Bin(BinOp::Div, a, zero));
+    }
+
+    #[test]
+    fn eval_and_display() {
+        let mut g: Exprs<i32> = Exprs::new();
+        let (x, y) = (g.sym("x"), g.sym("y"));
+        let three = g.constant(3);
+        let s = g.add(x, y);
+        let e = g.mul(s, three);
+
+        assert_eq!(g.display(e).to_string(), "(3 * (x + y))");
+
+        // Shared subexpressions are printed once:
+        let mut d = s;
+        for _ in 0..64 {
+            d = g.mul(d, d);
+        }
+        let shown = g.display(d).to_string();
+        assert!(shown.starts_with(&format!("#{} = (x + y)\n", s.index())));
+        assert_eq!(shown.lines().count(), 65);
+        let env = |v: Var| Some(if v.index() == 0 { 4 } else { -1 });
+        assert_eq!(g.eval(e, env), Some(9));
+        assert_eq!(g.eval(e, |_| None), None);
+        assert_eq!(g.vars_in(e).len(), 2);
+    }
+
+    #[test]
+    fn bounds_propagation() {
+        // One 2021/24 ALU block's "x" register:
+        //   x = ((z % 26) + 12) != w
+        let mut g: Exprs = Exprs::new();
+        let (w, z) = (g.var("w"), g.var("z"));
+        let (we, ze) = (g.leaf(w), g.leaf(z));
+
+        let c26 = g.constant(26);
+        let c12 = g.constant(12);
+        let m = g.rem(ze, c26);
+        let m = g.add(m, c12);
+        let eq = g.eql(m, we);
+
+        let env = |v: Var| Some(if v == w { 1..=9 } else { 0..=i64::MAX });
+        assert_eq!(g.bounds(m, env), 12..=37);
+        assert_eq!(g.bounds(eq, env), 0..=0);
+
+        let s = g.simplify(eq, env);
+        assert_eq!(g.as_const(s), Some(0));
+
+        // `z % 26` is just `z` when `z` is small:
+        let d = g.rem(ze, c26);
+        let s = g.simplify(d, |v| Some(if v == w { 1..=9 } else { 0..=25 }));
+        assert_eq!(s, ze);
+
+        // Dividing by a range that spans zero tells us nothing:
+        let q = g.div(c26, we);
+        assert_eq!(g.bounds(q, |_| Some(-1..=1)), i64::MIN..=i64::MAX);
+    }
+
+    #[test]
+    fn monkey_math() {
+        let input = "root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32";
+
+        let defs: HashMap<_, _> = input.lines().map(|l| l.split_once(": ").unwrap()).collect();
+
+        fn build(
+            g: &mut Exprs,
+            defs: &HashMap<&str, &str>,
+            name: &str,
+            unknown: Option<&str>,
+        ) -> ExprId {
+            if Some(name) == unknown {
+                return g.sym(name);
+            }
+
+            let def = defs[name];
+            if let Ok(n) = def.parse() {
+                return g.constant(n);
+            }
+
+            let [l, op, r]: [&str; 3] = def.split(' ').collect::<Vec<_>>().try_into().unwrap();
+            let (l, r) = (build(g, defs, l, unknown), build(g, defs, r, unknown));
+            let op = BinOp::ALL.into_iter().find(|o| o.symbol() == op).unwrap();
+            g.bin(op, l, r)
+        }
+
+        let mut g = Exprs::new();
+        let root = build(&mut g, &defs, "root", None);
+        assert_eq!(g.as_const(root), Some(152));
+
+        let (l, r) = defs["root"].split_once(" + ").unwrap();
+        let (l, r) = (
+            build(&mut g, &defs, l, Some("humn")),
+            build(&mut g, &defs, r, Some("humn")),
+        );
+        let eq = g.eql(l, r);
+        let humn = g.lookup_var("humn").unwrap();
+
+        assert_eq!(g.solve_for(humn, eq), Ok(301));
+    }
+
+    #[test]
+    fn solve_errors() {
+        let mut g: Exprs = Exprs::new();
+        let x = g.var("x");
+        let (xe, y) = (g.leaf(x), g.sym("y"));
+        let (two, three) = (g.constant(2), g.constant(3));
+
+        let sq = g.mul(xe, xe);
+        assert_eq!(g.solve_for(x, sq), Err(SolveError::NotLinear));
+
+        let with_y = g.add(xe, y);
+        assert_eq!(g.solve_for(x, with_y), Err(SolveError::NotLinear));
+
+        let two_x = g.mul(two, xe);
+        let eq = g.eql(two_x, three);
+        assert!(matches!(
+            g.solve_for(x, eq),
+            Err(SolveError::NotIntegral { .. })
+        ));
+
+        // (x / 2) * 2 - 3 == 0; 3 is a root with exact division but 3 / 2
+        // truncates:
+        let h = g.div(xe, two);
+        let h = g.mul(h, two);
+        let e = g.sub(h, three);
+        assert_eq!(g.solve_for(x, e), Err(SolveError::NoSolution));
+        let four = g.constant(4);
+        let e = g.eql(h, four);
+        assert_eq!(g.solve_for(x, e), Ok(4));
+
+        let same = g.sub(xe, xe);
+        assert_eq!(g.solve_for(x, same), Err(SolveError::AnyValue));
+
+        // The unknown only cancels out with exact division: (x / 2) * 2 - x
+        // isn't zero for odd x and x / 2 - (x - 1) / 2 is zero for x = 0.
+        let e = g.sub(h, xe);
+        assert_eq!(g.solve_for(x, e), Err(SolveError::NotLinear));
+        let one = g.constant(1);
+        let x_1 = g.sub(xe, one);
+        let (a, b) = (g.div(xe, two), g.div(x_1, two));
+        let e = g.sub(a, b);
+        assert_eq!(g.solve_for(x, e), Err(SolveError::NotLinear));
+        let (four, two_x) = (g.constant(4), g.mul(two, xe));
+        let e = g.add(two_x, four);
+        let e = g.div(e, two);
+        let e = g.sub(e, xe);
+        assert_eq!(g.solve_for(x, e), Err(SolveError::NoSolution));
+
+        // Overflow makes things non-linear rather than panicking:
+        let (max, min) = (g.constant(i64::MAX), g.constant(i64::MIN));
+        let e = g.add(xe, max);
+        let e = g.add(e, max);
+        assert_eq!(g.solve_for(x, e), Err(SolveError::NotLinear));
+        let e = g.sub(xe, min);
+        assert_eq!(g.solve_for(x, e), Err(SolveError::NotLinear));
+        let e = g.add(xe, min);
+        assert_eq!(g.solve_for(x, e), Err(SolveError::NotLinear));
+        assert_eq!(g.solve_for(x, two), Err(SolveError::NoSolution));
+    }
+}
@@ -365,7 +365,48 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::friends::Accumulator;
+    use crate::friends::{Accumulator, StateSequence, StateSequenceMutate};
+    use crate::sequence;
+
+    #[sequence]
+    #[derive(Debug)]
+    enum Dir {
+        Up,
+        Right,
+        Down,
+        Left,
+    }
+
+    #[sequence(once, mut, into)]
+    #[derive(Debug)]
+    enum Stage {
+        Start,
+        Mid,
+        End,
+    }
+
+    #[sequence(once, val, into)]
+    #[derive(Debug)]
+    enum Phase {
+        New,
+        Waxing,
+        Full,
+        Waning,
+    }
+
+    #[sequence(loop, val, into)]
+    #[derive(Debug)]
+    enum Lone {
+        Only,
+    }
+
+    #[sequence(loop, val, none)]
+    #[derive(Debug)]
+    enum Shape {
+        Dash,
+        Plus { arms: u8 },
+        Bar(u8, bool),
+    }
 
     #[test]
     fn addition() {
@@ -395,4 +436,54 @@ mod tests {
 
         assert_eq!(res, v.iter().accumulate_sum().collect::<Vec<i32>>());
     }
+
+    #[test]
+    fn sequences() {
+        assert_eq!(Dir::COUNT, 4);
+        assert_eq!(Dir::ALL, [Dir::Up, Dir::Right, Dir::Down, Dir::Left]);
+        assert_eq!(StateSequence::next(&Dir::Left), Dir::Up);
+        assert_eq!(Dir::Up.prev(), Dir::Left);
+        assert_eq!(Dir::Right.nth_next(7), Dir::Up);
+        assert_eq!(Dir::Right.nth_next(-6), Dir::Left);
+        assert_eq!(Dir::Right.nth_next(isize::MAX), Dir::Up);
+        assert_eq!(Dir::Right.nth_next(isize::MIN), Dir::Right);
+        assert_eq!((Dir::Down.index(), Dir::from_index(4)), (2, None));
+        let walk: Vec<_> = Dir::Left.take(5).collect();
+        assert_eq!(walk, [Dir::Up, Dir::Right, Dir::Down, Dir::Left, Dir::Up]);
+        assert_eq!(Dir::Up.size_hint(), (usize::MAX, None));
+
+        let mut s = Stage::Mid;
+        assert_eq!(StateSequenceMutate::next(&mut s), Stage::End);
+        assert_eq!(StateSequenceMutate::next(&mut s), Stage::End);
+        assert_eq!(Stage::Start.nth_next(10), Stage::End);
+        assert_eq!(Stage::End.prev().prev().prev(), Stage::Start);
+        assert_eq!(Stage::Mid.nth_next(isize::MAX), Stage::End);
+        assert_eq!(Stage::Mid.nth_next(isize::MIN), Stage::Start);
+
+        let mut s = Stage::Start;
+        let it = s.iter();
+        assert_eq!(it.len(), 3);
+        assert_eq!(it.collect::<Vec<_>>(), Stage::ALL);
+        let mut s = Stage::Mid;
+        assert_eq!(s.iter().len(), 2);
+
+        // `val` iterators step from what they last yielded:
+        let mut p = Phase::Waxing;
+        assert_eq!(p.iter().len(), 3);
+        assert_eq!(p.iter().count(), 3);
+        assert_eq!(p.iter().collect::<Vec<_>>(), Phase::ALL[1..]);
+        assert_eq!(p, Phase::Waxing);
+
+        // A loop of one never moves, so it's only yielded once:
+        let mut l = Lone::Only;
+        assert_eq!(l.iter().len(), 1);
+        assert_eq!(l.iter().collect::<Vec<_>>(), [Lone::Only]);
+
+        let shapes = Shape::all();
+        assert_eq!(shapes[1], Shape::Plus { arms: 0 });
+        assert_eq!(Shape::Plus { arms: 4 }.next(), Shape::Bar(0, false));
+        assert_eq!(Shape::Bar(3, true).nth_next(3), Shape::Bar(3, true));
+        assert_eq!(Shape::Bar(3, true).index(), 2);
+        assert_eq!(Shape::from_index(0), Some(Shape::Dash));
+    }
 }
@@ -0,0 +1,598 @@
+//! Hexagonal grid coordinates.
+//!
+//! Coordinates are stored in [axial] form (`q`, `r`); [`Cube`] coordinates
+//! (`q`, `r`, `s` with `q + r + s == 0`) are available for the cases where the
+//! symmetry is handy.
+//!
+//! Axial coordinates don't care about how the hexagons are drawn; only the
+//! names given to the six directions do. [`Pointy`] has `e`/`w` neighbours
+//! (2020/24) and [`Flat`] has `n`/`s` neighbours (2017/11). Both parse from
+//! the usual compass pair strings.
+//!
+//! [axial]: https://www.redblobgames.com/grids/hexagons/#coordinates-axial
+
+use std::collections::{hash_map::RandomState, HashMap, HashSet};
+use std::fmt::{self, Debug, Display};
+use std::hash::BuildHasher;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Axial {
+    pub q: isize,
+    pub r: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Cube {
+    pub q: isize,
+    pub r: isize,
+    pub s: isize,
+}
+
+/// The six unit offsets, going counter-clockwise (on a pointy-top layout,
+/// starting from east).
+///
+/// [`Axial::ring`] relies on this order.
+const UNIT: [Axial; 6] = [
+    Axial::new(1, 0),
+    Axial::new(1, -1),
+    Axial::new(0, -1),
+    Axial::new(-1, 0),
+    Axial::new(-1, 1),
+    Axial::new(0, 1),
+];
+
+impl Axial {
+    pub const ORIGIN: Self = Self::new(0, 0);
+
+    pub const fn new(q: isize, r: isize) -> Self {
+        Self { q, r }
+    }
+
+    pub const fn s(&self) -> isize {
+        -self.q - self.r
+    }
+
+    pub const fn cube(self) -> Cube {
+        Cube {
+            q: self.q,
+            r: self.r,
+            s: self.s(),
+        }
+    }
+
+    pub fn step<D: HexDir>(self, dir: D) -> Self {
+        self + dir.offset()
+    }
+
+    /// Follows every direction in `path`, starting from `self`.
+    pub fn walk<D: HexDir>(self, path: impl IntoIterator<Item = D>) -> Self {
+        path.into_iter().fold(self, Self::step)
+    }
+
+    pub fn neighbours(self) -> impl Iterator<Item = Self> + Clone {
+        UNIT.into_iter().map(move |d| self + d)
+    }
+
+    pub fn distance(self, other: Self) -> usize {
+        (other - self).norm()
+    }
+
+    /// Number of steps from the origin.
+    pub fn norm(self) -> usize {
+        (self.q.unsigned_abs() + self.r.unsigned_abs() + self.s().unsigned_abs()) / 2
+    }
+
+    pub fn is_origin(self) -> bool {
+        self == Self::ORIGIN
+    }
+
+    /// Rotates 60° about the origin; clockwise on a pointy-top layout.
+    pub fn rotate_cw(self) -> Self {
+        Self::new(-self.r, -self.s())
+    }
+
+    /// Rotates 60° about the origin; counter-clockwise on a pointy-top layout.
+    pub fn rotate_ccw(self) -> Self {
+        Self::new(-self.s(), -self.q)
+    }
+
+    /// All the cells exactly `radius` steps away from `self`.
+    ///
+    /// A radius of 0 yields just `self`; otherwise this yields `6 * radius`
+    /// cells.
+    pub fn ring(self, radius: usize) -> impl Iterator<Item = Self> + Clone {
+        let start = self + UNIT[4] * radius as isize;
+        let len = if radius == 0 { 1 } else { 6 * radius };
+
+        UNIT.into_iter()
+            .flat_map(move |d| (0..radius.max(1)).map(move |_| d))
+            .scan(start, |pos, d| {
+                let ret = *pos;
+                *pos += d;
+                Some(ret)
+            })
+            .take(len)
+    }
+
+    /// All the cells within `radius` steps of `self`, innermost ring first.
+    ///
+    /// Yields `1 + 3 * radius * (radius + 1)` cells.
+    pub fn spiral(self, radius: usize) -> impl Iterator<Item = Self> + Clone {
+        (0..=radius).flat_map(move |r| self.ring(r))
+    }
+}
+
+impl Cube {
+    pub const fn new(q: isize, r: isize, s: isize) -> Option<Self> {
+        if q + r + s == 0 {
+            Some(Self { q, r, s })
+        } else {
+            None
+        }
+    }
+
+    pub const fn axial(self) -> Axial {
+        Axial::new(self.q, self.r)
+    }
+
+    pub fn distance(self, other: Self) -> usize {
+        self.axial().distance(other.axial())
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(c: Cube) -> Self {
+        c.axial()
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(a: Axial) -> Self {
+        a.cube()
+    }
+}
+
+impl From<(isize, isize)> for Axial {
+    fn from((q, r): (isize, isize)) -> Self {
+        Self::new(q, r)
+    }
+}
+
+impl Display for Axial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.q, self.r)
+    }
+}
+
+impl Add for Axial {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.q + rhs.q, self.r + rhs.r)
+    }
+}
+
+impl AddAssign for Axial {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Axial {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.q - rhs.q, self.r - rhs.r)
+    }
+}
+
+impl SubAssign for Axial {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<isize> for Axial {
+    type Output = Self;
+
+    fn mul(self, rhs: isize) -> Self {
+        Self::new(self.q * rhs, self.r * rhs)
+    }
+}
+
+impl Neg for Axial {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.q, -self.r)
+    }
+}
+
+/// A set of directions for a particular hex layout.
+pub trait HexDir: Copy + Eq + Sized + 'static {
+    /// Going clockwise.
+    const ALL: [Self; 6];
+
+    fn offset(self) -> Axial;
+
+    /// The compass string for this direction (i.e. `"ne"`).
+    fn name(self) -> &'static str;
+
+    fn opposite(self) -> Self {
+        let idx = Self::ALL.iter().position(|d| *d == self).unwrap();
+        Self::ALL[(idx + 3) % 6]
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.name() == name)
+    }
+
+    /// Parses a run of compass strings with no separators (i.e. `"esenee"`).
+    ///
+    /// Two letter directions are tried before one letter ones.
+    fn parse_run(s: &str) -> Result<Vec<Self>, ParseDirError> {
+        let s = s.trim();
+        let mut out = Vec::with_capacity(s.len());
+        let mut pos = 0;
+
+        while pos < s.len() {
+            let two = s.get(pos..(pos + 2)).and_then(Self::from_name);
+            let one = s.get(pos..(pos + 1)).and_then(Self::from_name);
+
+            match (two, one) {
+                (Some(d), _) => {
+                    out.push(d);
+                    pos += 2;
+                }
+                (None, Some(d)) => {
+                    out.push(d);
+                    pos += 1;
+                }
+                (None, None) => return Err(ParseDirError::new(&s[pos..], pos)),
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Parses compass strings separated by `sep` (i.e. `"ne,ne,s,s"`).
+    fn parse_separated(s: &str, sep: &str) -> Result<Vec<Self>, ParseDirError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut pos = 0;
+        s.split(sep)
+            .map(|d| {
+                let ret = Self::from_name(d.trim()).ok_or_else(|| ParseDirError::new(d, pos));
+                pos += d.len() + sep.len();
+                ret
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirError {
+    pub got: String,
+    pub offset: usize,
+}
+
+impl ParseDirError {
+    fn new(got: &str, offset: usize) -> Self {
+        Self {
+            got: got.chars().take(2).collect(),
+            offset,
+        }
+    }
+}
+
+impl Display for ParseDirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid hex direction `{}` at offset {}",
+            self.got, self.offset
+        )
+    }
+}
+
+impl std::error::Error for ParseDirError {}
+
+macro_rules! hex_dirs {
+    ($(#[$m:meta])* $nom:ident { $($var:ident = $name:literal => ($q:literal, $r:literal),)* }) => {
+        $(#[$m])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum $nom {
+            $($var,)*
+        }
+
+        impl HexDir for $nom {
+            const ALL: [Self; 6] = [$(Self::$var,)*];
+
+            fn offset(self) -> Axial {
+                match self {
+                    $(Self::$var => Axial::new($q, $r),)*
+                }
+            }
+
+            fn name(self) -> &'static str {
+                match self {
+                    $(Self::$var => $name,)*
+                }
+            }
+        }
+
+        impl FromStr for $nom {
+            type Err = ParseDirError;
+
+            fn from_str(s: &str) -> Result<Self, ParseDirError> {
+                Self::from_name(s.trim()).ok_or_else(|| ParseDirError::new(s, 0))
+            }
+        }
+
+        impl Display for $nom {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    };
+}
+
+hex_dirs! {
+    /// Directions on a grid of pointy-top hexagons (rows are horizontal).
+    Pointy {
+        East = "e" => (1, 0),
+        Southeast = "se" => (0, 1),
+        Southwest = "sw" => (-1, 1),
+        West = "w" => (-1, 0),
+        Northwest = "nw" => (0, -1),
+        Northeast = "ne" => (1, -1),
+    }
+}
+
+hex_dirs! {
+    /// Directions on a grid of flat-top hexagons (columns are vertical).
+    Flat {
+        North = "n" => (0, -1),
+        Northeast = "ne" => (1, -1),
+        Southeast = "se" => (1, 0),
+        South = "s" => (0, 1),
+        Southwest = "sw" => (-1, 1),
+        Northwest = "nw" => (-1, 0),
+    }
+}
+
+/// A sparse set of "live" hex cells; i.e. the black tiles in 2020/24.
+#[derive(Debug, Clone, Default)]
+pub struct HexSet<S = RandomState> {
+    cells: HashSet<Axial, S>,
+}
+
+impl<S: BuildHasher + Default> HexSet<S> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashSet::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, c: Axial) -> bool {
+        self.cells.contains(&c)
+    }
+
+    /// Returns `true` if the cell was not already live.
+    pub fn insert(&mut self, c: Axial) -> bool {
+        self.cells.insert(c)
+    }
+
+    /// Returns `true` if the cell was live.
+    pub fn remove(&mut self, c: Axial) -> bool {
+        self.cells.remove(&c)
+    }
+
+    /// Flips the cell; returns whether it is now live.
+    pub fn toggle(&mut self, c: Axial) -> bool {
+        if self.cells.remove(&c) {
+            false
+        } else {
+            self.cells.insert(c);
+            true
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Axial> + '_ {
+        self.cells.iter().copied()
+    }
+
+    pub fn live_neighbours(&self, c: Axial) -> usize {
+        c.neighbours().filter(|n| self.contains(*n)).count()
+    }
+
+    /// Advances one generation.
+    ///
+    /// `rule` is given whether the cell is currently live and how many of its
+    /// neighbours are and returns whether the cell should be live in the next
+    /// generation. Cells with no live neighbours that aren't live themselves
+    /// are never considered.
+    pub fn step(&mut self, rule: impl Fn(bool, usize) -> bool) {
+        let mut counts: HashMap<Axial, usize, S> = HashMap::default();
+        counts.reserve(self.len() * 7);
+
+        for c in self.cells.iter() {
+            counts.entry(*c).or_default();
+            for n in c.neighbours() {
+                *counts.entry(n).or_default() += 1;
+            }
+        }
+
+        self.cells = counts
+            .into_iter()
+            .filter(|(c, n)| rule(self.cells.contains(c), *n))
+            .map(|(c, _)| c)
+            .collect();
+    }
+
+    pub fn bounds(&self) -> Option<(Axial, Axial)> {
+        let mut it = self.iter();
+        let first = it.next()?;
+
+        Some(it.fold((first, first), |(lo, hi), c| {
+            (
+                Axial::new(lo.q.min(c.q), lo.r.min(c.r)),
+                Axial::new(hi.q.max(c.q), hi.r.max(c.r)),
+            )
+        }))
+    }
+}
+
+impl<S: BuildHasher + Default> FromIterator<Axial> for HexSet<S> {
+    fn from_iter<I: IntoIterator<Item = Axial>>(iter: I) -> Self {
+        Self {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<S: BuildHasher + Default> Extend<Axial> for HexSet<S> {
+    fn extend<I: IntoIterator<Item = Axial>>(&mut self, iter: I) {
+        self.cells.extend(iter)
+    }
+}
+
+impl<S> IntoIterator for HexSet<S> {
+    type Item = Axial;
+    type IntoIter = std::collections::hash_set::IntoIter<Axial>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.cells.into_iter()
+    }
+}
+
+impl<S: BuildHasher> PartialEq for HexSet<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+}
+
+impl<S: BuildHasher> Eq for HexSet<S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_run() {
+        use Pointy::*;
+
+        assert_eq!(
+            Pointy::parse_run("esenee"),
+            Ok(vec![East, Southeast, Northeast, East])
+        );
+        assert_eq!(
+            Pointy::parse_run("nwwswee").map(|p| Axial::ORIGIN.walk(p)),
+            Ok(Axial::ORIGIN)
+        );
+        assert_eq!(
+            Pointy::parse_run("eesx"),
+            Err(ParseDirError {
+                got: "sx".into(),
+                offset: 2
+            })
+        );
+    }
+
+    #[test]
+    fn flat_distances() {
+        let dist = |s| {
+            Axial::ORIGIN
+                .walk(Flat::parse_separated(s, ",").unwrap())
+                .norm()
+        };
+
+        assert_eq!(dist("ne,ne,ne"), 3);
+        assert_eq!(dist("ne,ne,sw,sw"), 0);
+        assert_eq!(dist("ne,ne,s,s"), 2);
+        assert_eq!(dist("se,sw,se,sw,sw"), 3);
+    }
+
+    #[test]
+    fn opposites_cancel() {
+        for d in Pointy::ALL {
+            assert!(Axial::ORIGIN.step(d).step(d.opposite()).is_origin());
+        }
+        for d in Flat::ALL {
+            assert!(Axial::ORIGIN.step(d).step(d.opposite()).is_origin());
+        }
+    }
+
+    #[test]
+    fn rings_and_spirals() {
+        let c = Axial::new(3, -7);
+        assert_eq!(c.ring(0).collect::<Vec<_>>(), vec![c]);
+
+        for radius in 1..5 {
+            let ring: HashSet<_> = c.ring(radius).collect();
+            assert_eq!(ring.len(), 6 * radius);
+            assert!(ring.iter().all(|p| p.distance(c) == radius));
+        }
+
+        let spiral: HashSet<_> = c.spiral(4).collect();
+        assert_eq!(spiral.len(), 1 + 3 * 4 * 5);
+    }
+
+    #[test]
+    fn rotation() {
+        let a = Axial::new(2, -1);
+        assert_eq!(a.rotate_cw().rotate_ccw(), a);
+        assert_eq!((0..6).fold(a, |a, _| a.rotate_cw()), a);
+        assert_eq!(a.rotate_cw().norm(), a.norm());
+    }
+
+    #[test]
+    fn lobby_layout_example() {
+        let input = "sesenwnenenewseeswwswswwnenewsewsw
+neeenesenwnwwswnenewnwwsewnenwseswesw
+seswneswswsenwwnwse
+nwnwneseeswswnenewneswwnewseswneseene
+swweswneswnenwsewnwneneseenw
+eesenwseswswnenwswnwnwsewwnwsene
+sewnenenenesenwsewnenwwwse
+wenwwweseeeweswwwnwwe
+wsweesenenewnwwnwsenewsenwwsesesenwne
+neeswseenwwswnwswswnw
+nenwswwsewswnenenewsenwsenwnesesenew
+enewnwewneswsewnwswenweswnenwsenwsw
+sweneswneswneneenwnewenewwneswswnese
+swwesenesewenwneswnwwneseswwne
+enesenwswwswneneswsenwnewswseenwsese
+wnwnesenesenenwwnenwsewesewsesesew
+nenewswnwewswnenesenwnesewesw
+eneswnwswnwsenenwnwnwwseeswneewsenese
+neswnwewnwnwseenwseesewsenwsweewe
+wseweeenwnesenwwwswnew";
+
+        let mut tiles: HexSet = HexSet::new();
+        for l in input.lines() {
+            tiles.toggle(Axial::ORIGIN.walk(Pointy::parse_run(l).unwrap()));
+        }
+        assert_eq!(tiles.len(), 10);
+
+        let rule = |live, n| matches!((live, n), (true, 1 | 2) | (false, 2));
+        tiles.step(rule);
+        assert_eq!(tiles.len(), 15);
+
+        (2..=100).for_each(|_| tiles.step(rule));
+        assert_eq!(tiles.len(), 2208);
+    }
+}
@@ -0,0 +1,588 @@
+//! Sets of half-open intervals and boxes.
+//!
+//! Intervals are plain [`Range`]s (`start..end`, end exclusive). For inclusive
+//! puzzle ranges (`1-3` in 2022/04) use the `_inclusive` helpers which need
+//! `T: One`.
+
+use std::fmt::{self, Debug};
+use std::ops::{Add, Mul, Range, RangeInclusive, Sub};
+
+use num_traits::{One, Zero};
+
+/// The overlap of two ranges; `None` if they don't overlap.
+pub fn intersect<T: Copy + Ord>(a: &Range<T>, b: &Range<T>) -> Option<Range<T>> {
+    let r = a.start.max(b.start)..a.end.min(b.end);
+    (!r.is_empty()).then_some(r)
+}
+
+/// `(left, overlap, right)`; see [`split`].
+pub type Split<T> = (Option<Range<T>>, Option<Range<T>>, Option<Range<T>>);
+
+/// Splits `b` into the parts that are left of `a`, overlap with `a`, and are
+/// right of `a`.
+///
+/// ```text
+/// a:      ┌──────────┐
+/// b: └───────────────────┘
+///    └─L─┘└────O─────┘└─R┘
+/// ```
+pub fn split<T: Copy + Ord>(a: &Range<T>, b: &Range<T>) -> Split<T> {
+    let f = |r: Range<T>| (!r.is_empty()).then_some(r);
+
+    (
+        f(b.start..a.start.min(b.end)),
+        f(a.start.max(b.start)..a.end.min(b.end)),
+        f(a.end.max(b.start)..b.end),
+    )
+}
+
+pub fn inclusive<T: Copy + Add<Output = T> + One>(r: RangeInclusive<T>) -> Range<T> {
+    *r.start()..(*r.end() + T::one())
+}
+
+/// A set of values represented as sorted, disjoint, non-adjacent ranges.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Debug> Debug for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.ranges.iter()).finish()
+    }
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: vec![] }
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The smallest range containing every value in the set.
+    pub fn span(&self) -> Option<Range<T>> {
+        Some(self.ranges.first()?.start..self.ranges.last()?.end)
+    }
+
+    pub fn contains(&self, x: &T) -> bool {
+        // Index of the first range that ends after `x`:
+        let idx = self.ranges.partition_point(|r| r.end <= *x);
+        self.ranges.get(idx).map(|r| r.contains(x)).unwrap_or(false)
+    }
+
+    /// `true` if every value in `r` is in the set.
+    pub fn covers(&self, r: &Range<T>) -> bool {
+        if r.is_empty() {
+            return true;
+        }
+
+        let idx = self.ranges.partition_point(|s| s.end <= r.start);
+        self.ranges
+            .get(idx)
+            .map(|s| s.start <= r.start && r.end <= s.end)
+            .unwrap_or(false)
+    }
+
+    pub fn overlaps(&self, r: &Range<T>) -> bool {
+        let idx = self.ranges.partition_point(|s| s.end <= r.start);
+        self.ranges
+            .get(idx)
+            .map(|s| intersect(s, r).is_some())
+            .unwrap_or(false)
+    }
+
+    pub fn insert(&mut self, r: Range<T>) {
+        if r.is_empty() {
+            return;
+        }
+
+        // Every range that touches `r` (including ranges that are just
+        // adjacent) gets merged into it.
+        let lo = self.ranges.partition_point(|s| s.end < r.start);
+        let hi = self.ranges.partition_point(|s| s.start <= r.end);
+
+        let merged = if lo < hi {
+            r.start.min(self.ranges[lo].start)..r.end.max(self.ranges[hi - 1].end)
+        } else {
+            r
+        };
+
+        self.ranges.splice(lo..hi, [merged]);
+    }
+
+    pub fn remove(&mut self, r: Range<T>) {
+        if r.is_empty() {
+            return;
+        }
+
+        let lo = self.ranges.partition_point(|s| s.end <= r.start);
+        let hi = self.ranges.partition_point(|s| s.start < r.end);
+        if lo >= hi {
+            return;
+        }
+
+        let first = self.ranges[lo].clone();
+        let last = self.ranges[hi - 1].clone();
+
+        let left = (first.start < r.start).then_some(first.start..r.start);
+        let right = (r.end < last.end).then_some(r.end..last.end);
+
+        self.ranges.splice(lo..hi, left.into_iter().chain(right));
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut out = self.clone();
+        out.extend(other.ranges.iter().cloned());
+        out
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        let mut ranges = vec![];
+
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            if let Some(o) = intersect(x, y) {
+                ranges.push(o);
+            }
+
+            if x.end < y.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+
+        // Intersections of normalised sets can't be adjacent; each output range
+        // is bounded by a gap in one of the inputs.
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut out = self.clone();
+        for r in other.ranges.iter() {
+            out.remove(r.clone());
+        }
+
+        out
+    }
+
+    /// Everything in `within` that's not in the set.
+    pub fn complement(&self, within: Range<T>) -> Self {
+        let mut out: Self = [within].into_iter().collect();
+        for r in self.ranges.iter() {
+            out.remove(r.clone());
+        }
+
+        out
+    }
+
+    /// The gaps between ranges in the set.
+    pub fn gaps(&self) -> impl Iterator<Item = Range<T>> + '_ {
+        self.ranges.windows(2).map(|w| w[0].end..w[1].start)
+    }
+}
+
+impl<T: Copy + Ord + Zero + Sub<Output = T>> IntervalSet<T> {
+    /// The number of values in the set.
+    pub fn coverage(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::zero(), |acc, r| acc + (r.end - r.start))
+    }
+}
+
+impl<T: Copy + Ord + One + Add<Output = T>> IntervalSet<T> {
+    pub fn insert_inclusive(&mut self, r: RangeInclusive<T>) {
+        self.insert(inclusive(r))
+    }
+
+    pub fn remove_inclusive(&mut self, r: RangeInclusive<T>) {
+        self.remove(inclusive(r))
+    }
+}
+
+impl<T: Copy + Ord> Extend<Range<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|r| self.insert(r))
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut ranges: Vec<_> = iter.into_iter().filter(|r| !r.is_empty()).collect();
+        ranges.sort_unstable_by_key(|r| r.start);
+
+        let mut out: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+        for r in ranges {
+            match out.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => out.push(r),
+            }
+        }
+
+        Self { ranges: out }
+    }
+}
+
+impl<T> IntoIterator for IntervalSet<T> {
+    type Item = Range<T>;
+    type IntoIter = std::vec::IntoIter<Range<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranges.into_iter()
+    }
+}
+
+/// A piecewise map that shifts each source range to a destination; values
+/// outside all of the source ranges map to themselves.
+///
+/// This is the "almanac" map from 2023/05.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct OffsetMap<T> {
+    // (source range, destination start); sorted by source start
+    pieces: Vec<(Range<T>, T)>,
+}
+
+impl<T> OffsetMap<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T>,
+{
+    pub fn new() -> Self {
+        Self { pieces: vec![] }
+    }
+
+    /// Returns `Err` with the offending source range if it overlaps one that's
+    /// already in the map.
+    pub fn insert(&mut self, src: Range<T>, dest_start: T) -> Result<(), Range<T>> {
+        if src.is_empty() {
+            return Ok(());
+        }
+
+        let idx = self.pieces.partition_point(|(s, _)| s.start < src.start);
+        let overlaps = |i: usize| {
+            self.pieces
+                .get(i)
+                .map(|(s, _)| intersect(s, &src).is_some())
+                .unwrap_or(false)
+        };
+        if overlaps(idx) || (idx > 0 && overlaps(idx - 1)) {
+            return Err(src);
+        }
+
+        self.pieces.insert(idx, (src, dest_start));
+        Ok(())
+    }
+
+    pub fn map(&self, x: T) -> T {
+        let idx = self.pieces.partition_point(|(s, _)| s.end <= x);
+        match self.pieces.get(idx) {
+            Some((src, dest)) if src.contains(&x) => *dest + (x - src.start),
+            _ => x,
+        }
+    }
+
+    /// Maps a range through the map, splitting it at piece boundaries.
+    ///
+    /// Pieces come out in source order (not necessarily sorted).
+    pub fn map_range(&self, r: Range<T>) -> Vec<Range<T>> {
+        let first = self.pieces.partition_point(|(s, _)| s.end <= r.start);
+
+        let mut out = vec![];
+        let mut rest = Some(r);
+        for (src, dest) in &self.pieces[first..] {
+            let Some(r) = rest.take() else { break };
+            let (left, overlap, right) = split(src, &r);
+
+            out.extend(left);
+            if let Some(o) = overlap {
+                out.push((*dest + (o.start - src.start))..(*dest + (o.end - src.start)));
+            }
+            rest = right;
+        }
+        out.extend(rest);
+
+        out
+    }
+
+    pub fn map_set(&self, set: &IntervalSet<T>) -> IntervalSet<T> {
+        set.ranges
+            .iter()
+            .flat_map(|r| self.map_range(r.clone()))
+            .collect()
+    }
+}
+
+impl<T> FromIterator<(Range<T>, T)> for OffsetMap<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T> + Debug,
+{
+    /// Panics if any of the source ranges overlap.
+    fn from_iter<I: IntoIterator<Item = (Range<T>, T)>>(iter: I) -> Self {
+        let mut m = Self::new();
+        for (src, dest) in iter {
+            m.insert(src, dest)
+                .unwrap_or_else(|r| panic!("overlapping source range: {:?}", r));
+        }
+
+        m
+    }
+}
+
+/// An axis aligned box in `N` dimensions; half-open on every axis.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cuboid<T, const N: usize>(pub [Range<T>; N]);
+
+impl<T: Copy + Ord, const N: usize> Cuboid<T, N> {
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().any(|r| r.is_empty())
+    }
+
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let mut out = self.clone();
+        for (o, r) in out.0.iter_mut().zip(other.0.iter()) {
+            *o = intersect(o, r)?;
+        }
+
+        Some(out)
+    }
+
+    pub fn contains(&self, p: &[T; N]) -> bool {
+        self.0.iter().zip(p).all(|(r, x)| r.contains(x))
+    }
+
+    /// The parts of `self` that are not in `other`; at most `2 * N` disjoint
+    /// boxes.
+    pub fn subtract(&self, other: &Self) -> Vec<Self> {
+        let Some(overlap) = self.intersect(other) else {
+            return vec![self.clone()];
+        };
+
+        // Peel off the slabs on either side of the overlap one axis at a
+        // time, shrinking what's left to the overlap on that axis as we go.
+        let mut out = vec![];
+        let mut rest = self.clone();
+        for d in 0..N {
+            let (left, _, right) = split(&overlap.0[d], &rest.0[d]);
+            for slab in [left, right].into_iter().flatten() {
+                let mut piece = rest.clone();
+                piece.0[d] = slab;
+                out.push(piece);
+            }
+
+            rest.0[d] = overlap.0[d].clone();
+        }
+
+        out
+    }
+}
+
+impl<T, const N: usize> Cuboid<T, N>
+where
+    T: Copy + Ord + Zero + One + Sub<Output = T> + Mul<Output = T>,
+{
+    pub fn volume(&self) -> T {
+        if self.is_empty() {
+            T::zero()
+        } else {
+            self.0
+                .iter()
+                .fold(T::one(), |acc, r| acc * (r.end - r.start))
+        }
+    }
+}
+
+/// A union of [`Cuboid`]s, kept as a list of disjoint boxes so that toggling
+/// regions on and off (2021/22) is cheap to count.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CuboidSet<T, const N: usize> {
+    boxes: Vec<Cuboid<T, N>>,
+}
+
+impl<T: Copy + Ord, const N: usize> CuboidSet<T, N> {
+    pub fn new() -> Self {
+        Self { boxes: vec![] }
+    }
+
+    /// The disjoint boxes that make up the set.
+    pub fn boxes(&self) -> &[Cuboid<T, N>] {
+        &self.boxes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    pub fn contains(&self, p: &[T; N]) -> bool {
+        self.boxes.iter().any(|b| b.contains(p))
+    }
+
+    pub fn insert(&mut self, c: Cuboid<T, N>) {
+        if c.is_empty() {
+            return;
+        }
+
+        self.remove(&c);
+        self.boxes.push(c);
+    }
+
+    pub fn remove(&mut self, c: &Cuboid<T, N>) {
+        self.boxes = self.boxes.iter().flat_map(|b| b.subtract(c)).collect();
+    }
+
+    pub fn set(&mut self, c: Cuboid<T, N>, on: bool) {
+        if on {
+            self.insert(c)
+        } else {
+            self.remove(&c)
+        }
+    }
+}
+
+impl<T, const N: usize> CuboidSet<T, N>
+where
+    T: Copy + Ord + Zero + One + Sub<Output = T> + Mul<Output = T>,
+{
+    pub fn volume(&self) -> T {
+        self.boxes.iter().fold(T::zero(), |acc, b| acc + b.volume())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(rs: &[Range<i32>]) -> IntervalSet<i32> {
+        rs.iter().cloned().collect()
+    }
+
+    #[test]
+    fn split_components() {
+        use Option::{None as N, Some as S};
+        let a = 10..20;
+
+        assert_eq!(split(&a, &(0..5)), (S(0..5), N, N));
+        assert_eq!(split(&a, &(0..15)), (S(0..10), S(10..15), N));
+        assert_eq!(split(&a, &(0..25)), (S(0..10), S(10..20), S(20..25)));
+        assert_eq!(split(&a, &(12..15)), (N, S(12..15), N));
+        assert_eq!(split(&a, &(12..25)), (N, S(12..20), S(20..25)));
+        assert_eq!(split(&a, &(22..25)), (N, N, S(22..25)));
+    }
+
+    #[test]
+    fn normalisation() {
+        let s = set(&[5..7, 0..2, 2..3, 6..9, 4..4]);
+        assert_eq!(s.ranges(), &[0..3, 5..9]);
+        assert_eq!(s.coverage(), 7);
+        assert_eq!(s.gaps().collect::<Vec<_>>(), vec![3..5]);
+
+        let mut s = s;
+        s.insert(3..5);
+        assert_eq!(s.span(), Some(0..9));
+        assert_eq!(s.ranges().len(), 1);
+        s.remove(2..4);
+        assert_eq!(s.ranges(), &[0..2, 4..9]);
+        s.remove(-5..1);
+        s.remove(8..100);
+        assert_eq!(s.ranges(), &[1..2, 4..8]);
+
+        assert!(s.contains(&1) && !s.contains(&2) && s.contains(&7));
+        assert!(s.covers(&(4..8)) && !s.covers(&(1..5)));
+        assert!(s.overlaps(&(0..5)) && !s.overlaps(&(2..4)));
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[0..10, 20..30]);
+        let b = set(&[5..25, 40..50]);
+
+        assert_eq!(a.union(&b).ranges(), &[0..30, 40..50]);
+        assert_eq!(a.intersection(&b).ranges(), &[5..10, 20..25]);
+        assert_eq!(a.difference(&b).ranges(), &[0..5, 25..30]);
+        assert_eq!(b.difference(&a).ranges(), &[10..20, 40..50]);
+        assert_eq!(a.complement(-5..35).ranges(), &[-5..0, 10..20, 30..35]);
+    }
+
+    #[test]
+    fn inclusive_ranges() {
+        // 2022/04: `2-8` fully contains `3-7`
+        let mut s = IntervalSet::new();
+        s.insert_inclusive(2..=8);
+        assert!(s.covers(&inclusive(3..=7)));
+        assert_eq!(s.coverage(), 7);
+    }
+
+    #[test]
+    fn almanac() {
+        let maps: Vec<OffsetMap<u64>> = [
+            &[(98, 50, 2), (50, 52, 48)][..],
+            &[(15, 0, 37), (52, 37, 2), (0, 39, 15)],
+            &[(53, 49, 8), (11, 0, 42), (0, 42, 7), (7, 57, 4)],
+            &[(18, 88, 7), (25, 18, 70)],
+            &[(77, 45, 23), (45, 81, 19), (64, 68, 13)],
+            &[(69, 0, 1), (0, 1, 69)],
+            &[(56, 60, 37), (93, 56, 4)],
+        ]
+        .iter()
+        .map(|m| m.iter().map(|&(s, d, l)| (s..(s + l), d)).collect())
+        .collect();
+
+        let lowest = |seeds: IntervalSet<u64>| {
+            maps.iter()
+                .fold(seeds, |s, m| m.map_set(&s))
+                .span()
+                .unwrap()
+                .start
+        };
+
+        let seeds = [79, 14, 55, 13];
+        let p1 = seeds.iter().map(|&s| maps.iter().fold(s, |s, m| m.map(s)));
+        assert_eq!(p1.min(), Some(35));
+        assert_eq!(lowest(seeds.iter().map(|&s| s..(s + 1)).collect()), 35);
+        assert_eq!(lowest([79..93, 55..68].into_iter().collect()), 46);
+    }
+
+    #[test]
+    fn overlapping_offset_maps_are_rejected() {
+        let mut m = OffsetMap::new();
+        assert_eq!(m.insert(10..20, 0), Ok(()));
+        assert_eq!(m.insert(0..10, 100), Ok(()));
+        assert_eq!(m.insert(15..25, 0), Err(15..25));
+        assert_eq!(m.insert(5..6, 0), Err(5..6));
+        assert_eq!(m.map_range(5..25), vec![105..110, 0..10, 20..25]);
+    }
+
+    #[test]
+    fn reactor_reboot() {
+        let steps = [
+            (true, [10..=12, 10..=12, 10..=12]),
+            (true, [11..=13, 11..=13, 11..=13]),
+            (false, [9..=11, 9..=11, 9..=11]),
+            (true, [10..=10, 10..=10, 10..=10]),
+        ];
+
+        let mut reactor = CuboidSet::new();
+        for (on, c) in steps {
+            reactor.set(Cuboid(c.map(inclusive::<i64>)), on);
+        }
+
+        assert_eq!(reactor.volume(), 39);
+        assert!(reactor.contains(&[10, 10, 10]));
+        assert!(!reactor.contains(&[11, 11, 11]));
+
+        let boxes = reactor.boxes();
+        for (i, a) in boxes.iter().enumerate() {
+            for b in &boxes[(i + 1)..] {
+                assert_eq!(a.intersect(b), None);
+            }
+        }
+    }
+}
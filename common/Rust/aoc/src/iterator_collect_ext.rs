@@ -145,7 +145,132 @@ macro_rules! tuple_impl {
 }
 
 // trace_macros!(true);
-tuple_impl!((A B C D E F));
+tuple_impl!((A B C D E F G H J K L M));
+
+/// Pulls the next `N` elements out of `it`; if there aren't enough, the ones
+/// that were there.
+fn next_chunk<I: Iterator, const N: usize>(it: &mut I) -> Result<[I::Item; N], Vec<I::Item>> {
+    let elems = [(); N].map(|()| it.next());
+    if elems.iter().all(Option::is_some) {
+        Ok(elems.map(Option::unwrap))
+    } else {
+        Err(elems.into_iter().flatten().collect())
+    }
+}
+
+/// See [`IterCollectExt::array_windows`].
+#[derive(Debug, Clone)]
+pub struct ArrayWindows<I: Iterator, const N: usize> {
+    it: I,
+    window: Option<[I::Item; N]>,
+}
+
+impl<I: Iterator, const N: usize> Iterator for ArrayWindows<I, N>
+where
+    I::Item: Clone,
+{
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.window {
+            None => self.window = Some(next_chunk(&mut self.it).ok()?),
+            Some(w) => {
+                let next = self.it.next()?;
+                w.rotate_left(1);
+                w[N - 1] = next;
+            }
+        }
+
+        self.window.clone()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.it.size_hint();
+        match self.window {
+            Some(_) => (lo, hi),
+            // (`N` isn't 0; see `array_windows`)
+            None => (
+                lo.saturating_sub(N - 1),
+                hi.map(|h| h.saturating_sub(N - 1)),
+            ),
+        }
+    }
+}
+
+/// See [`IterCollectExt::array_chunks`].
+#[derive(Debug, Clone)]
+pub struct ArrayChunks<I: Iterator, const N: usize> {
+    it: I,
+    remainder: Option<Vec<I::Item>>,
+}
+
+impl<I: Iterator, const N: usize> ArrayChunks<I, N> {
+    /// The elements left over at the end that didn't make up a full chunk.
+    ///
+    /// `None` until the iterator has been exhausted.
+    pub fn remainder(&self) -> Option<&[I::Item]> {
+        self.remainder.as_deref()
+    }
+
+    pub fn into_remainder(self) -> Option<Vec<I::Item>> {
+        self.remainder
+    }
+}
+
+impl<I: Iterator, const N: usize> Iterator for ArrayChunks<I, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remainder.is_some() {
+            return None;
+        }
+
+        next_chunk(&mut self.it)
+            .map_err(|rem| self.remainder = Some(rem))
+            .ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.remainder.is_some() {
+            return (0, Some(0));
+        }
+
+        let (lo, hi) = self.it.size_hint();
+        (lo / N, hi.map(|h| h / N))
+    }
+}
+
+/// See [`IterCollectExt::array_combinations`].
+#[derive(Debug, Clone)]
+pub struct ArrayCombinations<T, const N: usize> {
+    pool: Vec<T>,
+    /// `None` once we're done.
+    indices: Option<[usize; N]>,
+}
+
+impl<T: Clone, const N: usize> Iterator for ArrayCombinations<T, N> {
+    type Item = [T; N];
+
+    fn next(&mut self) -> Option<[T; N]> {
+        let idxs = self.indices.as_mut()?;
+        let out = idxs.map(|i| self.pool[i].clone());
+
+        // Find the rightmost index that can still be bumped and reset
+        // everything after it to follow on from it:
+        let len = self.pool.len();
+        match (0..N).rev().find(|&i| idxs[i] != i + len - N) {
+            Some(i) => {
+                idxs[i] += 1;
+                for j in i + 1..N {
+                    idxs[j] = idxs[j - 1] + 1;
+                }
+            }
+            None => self.indices = None,
+        }
+
+        Some(out)
+    }
+}
 
 pub trait IterCollectExt: Iterator + Sized {
     fn reshape<C: Collectible<Self>>(self) -> C::Out {
@@ -162,6 +287,115 @@ pub trait IterCollectExt: Iterator + Sized {
     {
         self.reshape::<Tuple<N>>()
     }
+
+    /// Overlapping windows of `N` consecutive elements: `[a, b, c, d]` gives
+    /// `[a, b, c]` and `[b, c, d]` for `N = 3`.
+    ///
+    /// Panics if `N` is 0.
+    fn array_windows<const N: usize>(self) -> ArrayWindows<Self, N>
+    where
+        Self::Item: Clone,
+    {
+        assert!(N != 0, "windows must have at least one element");
+        ArrayWindows {
+            it: self,
+            window: None,
+        }
+    }
+
+    /// Non-overlapping chunks of `N` elements; anything left over at the end
+    /// is available from [`ArrayChunks::remainder`].
+    ///
+    /// Shares its name with an unstable std method; callers may need
+    /// `#[allow(unstable_name_collisions)]`.
+    ///
+    /// Panics if `N` is 0.
+    fn array_chunks<const N: usize>(self) -> ArrayChunks<Self, N> {
+        assert!(N != 0, "chunks must have at least one element");
+        ArrayChunks {
+            it: self,
+            remainder: None,
+        }
+    }
+
+    /// Every way to pick `N` elements (in their original order), in
+    /// lexicographic order of position.
+    fn array_combinations<const N: usize>(self) -> ArrayCombinations<Self::Item, N>
+    where
+        Self::Item: Clone,
+    {
+        let pool: Vec<_> = self.collect();
+        let indices = (N <= pool.len()).then(|| std::array::from_fn(|i| i));
+
+        ArrayCombinations { pool, indices }
+    }
 }
 
 impl<I: Iterator + Sized> IterCollectExt for I {}
+
+#[cfg(test)]
+#[allow(unstable_name_collisions)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_and_chunks() {
+        let w: Vec<_> = "abcd".chars().array_windows::<3>().collect();
+        assert_eq!(w, [['a', 'b', 'c'], ['b', 'c', 'd']]);
+        assert_eq!((0..2).array_windows::<3>().count(), 0);
+        assert_eq!((0..10).array_windows::<4>().size_hint(), (7, Some(7)));
+
+        // Infinite iterators report `usize::MAX`:
+        let w = (0..).array_windows::<2>();
+        assert_eq!(w.size_hint(), (usize::MAX - 1, None));
+        let w: Vec<_> = [1, 2]
+            .into_iter()
+            .cycle()
+            .array_windows::<3>()
+            .take(2)
+            .collect();
+        assert_eq!(w, [[1, 2, 1], [2, 1, 2]]);
+        assert_eq!(
+            std::iter::repeat(0).array_windows::<1>().size_hint().0,
+            usize::MAX
+        );
+
+        let start = "mjqjpqmgbljsphdztnvjfqwrcgsmlb"
+            .chars()
+            .array_windows::<4>()
+            .position(|w| (1..4).all(|i| !w[..i].contains(&w[i])));
+        assert_eq!(start.map(|i| i + 4), Some(7));
+
+        let mut c = (1..=8).array_chunks::<3>();
+        assert_eq!(c.remainder(), None);
+        assert_eq!(c.by_ref().collect::<Vec<_>>(), [[1, 2, 3], [4, 5, 6]]);
+        assert_eq!(c.remainder(), Some(&[7, 8][..]));
+        assert_eq!(c.next(), None);
+
+        let mut c = (1..=4).array_chunks::<2>();
+        assert_eq!(c.by_ref().count(), 2);
+        assert_eq!(c.into_remainder(), Some(vec![]));
+    }
+
+    #[test]
+    fn combinations() {
+        let c: Vec<_> = (1..=4).array_combinations::<2>().collect();
+        assert_eq!(c, [[1, 2], [1, 3], [1, 4], [2, 3], [2, 4], [3, 4]]);
+
+        assert_eq!((0..10).array_combinations::<3>().count(), 120);
+        assert_eq!((0..2).array_combinations::<3>().count(), 0);
+        assert_eq!((0..3).array_combinations::<0>().count(), 1);
+
+        let triple = [1721, 979, 366, 299, 675, 1456]
+            .into_iter()
+            .array_combinations::<3>()
+            .find(|t| t.iter().sum::<u32>() == 2020);
+        assert_eq!(triple, Some([979, 366, 675]));
+    }
+
+    #[test]
+    fn big_tuples() {
+        let t = (0..12).tuple::<12>();
+        assert_eq!((t.0, t.11), (0, 11));
+    }
+}
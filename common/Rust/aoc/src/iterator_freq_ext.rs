@@ -1,14 +1,28 @@
 use std::{
-    collections::{hash_map::RandomState, HashMap},
+    collections::hash_map::RandomState,
     hash::{BuildHasher, Hash},
 };
 
 use itertools::Itertools;
 
+use crate::counter::{ArrayCounter, Counter, SmallKey};
+
 pub trait IterFreqExt: Iterator + Sized
 where
     Self::Item: Eq + Hash,
 {
+    fn counter(self) -> Counter<Self::Item> {
+        self.collect()
+    }
+
+    /// See [`ArrayCounter`]; panics on items outside the alphabet.
+    fn array_counter(self) -> ArrayCounter<Self::Item>
+    where
+        Self::Item: SmallKey,
+    {
+        self.collect()
+    }
+
     fn most_common(self) -> Option<Self::Item> {
         self.most_common_with_hash_state::<RandomState>()
     }
@@ -55,12 +69,9 @@ where
     I::Item: Eq + Hash,
     HashState: BuildHasher + Default,
 {
-    let mut hm: HashMap<I::Item, usize, HashState> = HashMap::default();
-    // TODO: trusted len reserve optimization
-
-    for e in it {
-        *hm.entry(e).or_default() += 1;
-    }
-
-    hm.into_iter().sorted_by_key(|(_, count)| *count).collect()
+    let counts: Counter<I::Item, HashState> = it.collect();
+    counts
+        .into_iter()
+        .sorted_by_key(|(_, count)| *count)
+        .collect()
 }
@@ -1,4 +1,10 @@
-use std::{fmt::Debug, iter::Map, marker::PhantomData, str::FromStr};
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display},
+    iter::Map,
+    marker::PhantomData,
+    str::FromStr,
+};
 
 #[derive(Debug, Clone, Copy, Hash)]
 pub struct MapParseIter<I, T>(PhantomData<T>, I);
@@ -70,6 +76,112 @@ where
     }
 }
 
+/// An error for one item of an iterator (i.e. one line of the input) along
+/// with where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError<E, S = String> {
+    /// 1-based position in the iterator.
+    pub line: usize,
+    /// The offending item.
+    pub text: S,
+    pub error: E,
+}
+
+impl<E: Display, S: Debug> Display for LineError<E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {:?}: {}", self.line, self.text, self.error)
+    }
+}
+
+impl<E: Error, S: Debug> Error for LineError<E, S> {}
+
+/// Every [`LineError`] from an iterator; see [`IterMapExt::collect_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report<E, S = String> {
+    pub errors: Vec<LineError<E, S>>,
+}
+
+impl<E: Display, S: Debug> Display for Report<E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bad line(s):", self.errors.len())?;
+        for e in &self.errors {
+            write!(f, "\n  {e}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<E: Display + Debug, S: Debug> Error for Report<E, S> {}
+
+/// Like [`MapParseIter`] but yields [`Result`]s instead of panicking.
+#[derive(Debug, Clone, Copy, Hash)]
+pub struct TryMapParseIter<I, T>(PhantomData<T>, I, usize);
+
+impl<'a, I: Iterator<Item = &'a str>, T: FromStr> Iterator for TryMapParseIter<I, T> {
+    type Item = Result<T, LineError<T::Err, &'a str>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.1.next()?;
+        self.2 += 1;
+
+        Some(text.trim().parse().map_err(|error| LineError {
+            line: self.2,
+            text,
+            error,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.1.size_hint()
+    }
+}
+
+impl<'a, I: ExactSizeIterator<Item = &'a str>, T: FromStr> ExactSizeIterator
+    for TryMapParseIter<I, T>
+{
+    fn len(&self) -> usize {
+        self.1.len()
+    }
+}
+
+/// Like [`MapTryIntoIter`] but yields [`Result`]s instead of panicking.
+#[derive(Debug, Clone, Copy, Hash)]
+pub struct TryMapTryIntoIter<I, T>(PhantomData<T>, I, usize);
+
+impl<I: Iterator, T: TryFrom<I::Item>> Iterator for TryMapTryIntoIter<I, T>
+where
+    I::Item: Clone,
+{
+    type Item = Result<T, LineError<T::Error, I::Item>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.1.next()?;
+        self.2 += 1;
+
+        Some(text.clone().try_into().map_err(|error| LineError {
+            line: self.2,
+            text,
+            error,
+        }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.1.size_hint()
+    }
+}
+
+impl<I: ExactSizeIterator, T: TryFrom<I::Item>> ExactSizeIterator for TryMapTryIntoIter<I, T>
+where
+    I::Item: Clone,
+{
+    fn len(&self) -> usize {
+        self.1.len()
+    }
+}
+
 pub trait IterMapExt: Sized + Iterator {
     fn map_parse<'a, T>(self) -> MapParseIter<Self, T>
     where
@@ -95,6 +207,129 @@ pub trait IterMapExt: Sized + Iterator {
     {
         self.map_into().map(func)
     }
+
+    /// Like [`IterMapExt::map_parse`] but yields a [`LineError`] for items
+    /// that don't parse.
+    fn try_map_parse<'a, T>(self) -> TryMapParseIter<Self, T>
+    where
+        Self: Iterator<Item = &'a str>,
+        T: FromStr,
+    {
+        TryMapParseIter(PhantomData, self, 0)
+    }
+
+    /// Like [`IterMapExt::map_into`] but yields a [`LineError`] for items
+    /// that don't convert.
+    fn try_map_try_into<T>(self) -> TryMapTryIntoIter<Self, T>
+    where
+        T: TryFrom<Self::Item>,
+        Self::Item: Clone,
+    {
+        TryMapTryIntoIter(PhantomData, self, 0)
+    }
+
+    /// Collects the [`Ok`] values if there are no errors; otherwise gathers
+    /// up _every_ error (not just the first).
+    fn collect_report<T, E, S, C>(self) -> Result<C, Report<E, S>>
+    where
+        Self: Iterator<Item = Result<T, LineError<E, S>>>,
+        C: FromIterator<T>,
+    {
+        let mut errors = vec![];
+        let ok: C = self
+            .filter_map(|r| r.map_err(|e| errors.push(e)).ok())
+            .collect();
+
+        if errors.is_empty() {
+            Ok(ok)
+        } else {
+            Err(Report { errors })
+        }
+    }
 }
 
 impl<I: Iterator + Sized> IterMapExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_try_map::LineTryMap;
+
+    #[test]
+    fn fallible_parsing() {
+        let input = "1\n 2 \nthree\n4\n-5";
+
+        let all: Vec<Result<u8, _>> = input.lines().try_map_parse().collect();
+        assert_eq!(all[1], Ok(2));
+        let err = all[2].as_ref().unwrap_err();
+        assert_eq!((err.line, err.text), (3, "three"));
+        assert_eq!(
+            err.to_string(),
+            "line 3: \"three\": invalid digit found in string"
+        );
+
+        let report = input
+            .lines()
+            .try_map_parse::<u8>()
+            .collect_report::<_, _, _, Vec<_>>()
+            .unwrap_err();
+        assert_eq!(
+            report.errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            [3, 5]
+        );
+        assert!(report.to_string().starts_with("2 bad line(s):\n  line 3"));
+
+        let ok: Vec<i8> = input
+            .lines()
+            .filter(|l| l.trim().parse::<i8>().is_ok())
+            .try_map_parse()
+            .collect_report()
+            .unwrap();
+        assert_eq!(ok, [1, 2, 4, -5]);
+    }
+
+    #[test]
+    fn fallible_conversions() {
+        let big = [1u32, 300, 2, 256];
+        let errs = big
+            .into_iter()
+            .try_map_try_into::<u8>()
+            .collect_report::<_, _, _, Vec<_>>()
+            .unwrap_err();
+        assert_eq!(
+            errs.errors
+                .iter()
+                .map(|e| (e.line, e.text))
+                .collect::<Vec<_>>(),
+            [(2, 300), (4, 256)]
+        );
+
+        #[derive(Debug, PartialEq)]
+        struct Even(u8);
+        impl TryFrom<&str> for Even {
+            type Error = String;
+
+            fn try_from(s: &str) -> Result<Self, String> {
+                match s.parse::<u8>() {
+                    Ok(n) if n % 2 == 0 => Ok(Even(n)),
+                    _ => Err(format!("{s} isn't even")),
+                }
+            }
+        }
+
+        let halves: Result<Vec<u8>, _> = "2\n4\n8"
+            .line_try_map_result(|Even(n)| n / 2)
+            .collect_report();
+        assert_eq!(halves, Ok(vec![1, 2, 4]));
+
+        let bad: Vec<_> = "2\n3".line_try_map_result(|Even(n)| n).collect();
+        assert_eq!(
+            bad[1],
+            Err(LineError {
+                line: 2,
+                text: "3",
+                error: "3 isn't even".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,593 @@
+//! Straight-line register programs, compiled to native code.
+//!
+//! A [`Program`] is a list of [`Step`]s over `i64` registers (which are read
+//! and written by the caller), temporaries (which aren't) and inputs. There's
+//! no control flow: this is for evaluating the same small computation many
+//! millions of times (brute forcing 2021/24, for example).
+//!
+//! With the `llvm` feature [`Program::compile`] JITs the program with LLVM;
+//! otherwise (or with [`Program::interpret`]) it's run by an interpreter with
+//! identical semantics:
+//!   - `+`, `-` and `*` wrap
+//!   - `/` and `%` truncate (as in Rust) and give 0 when dividing by zero;
+//!     `i64::MIN / -1` wraps
+//!   - [`BinOp::Eql`] is 1 or 0
+//!
+//! ```
+//! use aoc::expr::BinOp;
+//! use aoc::jit::{Loc, Operand, Program};
+//!
+//! // r0 = (r0 * 26 + in0) % 1000
+//! let mut p = Program::new();
+//! let t = p.tmp();
+//! p.bin(t, BinOp::Mul, Loc::Reg(0), 26);
+//! p.bin(t, BinOp::Add, t, Operand::Input(0));
+//! p.bin(Loc::Reg(0), BinOp::Rem, t, 1000);
+//!
+//! let f = p.compile();
+//! let mut regs = [40];
+//! f.call(&mut regs, &[7]);
+//! assert_eq!(regs, [47]);
+//! ```
+
+use std::fmt::{self, Debug};
+
+use crate::expr::{BinOp, ExprId, Exprs, Node};
+use crate::vm;
+
+/// Somewhere a value can be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Loc {
+    /// Visible to the caller.
+    Reg(u32),
+    /// Scratch space; starts out as 0 on every call.
+    Tmp(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Loc(Loc),
+    Input(u32),
+    Imm(i64),
+}
+
+impl From<Loc> for Operand {
+    fn from(l: Loc) -> Self {
+        Operand::Loc(l)
+    }
+}
+
+impl From<i64> for Operand {
+    fn from(i: i64) -> Self {
+        Operand::Imm(i)
+    }
+}
+
+impl From<vm::Reg> for Loc {
+    fn from(r: vm::Reg) -> Self {
+        Loc::Reg(r.0 as u32)
+    }
+}
+
+impl From<vm::Reg> for Operand {
+    fn from(r: vm::Reg) -> Self {
+        Operand::Loc(r.into())
+    }
+}
+
+impl From<vm::Src<i64>> for Operand {
+    fn from(s: vm::Src<i64>) -> Self {
+        match s {
+            vm::Src::Reg(r) => r.into(),
+            vm::Src::Imm(i) => Operand::Imm(i),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Step {
+    Mov {
+        dst: Loc,
+        src: Operand,
+    },
+    Bin {
+        dst: Loc,
+        op: BinOp,
+        lhs: Operand,
+        rhs: Operand,
+    },
+}
+
+/// The semantics described in the module docs.
+pub fn apply(op: BinOp, a: i64, b: i64) -> i64 {
+    match op {
+        BinOp::Add => a.wrapping_add(b),
+        BinOp::Sub => a.wrapping_sub(b),
+        BinOp::Mul => a.wrapping_mul(b),
+        BinOp::Div if b == 0 => 0,
+        BinOp::Div => a.wrapping_div(b),
+        BinOp::Rem if b == 0 => 0,
+        BinOp::Rem => a.wrapping_rem(b),
+        BinOp::Eql => (a == b) as i64,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Program {
+    steps: Vec<Step>,
+    regs: usize,
+    tmps: usize,
+    inputs: usize,
+}
+
+impl Program {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// The number of registers the program uses (i.e. the highest register
+    /// index plus one); `call` needs at least this many.
+    pub fn num_regs(&self) -> usize {
+        self.regs
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// A fresh temporary.
+    pub fn tmp(&mut self) -> Loc {
+        self.tmps += 1;
+        Loc::Tmp(self.tmps as u32 - 1)
+    }
+
+    fn note_loc(&mut self, l: Loc) {
+        match l {
+            Loc::Reg(r) => self.regs = self.regs.max(r as usize + 1),
+            Loc::Tmp(t) => self.tmps = self.tmps.max(t as usize + 1),
+        }
+    }
+
+    fn note(&mut self, o: Operand) {
+        match o {
+            Operand::Loc(l) => self.note_loc(l),
+            Operand::Input(i) => self.inputs = self.inputs.max(i as usize + 1),
+            Operand::Imm(_) => {}
+        }
+    }
+
+    pub fn push(&mut self, step: Step) {
+        match step {
+            Step::Mov { dst, src } => {
+                self.note_loc(dst);
+                self.note(src);
+            }
+            Step::Bin { dst, lhs, rhs, .. } => {
+                self.note_loc(dst);
+                self.note(lhs);
+                self.note(rhs);
+            }
+        }
+
+        self.steps.push(step);
+    }
+
+    pub fn mov(&mut self, dst: impl Into<Loc>, src: impl Into<Operand>) {
+        self.push(Step::Mov {
+            dst: dst.into(),
+            src: src.into(),
+        })
+    }
+
+    pub fn bin(
+        &mut self,
+        dst: impl Into<Loc>,
+        op: BinOp,
+        lhs: impl Into<Operand>,
+        rhs: impl Into<Operand>,
+    ) {
+        self.push(Step::Bin {
+            dst: dst.into(),
+            op,
+            lhs: lhs.into(),
+            rhs: rhs.into(),
+        })
+    }
+
+    /// Appends code that evaluates `e` (with variable `n` read from input
+    /// `n`), returning where the result ends up.
+    ///
+    /// Each node gets its own temporary so shared subexpressions are only
+    /// computed once.
+    pub fn lower_expr(&mut self, exprs: &Exprs<i64>, e: ExprId) -> Operand {
+        let mut vals: Vec<Option<Operand>> = vec![None; exprs.len()];
+        for id in exprs.reachable(e, |_| false) {
+            let val = |id: ExprId| vals[id.index()].unwrap();
+            let out = match exprs.node(id) {
+                Node::Const(c) => Operand::Imm(c),
+                Node::Var(v) => Operand::Input(v.index() as u32),
+                Node::Neg(a) => {
+                    let t = self.tmp();
+                    self.bin(t, BinOp::Sub, 0, val(a));
+                    t.into()
+                }
+                Node::Bin(op, a, b) => {
+                    let t = self.tmp();
+                    self.bin(t, op, val(a), val(b));
+                    t.into()
+                }
+            };
+
+            vals[id.index()] = Some(out);
+        }
+
+        vals[e.index()].unwrap()
+    }
+
+    fn check(&self, regs: &[i64], inputs: &[i64]) {
+        assert!(
+            regs.len() >= self.regs,
+            "program uses {} registers but only {} were given",
+            self.regs,
+            regs.len()
+        );
+        assert!(
+            inputs.len() >= self.inputs,
+            "program uses {} inputs but only {} were given",
+            self.inputs,
+            inputs.len()
+        );
+    }
+
+    /// Runs the program with the interpreter.
+    pub fn run(&self, regs: &mut [i64], inputs: &[i64]) {
+        self.check(regs, inputs);
+
+        let mut tmps = vec![0; self.tmps];
+        let val = |o: Operand, regs: &[i64], tmps: &[i64]| match o {
+            Operand::Loc(Loc::Reg(r)) => regs[r as usize],
+            Operand::Loc(Loc::Tmp(t)) => tmps[t as usize],
+            Operand::Input(i) => inputs[i as usize],
+            Operand::Imm(i) => i,
+        };
+
+        for step in &self.steps {
+            let (dst, v) = match *step {
+                Step::Mov { dst, src } => (dst, val(src, regs, &tmps)),
+                Step::Bin { dst, op, lhs, rhs } => {
+                    (dst, apply(op, val(lhs, regs, &tmps), val(rhs, regs, &tmps)))
+                }
+            };
+
+            match dst {
+                Loc::Reg(r) => regs[r as usize] = v,
+                Loc::Tmp(t) => tmps[t as usize] = v,
+            }
+        }
+    }
+
+    /// Uses the interpreter, even with the `llvm` feature.
+    pub fn interpret(&self) -> Compiled {
+        Compiled {
+            program: self.clone(),
+            #[cfg(feature = "llvm")]
+            native: None,
+        }
+    }
+
+    /// JITs the program if the `llvm` feature is enabled.
+    pub fn compile(&self) -> Compiled {
+        Compiled {
+            program: self.clone(),
+            #[cfg(feature = "llvm")]
+            native: Some(llvm::compile(self)),
+        }
+    }
+}
+
+pub struct Compiled {
+    program: Program,
+    #[cfg(feature = "llvm")]
+    native: Option<llvm::Native>,
+}
+
+impl Debug for Compiled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compiled")
+            .field("native", &self.is_native())
+            .field("program", &self.program)
+            .finish()
+    }
+}
+
+impl Compiled {
+    pub fn is_native(&self) -> bool {
+        #[cfg(feature = "llvm")]
+        return self.native.is_some();
+
+        #[cfg(not(feature = "llvm"))]
+        false
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Panics if there are fewer registers or inputs than the program uses.
+    pub fn call(&self, regs: &mut [i64], inputs: &[i64]) {
+        #[cfg(feature = "llvm")]
+        if let Some(native) = &self.native {
+            self.program.check(regs, inputs);
+            // SAFETY: the lengths were checked above and the generated code
+            // only touches registers and inputs that the program mentions.
+            return unsafe { native.call(regs.as_mut_ptr(), inputs.as_ptr()) };
+        }
+
+        self.program.run(regs, inputs)
+    }
+}
+
+#[cfg(feature = "llvm")]
+mod llvm {
+    use inkwell::{
+        builder::Builder,
+        context::Context,
+        execution_engine::JitFunction,
+        types::IntType,
+        values::{IntValue, PointerValue},
+        AddressSpace, IntPredicate, OptimizationLevel,
+    };
+
+    use super::*;
+
+    type Func = unsafe extern "C" fn(*mut i64, *const i64);
+
+    pub(super) struct Native(JitFunction<'static, Func>);
+
+    // The generated code is immutable and `Native` is never cloned, so
+    // sharing it (and the reference counted engine it holds onto) between
+    // threads is fine.
+    unsafe impl Send for Native {}
+    unsafe impl Sync for Native {}
+
+    impl Native {
+        pub(super) unsafe fn call(&self, regs: *mut i64, inputs: *const i64) {
+            self.0.call(regs, inputs)
+        }
+    }
+
+    struct Gen<'c> {
+        builder: Builder<'c>,
+        i64: IntType<'c>,
+        regs: PointerValue<'c>,
+        inputs: PointerValue<'c>,
+        /// The current value of each register (loaded lazily) and whether
+        /// it's been written to.
+        reg_vals: Vec<Option<(IntValue<'c>, bool)>>,
+        tmp_vals: Vec<Option<IntValue<'c>>>,
+    }
+
+    impl<'c> Gen<'c> {
+        fn load(&self, base: PointerValue<'c>, idx: u32) -> IntValue<'c> {
+            let i = self.i64.const_int(idx as u64, false);
+            let ptr = unsafe {
+                self.builder
+                    .build_in_bounds_gep(self.i64, base, &[i], "ptr")
+            };
+            let val = self.builder.build_load(self.i64, ptr.unwrap(), "val");
+            val.unwrap().into_int_value()
+        }
+
+        fn val(&mut self, o: Operand) -> IntValue<'c> {
+            match o {
+                Operand::Loc(Loc::Reg(r)) => match self.reg_vals[r as usize] {
+                    Some((v, _)) => v,
+                    None => {
+                        let v = self.load(self.regs, r);
+                        self.reg_vals[r as usize] = Some((v, false));
+                        v
+                    }
+                },
+                Operand::Loc(Loc::Tmp(t)) => {
+                    self.tmp_vals[t as usize].unwrap_or_else(|| self.i64.const_zero())
+                }
+                Operand::Input(i) => self.load(self.inputs, i),
+                Operand::Imm(i) => self.i64.const_int(i as u64, true),
+            }
+        }
+
+        fn bin(&self, op: BinOp, a: IntValue<'c>, b: IntValue<'c>) -> IntValue<'c> {
+            let b_ = &self.builder;
+            let cmp = |p, x, y| b_.build_int_compare(p, x, y, "cmp").unwrap();
+            let zero = self.i64.const_zero();
+
+            // Division by zero and `MIN / -1` are UB in LLVM; divide by 1
+            // instead and then patch up the result.
+            let safe_div = || {
+                let by_zero = cmp(IntPredicate::EQ, b, zero);
+                let overflow = b_
+                    .build_and(
+                        cmp(
+                            IntPredicate::EQ,
+                            a,
+                            self.i64.const_int(i64::MIN as u64, true),
+                        ),
+                        cmp(IntPredicate::EQ, b, self.i64.const_int(-1i64 as u64, true)),
+                        "overflow",
+                    )
+                    .unwrap();
+                let bad = b_.build_or(by_zero, overflow, "bad").unwrap();
+                let divisor = b_
+                    .build_select(bad, self.i64.const_int(1, false), b, "divisor")
+                    .unwrap()
+                    .into_int_value();
+
+                (by_zero, divisor)
+            };
+
+            // (Building only fails if the builder isn't positioned, and it
+            // always is.)
+            let res = match op {
+                BinOp::Add => b_.build_int_add(a, b, "add"),
+                BinOp::Sub => b_.build_int_sub(a, b, "sub"),
+                BinOp::Mul => b_.build_int_mul(a, b, "mul"),
+                BinOp::Div => {
+                    let (by_zero, divisor) = safe_div();
+                    let q = b_.build_int_signed_div(a, divisor, "div").unwrap();
+                    b_.build_select(by_zero, zero, q, "div")
+                        .map(|v| v.into_int_value())
+                }
+                BinOp::Rem => {
+                    // `MIN % 1` is 0, as is `MIN.wrapping_rem(-1)`.
+                    let (by_zero, divisor) = safe_div();
+                    let r = b_.build_int_signed_rem(a, divisor, "rem").unwrap();
+                    b_.build_select(by_zero, zero, r, "rem")
+                        .map(|v| v.into_int_value())
+                }
+                BinOp::Eql => {
+                    let c = cmp(IntPredicate::EQ, a, b);
+                    b_.build_int_z_extend(c, self.i64, "eql")
+                }
+            };
+
+            res.unwrap()
+        }
+    }
+
+    pub(super) fn compile(p: &Program) -> Native {
+        // Everything generated from a context borrows it; contexts are small
+        // and programs are compiled a handful of times per run so we leak it.
+        let ctx: &'static Context = Box::leak(Box::new(Context::create()));
+        let module = ctx.create_module("jit");
+        let engine = module
+            .create_jit_execution_engine(OptimizationLevel::Aggressive)
+            .unwrap();
+
+        let i64 = ctx.i64_type();
+        let ptr = i64.ptr_type(AddressSpace::default());
+        let fn_type = ctx.void_type().fn_type(&[ptr.into(), ptr.into()], false);
+        let function = module.add_function("run", fn_type, None);
+        let builder = ctx.create_builder();
+        builder.position_at_end(ctx.append_basic_block(function, "entry"));
+
+        let mut g = Gen {
+            builder,
+            i64,
+            regs: function.get_nth_param(0).unwrap().into_pointer_value(),
+            inputs: function.get_nth_param(1).unwrap().into_pointer_value(),
+            reg_vals: vec![None; p.regs],
+            tmp_vals: vec![None; p.tmps],
+        };
+
+        for step in &p.steps {
+            let (dst, v) = match *step {
+                Step::Mov { dst, src } => (dst, g.val(src)),
+                Step::Bin { dst, op, lhs, rhs } => {
+                    let (a, b) = (g.val(lhs), g.val(rhs));
+                    (dst, g.bin(op, a, b))
+                }
+            };
+
+            match dst {
+                Loc::Reg(r) => g.reg_vals[r as usize] = Some((v, true)),
+                Loc::Tmp(t) => g.tmp_vals[t as usize] = Some(v),
+            }
+        }
+
+        for (r, v) in g.reg_vals.iter().enumerate() {
+            if let Some((v, true)) = *v {
+                let i = i64.const_int(r as u64, false);
+                let ptr = unsafe { g.builder.build_in_bounds_gep(i64, g.regs, &[i], "ptr") };
+                g.builder.build_store(ptr.unwrap(), v).unwrap();
+            }
+        }
+        g.builder.build_return(None).unwrap();
+
+        Native(unsafe { engine.get_function("run") }.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semantics() {
+        let cases = [
+            (BinOp::Add, i64::MAX, 1, i64::MIN),
+            (BinOp::Mul, i64::MIN, -1, i64::MIN),
+            (BinOp::Div, -7, 2, -3),
+            (BinOp::Div, 7, 0, 0),
+            (BinOp::Div, i64::MIN, -1, i64::MIN),
+            (BinOp::Rem, -7, 2, -1),
+            (BinOp::Rem, 7, 0, 0),
+            (BinOp::Rem, i64::MIN, -1, 0),
+            (BinOp::Eql, 3, 3, 1),
+        ];
+
+        for (op, a, b, want) in cases {
+            let mut p = Program::new();
+            p.bin(Loc::Reg(1), op, Operand::Input(0), Loc::Reg(0));
+
+            let mut regs = [b, 99];
+            p.compile().call(&mut regs, &[a]);
+            assert_eq!(regs, [b, want], "{a} {op} {b}");
+
+            let mut regs = [b, 99];
+            p.interpret().call(&mut regs, &[a]);
+            assert_eq!(regs, [b, want], "{a} {op} {b}");
+        }
+    }
+
+    #[test]
+    fn from_vm_and_expr() {
+        // One stage of 2021/24.
+        let mut e = Exprs::new();
+        let (z, w) = (e.sym("z"), e.sym("w"));
+        let (c26, c1, c0, c25) = (e.constant(26), e.constant(1), e.constant(0), e.constant(25));
+        let c_11 = e.constant(11);
+        let c3 = e.constant(3);
+        let zm = e.rem(z, c26);
+        let x = e.add(zm, c_11);
+        let x = e.eql(x, w);
+        let x = e.eql(x, c0);
+        let y = e.mul(c25, x);
+        let y = e.add(y, c1);
+        let z2 = e.mul(z, y);
+        let y2 = e.add(w, c3);
+        let y2 = e.mul(y2, x);
+        let out = e.add(z2, y2);
+
+        let mut p = Program::new();
+        let res = p.lower_expr(&e, out);
+        p.mov(vm::Reg(25), res);
+        assert_eq!((p.num_regs(), p.num_inputs()), (26, 2));
+
+        let f = p.compile();
+        for (zv, wv) in [(0, 9), (12, 1), (-30, 4), (1000, 7)] {
+            let mut regs = [0; 26];
+            f.call(&mut regs, &[zv, wv]);
+            let want = e.eval(out, |v| Some([zv, wv][v.index()])).unwrap();
+            assert_eq!(regs[25], want);
+        }
+
+        let mut p = Program::new();
+        let src: vm::Src = "b".parse().unwrap();
+        p.bin(vm::Reg(0), BinOp::Mul, src, vm::Src::Imm(3));
+        let mut regs = [0, 5];
+        p.interpret().call(&mut regs, &[]);
+        assert_eq!(regs, [15, 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "registers")]
+    fn too_few_registers() {
+        let mut p = Program::new();
+        p.mov(Loc::Reg(3), 1);
+        p.compile().call(&mut [0; 2], &[]);
+    }
+}
@@ -1,6 +1,10 @@
 #[doc(hidden)]
 pub use aoc_macros::*;
 
+// So that code generated by our proc macros (which refers to `::aoc`) also
+// works within this crate.
+extern crate self as aoc;
+
 #[macro_export]
 macro_rules! sub {
     ($($tt:tt)*) => {{
@@ -34,14 +38,31 @@ pub use AdventOfCode as A;
 #[macro_use]
 mod macros;
 pub mod client;
+pub mod counter;
+pub mod cycle;
+pub mod expr;
 pub mod friends;
+pub mod hex;
+pub mod interval;
 pub mod iterator_collect_ext;
 pub mod iterator_dbg_ext;
 pub mod iterator_freq_ext;
 pub mod iterator_map_ext;
+pub mod jit;
 pub mod line_try_map;
+pub mod num;
 pub mod object_store;
+pub mod parse;
+pub mod plot;
+pub mod polyglot;
+pub mod pratt;
+pub mod registry;
+pub mod ring;
+pub mod search;
+pub mod sections;
 pub mod tuple_idx;
+pub mod viz;
+pub mod vm;
 
 pub use client::AdventOfCode;
 pub use friends::Itertools;
@@ -51,5 +72,6 @@ pub use iterator_dbg_ext::IterDbgExt as _;
 pub use iterator_freq_ext::IterFreqExt as _;
 pub use iterator_map_ext::IterMapExt as _;
 pub use line_try_map::LineTryMap as _;
+pub use sections::Sections as _;
 pub use tuple_idx::num_structs::*;
 pub use tuple_idx::TupleGetExt as _;
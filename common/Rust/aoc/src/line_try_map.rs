@@ -1,6 +1,8 @@
 use std::{fmt::Debug, iter::Map, str::Lines};
 
-use crate::iterator_map_ext::MapTryIntoIter;
+use itertools::{Itertools, MapOk};
+
+use crate::iterator_map_ext::{MapTryIntoIter, TryMapTryIntoIter};
 
 use super::iterator_map_ext::IterMapExt;
 
@@ -29,6 +31,19 @@ pub trait LineTryMap: AsRef<str> {
             .map((|l: &'s str| -> &'s [u8] { l.as_bytes() }) as _)
             .map_try(func)
     }
+
+    /// Like [`LineTryMap::line_try_map`] but yields a
+    /// [`LineError`](crate::iterator_map_ext::LineError) (with the line
+    /// number and text) for lines that don't convert instead of panicking.
+    fn line_try_map_result<'s, T, U, F: FnMut(T) -> U>(
+        &'s self,
+        func: F,
+    ) -> MapOk<TryMapTryIntoIter<Lines<'s>, T>, F>
+    where
+        T: TryFrom<&'s str>,
+    {
+        self.as_ref().lines().try_map_try_into().map_ok(func)
+    }
 }
 
 impl<S: AsRef<str>> LineTryMap for S {}
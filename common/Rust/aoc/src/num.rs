@@ -0,0 +1,427 @@
+//! Number theory helpers.
+//!
+//! Modular arithmetic goes through [`ModInt`] which widens to 128 bits for
+//! the multiplications (and falls back to double-and-add for 128-bit types)
+//! so that nothing overflows for moduli that fit in the type.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+
+use num_traits::{NumCast, PrimInt, Signed};
+
+pub fn gcd<T: PrimInt>(mut a: T, mut b: T) -> T {
+    while b != T::zero() {
+        (a, b) = (b, a % b);
+    }
+
+    if a < T::zero() {
+        T::zero() - a
+    } else {
+        a
+    }
+}
+
+pub fn lcm<T: PrimInt>(a: T, b: T) -> T {
+    if a == T::zero() || b == T::zero() {
+        return T::zero();
+    }
+
+    let l = a / gcd(a, b) * b;
+    if l < T::zero() {
+        T::zero() - l
+    } else {
+        l
+    }
+}
+
+pub fn gcd_all<T: PrimInt>(it: impl IntoIterator<Item = T>) -> T {
+    it.into_iter().fold(T::zero(), gcd)
+}
+
+/// The lcm of every element; 1 for an empty iterator.
+pub fn lcm_all<T: PrimInt>(it: impl IntoIterator<Item = T>) -> T {
+    it.into_iter().fold(T::one(), lcm)
+}
+
+/// Extended Euclidean algorithm: returns `(g, x, y)` such that
+/// `a * x + b * y == g` where `g` is the (non-negative) gcd of `a` and `b`.
+pub fn egcd<T: PrimInt + Signed>(a: T, b: T) -> (T, T, T) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::one(), T::zero());
+    let (mut old_y, mut y) = (T::zero(), T::one());
+
+    while r != T::zero() {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < T::zero() {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Integer types that support overflow-free modular arithmetic.
+pub trait ModInt: PrimInt {
+    /// The non-negative remainder; in `0..m` for positive `m`.
+    fn rem_euclid(self, m: Self) -> Self;
+
+    /// `self * rhs (mod m)`, in `0..m`.
+    fn mul_mod(self, rhs: Self, m: Self) -> Self;
+
+    fn add_mod(self, rhs: Self, m: Self) -> Self {
+        let (a, b) = (self.rem_euclid(m), rhs.rem_euclid(m));
+        // `a + b` can overflow but `a - (m - b)` can't:
+        if a >= m - b {
+            a - (m - b)
+        } else {
+            a + b
+        }
+    }
+
+    fn pow_mod(self, mut exp: Self, m: Self) -> Self {
+        assert!(exp >= Self::zero(), "negative exponents aren't supported");
+
+        let mut base = self.rem_euclid(m);
+        let mut acc = Self::one().rem_euclid(m);
+        while exp > Self::zero() {
+            if exp & Self::one() == Self::one() {
+                acc = acc.mul_mod(base, m);
+            }
+
+            base = base.mul_mod(base, m);
+            exp = exp >> 1;
+        }
+
+        acc
+    }
+}
+
+macro_rules! mod_int_widening {
+    ($($t:ty => $wide:ty),* $(,)?) => {$(
+        impl ModInt for $t {
+            #[inline]
+            fn rem_euclid(self, m: Self) -> Self {
+                <$t>::rem_euclid(self, m)
+            }
+
+            #[inline]
+            fn mul_mod(self, rhs: Self, m: Self) -> Self {
+                ((self as $wide) * (rhs as $wide)).rem_euclid(m as $wide) as $t
+            }
+        }
+    )*};
+}
+
+mod_int_widening! {
+    u8 => u128, u16 => u128, u32 => u128, u64 => u128, usize => u128,
+    i8 => i128, i16 => i128, i32 => i128, i64 => i128, isize => i128,
+}
+
+/// Double-and-add; there's nothing wider to multiply in.
+fn mul_mod_u128(a: u128, mut b: u128, m: u128) -> u128 {
+    let mut a = a % m;
+    b %= m;
+
+    let mut acc = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            acc = a.add_mod(acc, m);
+        }
+
+        a = a.add_mod(a, m);
+        b >>= 1;
+    }
+
+    acc
+}
+
+impl ModInt for u128 {
+    fn rem_euclid(self, m: Self) -> Self {
+        self % m
+    }
+
+    fn mul_mod(self, rhs: Self, m: Self) -> Self {
+        mul_mod_u128(self, rhs, m)
+    }
+}
+
+impl ModInt for i128 {
+    fn rem_euclid(self, m: Self) -> Self {
+        i128::rem_euclid(self, m)
+    }
+
+    fn mul_mod(self, rhs: Self, m: Self) -> Self {
+        let m = m.unsigned_abs();
+        let (a, b) = (self.rem_euclid(m as i128), rhs.rem_euclid(m as i128));
+
+        mul_mod_u128(a as u128, b as u128, m) as i128
+    }
+}
+
+pub fn mod_pow<T: ModInt>(base: T, exp: T, m: T) -> T {
+    base.pow_mod(exp, m)
+}
+
+/// `x` such that `a * x == 1 (mod m)`; `None` if `a` and `m` aren't coprime.
+///
+/// `m` must fit in an `i128`.
+pub fn mod_inverse<T: ModInt>(a: T, m: T) -> Option<T> {
+    let m_wide = m.to_i128().expect("modulus must fit in an i128");
+    let a_wide = a.rem_euclid(m).to_i128().unwrap();
+
+    let (g, x, _) = egcd(a_wide, m_wide);
+    if g != 1 {
+        return None;
+    }
+
+    NumCast::from(x.rem_euclid(m_wide))
+}
+
+/// Chinese remainder theorem for `(residue, modulus)` pairs; moduli do not
+/// need to be coprime.
+///
+/// Returns `(x, l)` where `x` is the smallest non-negative solution and `l` is
+/// the lcm of the moduli (so every `x + k * l` is also a solution), or `None`
+/// if the congruences are inconsistent or the answer doesn't fit in `T`.
+///
+/// Internally this works in `i128`; the lcm of the moduli must fit in one.
+pub fn crt<T: PrimInt>(pairs: impl IntoIterator<Item = (T, T)>) -> Option<(T, T)> {
+    let (x, l) = pairs
+        .into_iter()
+        .try_fold((0i128, 1i128), |(x, l), (r, m)| {
+            let (r, m) = (r.to_i128()?, m.to_i128()?);
+            let r = r.rem_euclid(m);
+
+            // x + l * k == r (mod m) => l * k == r - x (mod m)
+            let g = gcd(l, m);
+            let diff = r - x;
+            if diff % g != 0 {
+                return None;
+            }
+
+            let m_g = m / g;
+            let k = if m_g == 1 {
+                0
+            } else {
+                (diff / g).mul_mod(mod_inverse(l / g, m_g)?, m_g)
+            };
+
+            let new_l = l.checked_mul(m_g)?;
+            Some(((x + l * k).rem_euclid(new_l), new_l))
+        })?;
+
+    Some((NumCast::from(x)?, NumCast::from(l)?))
+}
+
+/// Baby-step giant-step: the smallest `x >= 0` such that
+/// `base^x == target (mod m)`.
+///
+/// `base` must be invertible mod `m` (i.e. coprime with it) and `m` must fit
+/// in an `i128`. Runs in `O(sqrt(m))` time and memory.
+pub fn discrete_log<T: ModInt + Hash>(base: T, target: T, m: T) -> Option<T> {
+    if m == T::one() {
+        return Some(T::zero());
+    }
+
+    let n = isqrt(m) + T::one();
+    let target = target.rem_euclid(m);
+
+    // baby steps: base^j for j in 0..n; keep the smallest `j` for each value
+    let mut table = HashMap::new();
+    let mut curr = T::one();
+    let mut j = T::zero();
+    while j < n {
+        table.entry(curr).or_insert(j);
+        curr = curr.mul_mod(base, m);
+        j = j + T::one();
+    }
+
+    // giant steps: target * base^(-n * i)
+    let factor = mod_inverse(base, m)?.pow_mod(n, m);
+    let mut gamma = target;
+    let mut i = T::zero();
+    while i < n {
+        if let Some(&j) = table.get(&gamma) {
+            return Some(i * n + j);
+        }
+
+        gamma = gamma.mul_mod(factor, m);
+        i = i + T::one();
+    }
+
+    None
+}
+
+/// `floor(sqrt(n))`, exactly.
+///
+/// Panics if `n` is negative.
+pub fn isqrt<T: PrimInt>(n: T) -> T {
+    assert!(
+        n >= T::zero(),
+        "can't take the square root of a negative number"
+    );
+    if n < T::one() + T::one() {
+        return n;
+    }
+
+    // Start above the root and walk down with Newton's method; the iterates
+    // decrease monotonically until they hit the floor of the root.
+    let bits = T::zero().count_zeros() - n.leading_zeros();
+    let mut x = T::one() << (bits as usize).div_ceil(2);
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x;
+        }
+
+        x = y;
+    }
+}
+
+/// The integers `x` for which `a*x^2 + b*x + c < 0`, for `a > 0`.
+///
+/// 2023/06's "how long to hold the button" is
+/// `quadratic_negative(1, -time, distance)`.
+pub fn quadratic_negative(a: i128, b: i128, c: i128) -> Option<RangeInclusive<i128>> {
+    quadratic_interval(a, b, c, |v| v < 0)
+}
+
+/// The integers `x` for which `a*x^2 + b*x + c <= 0`, for `a > 0`.
+pub fn quadratic_non_positive(a: i128, b: i128, c: i128) -> Option<RangeInclusive<i128>> {
+    quadratic_interval(a, b, c, |v| v <= 0)
+}
+
+fn quadratic_interval(
+    a: i128,
+    b: i128,
+    c: i128,
+    pred: impl Fn(i128) -> bool,
+) -> Option<RangeInclusive<i128>> {
+    assert!(a > 0, "the leading coefficient must be positive");
+
+    let disc = b * b - 4 * a * c;
+    if disc < 0 {
+        return None;
+    }
+
+    let holds = |x: i128| pred(a * x * x + b * x + c);
+
+    // The set is an interval centred on `-b / 2a`; if it's non-empty one of
+    // the integers on either side of the centre is in it.
+    let vertex = (-b).div_euclid(2 * a);
+    let inside = [vertex, vertex + 1].into_iter().find(|&v| holds(v))?;
+
+    // Estimate the roots and then fix up the rounding:
+    let s = isqrt(disc);
+    let mut lo = (-b - s).div_euclid(2 * a).min(inside);
+    while !holds(lo) {
+        lo += 1;
+    }
+    while holds(lo - 1) {
+        lo -= 1;
+    }
+
+    let mut hi = (-b + s).div_euclid(2 * a).max(inside);
+    while !holds(hi) {
+        hi -= 1;
+    }
+    while holds(hi + 1) {
+        hi += 1;
+    }
+
+    Some(lo..=hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(48u32, 18), 6);
+        assert_eq!(gcd(-48i64, 18), 6);
+        assert_eq!(gcd(0u8, 7), 7);
+        assert_eq!(lcm(4i32, -6), 12);
+        assert_eq!(lcm_all([2u64, 3, 4, 5]), 60);
+        assert_eq!(gcd_all([12usize, 18, 30]), 6);
+    }
+
+    #[test]
+    fn extended_gcd() {
+        for (a, b) in [(240i64, 46), (-240, 46), (17, 0), (0, 5), (3, -7)] {
+            let (g, x, y) = egcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        assert_eq!(mod_inverse(3u32, 11), Some(4));
+        assert_eq!(mod_inverse(-3i64, 11), Some(7));
+        assert_eq!(mod_inverse(6u32, 9), None);
+
+        let m = (1u64 << 63) - 25; // a big prime
+        assert_eq!(mod_pow(2, m - 1, m), 1);
+        assert_eq!((m - 1).mul_mod(m - 1, m), 1);
+
+        let m = u128::MAX - 158; // a bigger prime
+        assert_eq!(mod_pow(3, m - 1, m), 1);
+        assert_eq!((m - 1).add_mod(m - 1, m), m - 2);
+        assert_eq!((-5i128).mul_mod(3, 7), 6);
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        // 2020/13: 7,13,x,x,59,x,31,19
+        let buses = [(0, 7), (1, 13), (4, 59), (6, 31), (7, 19)];
+        let (t, l) = crt(buses.iter().map(|&(i, m): &(i64, i64)| (-i, m))).unwrap();
+        assert_eq!(t, 1068781);
+        assert_eq!(l, 7 * 13 * 59 * 31 * 19);
+
+        // Non-coprime moduli:
+        assert_eq!(crt([(2u32, 6), (8, 9)]), Some((8, 18)));
+        assert_eq!(crt([(1u32, 4), (2, 6)]), None);
+        assert_eq!(crt::<u8>([]), Some((0, 1)));
+    }
+
+    #[test]
+    fn combo_breaker() {
+        // 2020/25
+        assert_eq!(discrete_log(7u64, 5764801, 20201227), Some(8));
+        assert_eq!(discrete_log(7u64, 17807724, 20201227), Some(11));
+        assert_eq!(discrete_log(2u32, 3, 7), None);
+        assert_eq!(discrete_log(3u32, 1, 7), Some(0));
+    }
+
+    #[test]
+    fn integer_sqrt() {
+        for n in 0u64..10_000 {
+            let r = isqrt(n);
+            assert!(r * r <= n && (r + 1) * (r + 1) > n, "{n}");
+        }
+
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(isqrt(i32::MAX), 46340);
+    }
+
+    #[test]
+    fn wait_for_it() {
+        let races = [(7, 9, 4), (15, 40, 8), (30, 200, 9), (71530, 940200, 71503)];
+        for (time, dist, ways) in races {
+            let r = quadratic_negative(1, -time, dist).unwrap();
+            assert_eq!(r.end() - r.start() + 1, ways);
+        }
+
+        assert_eq!(quadratic_negative(1, 0, 0), None);
+        assert_eq!(quadratic_non_positive(1, 0, 0), Some(0..=0));
+        assert_eq!(quadratic_negative(4, 0, -1), Some(0..=0));
+        assert_eq!(quadratic_negative(1, 0, 1), None);
+    }
+}
@@ -1,33 +1,54 @@
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::{Index, IndexMut};
-use std::{borrow::Borrow, collections::HashSet};
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+};
 
 // We can implement Hash and Eq here because [`ObjectStore`] ensures that each
-// index (the `usize` here) corresponds to a unique instance.
+// (index, generation) pair (the `usize` and `u32` here) corresponds to a
+// unique instance.
 #[derive(Debug)]
 pub struct Ref<'store, T: Eq + Hash, UniqueMarker = ()>(
     usize,
+    u32,
     PhantomData<(&'store mut (), T, UniqueMarker)>,
 );
 
+impl<'s, T: Eq + Hash, U> Ref<'s, T, U> {
+    /// The slot in the backing `Vec` this refers to.
+    ///
+    /// These are dense (`0..store.len()`) as long as nothing has been removed;
+    /// slots freed by [`ObjectStore::remove`] are reused by later inserts.
+    pub fn index(self) -> usize {
+        self.0
+    }
+
+    /// Bumped every time the slot is reused.
+    pub fn generation(self) -> u32 {
+        self.1
+    }
+}
+
 impl<'s, T: Eq + Hash, U> Copy for Ref<'s, T, U> {}
 impl<'s, T: Eq + Hash, U> Clone for Ref<'s, T, U> {
     fn clone(&self) -> Self {
-        Ref(self.0, PhantomData)
+        *self
     }
 }
 
 impl<'s, T: Eq + Hash, U> Eq for Ref<'s, T, U> {}
 impl<'s, T: Eq + Hash, U> PartialEq for Ref<'s, T, U> {
     fn eq(&self, other: &Self) -> bool {
-        self.0.eq(&other.0)
+        (self.0, self.1).eq(&(other.0, other.1))
     }
 }
 
 impl<'s, T: Eq + Hash, U> Hash for Ref<'s, T, U> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.hash(state)
+        self.0.hash(state);
+        self.1.hash(state);
     }
 }
 
@@ -135,8 +156,10 @@ impl<'store, T: Eq + Hash, U> PartialEq<T> for InnerRef<'store, T, U> {
 /// can then be turned into regular immutable or mutable references by
 /// providing a reference to the object store instance the reference came from.
 ///
-/// Because this type is append only (i.e. you cannot remove items; you can only
-/// add them), the provided [`Ref`]s can outlive
+/// Items can be [removed](ObjectStore::remove); this marks their slot as free
+/// (to be reused by a later insert) and bumps the slot's generation so that
+/// outstanding [`Ref`]s to the removed item are detected as stale. The
+/// provided [`Ref`]s can outlive
 //
 // TODO: can we get a Ref and then use it with an ObjectStore that it did not
 // originate from? or does the invariant nature of lifetimes on mutable
@@ -208,6 +231,13 @@ pub struct ObjectStore<'store, T: Eq + Hash, UniqueMarker = ()> {
     //
     // So, we have to resort to using unsafe.
     map: HashSet<InnerRef<'store, T, UniqueMarker>>,
+
+    // Generation and liveness for each slot in `store`.
+    //
+    // Removed items stay in `store` (we can't move them out without leaving a
+    // hole) until their slot is reused.
+    slots: Vec<(u32, bool)>,
+    free: Vec<usize>,
 }
 
 impl<'s, T: Eq + Hash, U> ObjectStore<'s, T, U> {
@@ -219,6 +249,8 @@ impl<'s, T: Eq + Hash, U> ObjectStore<'s, T, U> {
             Ok(Self {
                 store,
                 map: HashSet::new(),
+                slots: Vec::new(),
+                free: Vec::new(),
             })
         }
     }
@@ -227,10 +259,19 @@ impl<'s, T: Eq + Hash, U> ObjectStore<'s, T, U> {
         if let Some(r) = self.lookup(&obj) {
             Err((obj, r))
         } else {
-            let idx = self.store.len();
-            let r = Ref(idx, PhantomData);
+            let r = if let Some(idx) = self.free.pop() {
+                let (generation, live) = &mut self.slots[idx];
+                *generation += 1;
+                *live = true;
+
+                self.store[idx] = obj;
+                Ref(idx, *generation, PhantomData)
+            } else {
+                self.store.push(obj);
+                self.slots.push((0, true));
+                Ref(self.store.len() - 1, 0, PhantomData)
+            };
 
-            self.store.push(obj);
             let _added = self.map.insert(InnerRef(self.store as *const _, r));
             debug_assert!(_added);
 
@@ -238,15 +279,84 @@ impl<'s, T: Eq + Hash, U> ObjectStore<'s, T, U> {
         }
     }
 
+    /// Inserts everything in `iter`, reusing existing entries for duplicates.
+    ///
+    /// Returns the [`Ref`] for each item, in order; `Ref::index` on these
+    /// gives a dense remapping of the items.
+    pub fn intern_all(&mut self, iter: impl IntoIterator<Item = T>) -> Vec<Ref<'s, T, U>> {
+        iter.into_iter()
+            .map(|obj| self.insert(obj).unwrap_or_else(|(_, r)| r))
+            .collect()
+    }
+
     pub fn lookup(&self, obj: &T) -> Option<Ref<'s, T, U>> {
         self.map.get(obj).map(|t| t.1)
     }
 
+    /// Removes the item `r` refers to; returns `false` if `r` is stale.
+    ///
+    /// The item itself is left in the backing [`Vec`] until its slot is
+    /// reused.
+    pub fn remove(&mut self, r: Ref<'s, T, U>) -> bool {
+        if !self.contains(r) {
+            return false;
+        }
+
+        let _removed = self.map.remove(&self.store[r.0]);
+        debug_assert!(_removed);
+
+        self.slots[r.0].1 = false;
+        self.free.push(r.0);
+        true
+    }
+
+    /// Whether `r` refers to an item that hasn't been removed.
+    pub fn contains(&self, r: Ref<'s, T, U>) -> bool {
+        self.slots.get(r.0) == Some(&(r.1, true))
+    }
+
+    /// The [`Ref`] for the live item in slot `idx`, if there is one; the
+    /// inverse of [`Ref::index`].
+    pub fn ref_at(&self, idx: usize) -> Option<Ref<'s, T, U>> {
+        match self.slots.get(idx) {
+            Some(&(generation, true)) => Some(Ref(idx, generation, PhantomData)),
+            _ => None,
+        }
+    }
+
+    /// Number of live items.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// One more than the largest [`Ref::index`] handed out so far; useful for
+    /// sizing tables indexed by [`Ref::index`].
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Live items in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (Ref<'s, T, U>, &T)> + '_ {
+        (0..self.slots.len()).filter_map(|idx| Some((self.ref_at(idx)?, &self.store[idx])))
+    }
+
+    pub fn try_get(&self, r: Ref<'s, T, U>) -> Option<&T> {
+        self.contains(r).then(|| &self.store[r.0])
+    }
+
+    /// Panics if `r` is stale.
     pub fn get(&self, r: Ref<'s, T, U>) -> &T {
+        assert!(self.contains(r), "stale Ref");
         &self.store[r.0]
     }
 
+    /// Panics if `r` is stale.
     pub fn get_mut(&mut self, r: Ref<'s, T, U>) -> &mut T {
+        assert!(self.contains(r), "stale Ref");
         &mut self.store[r.0]
     }
 }
@@ -265,6 +375,89 @@ impl<'s, T: Eq + Hash, U> IndexMut<Ref<'s, T, U>> for ObjectStore<'s, T, U> {
     }
 }
 
+/// An [`ObjectStore`] specialised for string slices borrowed from the input
+/// (node names, valve names, ...).
+///
+/// This owns its storage (no `Vec` to pass in), can look up any `&str`
+/// regardless of lifetime and is append only: there's no removal so the
+/// [`Ref::index`]s it hands out are always dense.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrInterner<'s, UniqueMarker = ()> {
+    names: Vec<&'s str>,
+    map: HashMap<&'s str, usize>,
+    _marker: PhantomData<UniqueMarker>,
+}
+
+impl<'s, U> Default for StrInterner<'s, U> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'s, U> StrInterner<'s, U> {
+    pub fn new() -> Self {
+        Self {
+            names: Vec::new(),
+            map: HashMap::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the existing [`Ref`] if `name` has already been interned.
+    pub fn intern(&mut self, name: &'s str) -> Ref<'s, &'s str, U> {
+        let idx = *self.map.entry(name).or_insert_with(|| {
+            self.names.push(name);
+            self.names.len() - 1
+        });
+
+        Ref(idx, 0, PhantomData)
+    }
+
+    /// See [`ObjectStore::intern_all`].
+    pub fn intern_all(
+        &mut self,
+        iter: impl IntoIterator<Item = &'s str>,
+    ) -> Vec<Ref<'s, &'s str, U>> {
+        iter.into_iter().map(|n| self.intern(n)).collect()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Ref<'s, &'s str, U>> {
+        self.map.get(name).map(|&idx| Ref(idx, 0, PhantomData))
+    }
+
+    pub fn ref_at(&self, idx: usize) -> Option<Ref<'s, &'s str, U>> {
+        (idx < self.names.len()).then_some(Ref(idx, 0, PhantomData))
+    }
+
+    pub fn get(&self, r: Ref<'s, &'s str, U>) -> &'s str {
+        self.names[r.0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Interned names in [`Ref::index`] order.
+    pub fn iter(&self) -> impl Iterator<Item = (Ref<'s, &'s str, U>, &'s str)> + '_ {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(idx, &n)| (Ref(idx, 0, PhantomData), n))
+    }
+}
+
+impl<'s, U> Index<Ref<'s, &'s str, U>> for StrInterner<'s, U> {
+    type Output = str;
+
+    fn index(&self, r: Ref<'s, &'s str, U>) -> &str {
+        self.get(r)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,4 +548,65 @@ mod tests {
 
         assert_eq!(ObjectStore::<u8>::new(&mut f), Err(()));
     }
+
+    #[test]
+    fn removal_and_generations() {
+        let mut v = vec![];
+        let mut s: ObjectStore<&str> = ObjectStore::new(&mut v).unwrap();
+
+        let [a, b, c]: [_; 3] = s.intern_all(["a", "b", "c"]).try_into().unwrap();
+        assert_eq!(s.len(), 3);
+        assert_eq!(b.index(), 1);
+
+        assert!(s.remove(b));
+        assert!(!s.remove(b));
+        assert_eq!(s.lookup(&"b"), None);
+        assert_eq!(s.try_get(b), None);
+        assert_eq!(s.ref_at(1), None);
+        assert_eq!(s.iter().map(|(_, &x)| x).collect::<Vec<_>>(), ["a", "c"]);
+
+        // The slot is reused but the old `Ref` stays stale:
+        let d = s.insert("d").unwrap();
+        assert_eq!((d.index(), d.generation()), (1, 1));
+        assert_ne!(b, d);
+        assert_eq!(s.try_get(b), None);
+        assert_eq!(s.ref_at(1), Some(d));
+        assert_eq!(s[d], "d");
+
+        assert_eq!(s.intern_all(["c", "d", "a"]), [c, d, a]);
+        assert_eq!((s.len(), s.capacity()), (3, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "stale Ref")]
+    fn stale_refs_panic() {
+        let mut v = vec![];
+        let mut s: ObjectStore<u8> = ObjectStore::new(&mut v).unwrap();
+
+        let a = s.insert(1).unwrap();
+        s.remove(a);
+        s.get(a);
+    }
+
+    #[test]
+    fn str_interner() {
+        let input = String::from("AAA = (BBB, CCC)\nBBB = (DDD, EEE)");
+        let mut names: StrInterner = StrInterner::new();
+
+        let ids = names.intern_all(
+            input
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .filter(|w| !w.is_empty()),
+        );
+        let ids: Vec<_> = ids.into_iter().map(Ref::index).collect();
+        assert_eq!(ids, [0, 1, 2, 1, 3, 4]);
+
+        let key = String::from("DDD");
+        let d = names.lookup(&key).unwrap();
+        assert_eq!(&names[d], "DDD");
+        assert_eq!(names.ref_at(3), Some(d));
+        assert_eq!(names.ref_at(5), None);
+        assert_eq!(names.len(), 5);
+        assert_eq!(names.iter().nth(4).map(|(_, n)| n), Some("EEE"));
+    }
 }
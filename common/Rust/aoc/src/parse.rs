@@ -0,0 +1,320 @@
+//! Runtime support for `#[derive(AocParse)]`.
+//!
+//! The derive generates [`FromStr`] and `TryFrom<&str>` impls from a format
+//! string (along with a [`FromSection`](crate::sections::FromSection) impl so
+//! the type can be used with [`Sections`](crate::sections::Sections)):
+//!
+//! ```
+//! use aoc::AocParse;
+//!
+//! #[derive(Debug, PartialEq, AocParse)]
+//! #[aoc(fmt = "{lo}-{hi} {ch}: {password}")]
+//! struct Line {
+//!     lo: usize,
+//!     hi: usize,
+//!     ch: char,
+//!     password: String,
+//! }
+//!
+//! let l: Line = "1-3 a: abcde".parse().unwrap();
+//! assert_eq!((l.lo, l.hi, l.ch), (1, 3, 'a'));
+//!
+//! let err = "1-x a: abcde".parse::<Line>().unwrap_err();
+//! assert_eq!(err.column, 3);
+//! ```
+//!
+//! Format strings are literal text and `{field}` placeholders (`{0}` for
+//! tuple fields; `{{` and `}}` for literal braces). A placeholder takes
+//! everything up to the next occurrence of the literal that follows it (or the
+//! rest of the input) and hands it to the field's `FromStr` impl; nested
+//! `AocParse` types have their error columns adjusted to point into the outer
+//! input.
+//!
+//! `Vec<T>` fields are split on the separator given after a colon
+//! (`{nums:, }`) or on whitespace if there isn't one.
+//!
+//! On enums every variant gets its own `#[aoc(fmt = "...")]` (unit variants
+//! default to their name). The variant whose format starts with the longest
+//! literal prefix of the input is the one that's parsed.
+
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{self, Display};
+#[allow(unused_imports)] // for docs
+use std::str::FromStr;
+
+/// Why (and where) an input didn't match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The type that was being parsed.
+    pub ty: &'static str,
+    /// 1-based, in characters.
+    pub column: usize,
+    pub message: String,
+    pub input: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "couldn't parse `{}` from {:?}: {} (at column {})",
+            self.ty, self.input, self.message, self.column
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+
+    /// Walks through the input as the generated code matches pieces of the
+    /// format string.
+    pub struct Cursor<'s> {
+        input: &'s str,
+        pos: usize,
+        ty: &'static str,
+    }
+
+    impl<'s> Cursor<'s> {
+        pub fn new(input: &'s str, ty: &'static str) -> Self {
+            Self { input, pos: 0, ty }
+        }
+
+        fn err(&self, byte_pos: usize, message: String) -> ParseError {
+            ParseError {
+                ty: self.ty,
+                column: self.input[..byte_pos].chars().count() + 1,
+                message,
+                input: self.input.to_string(),
+            }
+        }
+
+        fn rest(&self) -> &'s str {
+            &self.input[self.pos..]
+        }
+
+        pub fn literal(&mut self, lit: &str) -> Result<(), ParseError> {
+            if self.rest().starts_with(lit) {
+                self.pos += lit.len();
+                Ok(())
+            } else {
+                let got: String = self.rest().chars().take(lit.chars().count()).collect();
+                Err(self.err(self.pos, format!("expected {lit:?}, got {got:?}")))
+            }
+        }
+
+        /// Index of the option with the longest prefix of the remaining input.
+        pub fn pick(&self, prefixes: &[&str]) -> Result<usize, ParseError> {
+            prefixes
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| self.rest().starts_with(*p))
+                .min_by_key(|(_, p)| std::cmp::Reverse(p.len()))
+                .map(|(i, _)| i)
+                .ok_or_else(|| {
+                    let opts: Vec<_> = prefixes.iter().map(|p| format!("{p:?}")).collect();
+                    self.err(self.pos, format!("expected one of {}", opts.join(", ")))
+                })
+        }
+
+        /// The input up until `next` (or the end), and its starting offset.
+        fn until(
+            &mut self,
+            name: &str,
+            next: Option<&str>,
+        ) -> Result<(&'s str, usize), ParseError> {
+            let start = self.pos;
+            let len = match next {
+                Some(lit) => self
+                    .rest()
+                    .find(lit)
+                    .ok_or_else(|| self.err(start, format!("expected {lit:?} after `{name}`")))?,
+                None => self.rest().len(),
+            };
+
+            self.pos += len;
+            Ok((&self.input[start..start + len], start))
+        }
+
+        fn parse_at<T: FromStr>(&self, name: &str, s: &str, at: usize) -> Result<T, ParseError>
+        where
+            T::Err: Display + 'static,
+        {
+            s.parse().map_err(
+                |e: T::Err| match (&e as &dyn Any).downcast_ref::<ParseError>() {
+                    Some(inner) => {
+                        let prefix = self.input[..at].chars().count();
+                        ParseError {
+                            ty: self.ty,
+                            column: prefix + inner.column,
+                            message: format!("in `{name}`: {}", inner.message),
+                            input: self.input.to_string(),
+                        }
+                    }
+                    None => self.err(at, format!("invalid `{name}` ({s:?}): {e}")),
+                },
+            )
+        }
+
+        pub fn field<T: FromStr>(&mut self, name: &str, next: Option<&str>) -> Result<T, ParseError>
+        where
+            T::Err: Display + 'static,
+        {
+            let (s, at) = self.until(name, next)?;
+            self.parse_at(name, s, at)
+        }
+
+        pub fn vec_field<T: FromStr>(
+            &mut self,
+            name: &str,
+            sep: Option<&str>,
+            next: Option<&str>,
+        ) -> Result<Vec<T>, ParseError>
+        where
+            T::Err: Display + 'static,
+        {
+            let (s, at) = self.until(name, next)?;
+            let elems: Box<dyn Iterator<Item = &str>> = match sep {
+                _ if s.is_empty() => Box::new(std::iter::empty()),
+                Some(sep) => Box::new(s.split(sep)),
+                None => Box::new(s.split_whitespace()),
+            };
+
+            elems
+                .map(|e| {
+                    let offset = e.as_ptr() as usize - s.as_ptr() as usize;
+                    self.parse_at(name, e, at + offset)
+                })
+                .collect()
+        }
+
+        pub fn finish(self) -> Result<(), ParseError> {
+            if self.rest().is_empty() {
+                Ok(())
+            } else {
+                Err(self.err(self.pos, format!("unexpected trailing {:?}", self.rest())))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AocParse;
+
+    #[derive(Debug, PartialEq, AocParse)]
+    #[aoc(fmt = "{0},{1}")]
+    struct Point(i32, i32);
+
+    #[derive(Debug, PartialEq, AocParse)]
+    #[aoc(fmt = "{from} -> {to}")]
+    struct Segment {
+        from: Point,
+        to: Point,
+    }
+
+    #[derive(Debug, PartialEq, AocParse)]
+    #[aoc(fmt = "Valve {name} has flow rate={rate}; tunnels lead to valves {to:, }")]
+    struct Valve {
+        name: String,
+        rate: u32,
+        to: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, AocParse)]
+    enum Instr {
+        #[aoc(fmt = "noop")]
+        Noop,
+        #[aoc(fmt = "addx {0}")]
+        AddX(i64),
+        #[aoc(fmt = "turn on {from} through {to}")]
+        On {
+            from: Point,
+            to: Point,
+        },
+        #[aoc(fmt = "turn off {from} through {to}")]
+        Off {
+            from: Point,
+            to: Point,
+        },
+        Halt,
+    }
+
+    #[derive(Debug, PartialEq, AocParse)]
+    #[aoc(fmt = "{{{name}}}: {nums}")]
+    struct Braces {
+        name: char,
+        nums: Vec<u8>,
+    }
+
+    #[test]
+    fn structs() {
+        assert_eq!("3,-4".parse(), Ok(Point(3, -4)));
+        assert_eq!(
+            Segment::try_from("0,9 -> 5,9"),
+            Ok(Segment {
+                from: Point(0, 9),
+                to: Point(5, 9)
+            })
+        );
+
+        let v: Valve = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB"
+            .parse()
+            .unwrap();
+        assert_eq!(v.to, ["DD", "II", "BB"]);
+
+        assert_eq!(
+            "{x}: 1  2 3".parse(),
+            Ok(Braces {
+                name: 'x',
+                nums: vec![1, 2, 3]
+            })
+        );
+        assert!("{x}: ".parse::<Braces>().unwrap().nums.is_empty());
+    }
+
+    #[test]
+    fn enums() {
+        assert_eq!("noop".parse(), Ok(Instr::Noop));
+        assert_eq!("Halt".parse(), Ok(Instr::Halt));
+        assert_eq!("addx -7".parse(), Ok(Instr::AddX(-7)));
+        assert_eq!(
+            "turn off 1,2 through 3,4".parse(),
+            Ok(Instr::Off {
+                from: Point(1, 2),
+                to: Point(3, 4)
+            })
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let e = "3;4".parse::<Point>().unwrap_err();
+        assert_eq!((e.ty, e.column), ("Point", 1));
+        assert_eq!(e.message, r#"expected "," after `0`"#);
+
+        let e = "3,4 -> 5,x".parse::<Segment>().unwrap_err();
+        assert_eq!((e.ty, e.column), ("Segment", 10));
+        assert!(e.message.starts_with("in `to`: invalid `1` (\"x\")"));
+
+        let e = "3,4 => 5,6".parse::<Segment>().unwrap_err();
+        assert_eq!(e.column, 1);
+
+        let e = "3,4 -> 5,6 ".parse::<Segment>().unwrap_err();
+        assert_eq!(e.column, 10);
+
+        let e = "{é}: 1 2 300".parse::<Braces>().unwrap_err();
+        assert_eq!(e.column, 10);
+        assert!(e.to_string().contains("at column 10"));
+
+        let e = "toggle 0,0 through 1,1".parse::<Instr>().unwrap_err();
+        assert_eq!(e.column, 1);
+        assert!(e.message.starts_with("expected one of \"noop\", \"addx \""));
+
+        let e = "addx q".parse::<Instr>().unwrap_err();
+        assert_eq!(e.column, 6);
+    }
+}
@@ -0,0 +1,496 @@
+//! Quick charts for staring at puzzle data.
+//!
+//! The terminal renderers ([`sparkline`], [`Canvas`], [`braille_line`],
+//! [`braille_scatter`]) are always available and only produce `String`s, so
+//! they work fine over SSH. Writing PNG/SVG files (`line`, `scatter`,
+//! `histogram` and `Chart`) needs the `plot` feature.
+//!
+//! Everything takes plain iterators: `(x, y)` pairs for line/scatter charts
+//! (`.enumerate()` or `.accumulate(..)` output works as is) and bare values
+//! for sparklines and histograms.
+//!
+//! ```
+//! use aoc::plot;
+//!
+//! assert_eq!(plot::sparkline([1, 2, 3, 4, 5, 6, 7, 8]), "▁▂▃▄▅▆▇█");
+//! print!("{}", plot::braille_line((0..100).map(|x| (x, x * x)), 40, 10));
+//! ```
+
+use std::fmt::{self, Display};
+
+use num_traits::ToPrimitive;
+
+fn to_f64<T: ToPrimitive>(v: T) -> f64 {
+    v.to_f64().unwrap_or(f64::NAN)
+}
+
+fn points<X: ToPrimitive, Y: ToPrimitive>(
+    points: impl IntoIterator<Item = (X, Y)>,
+) -> Vec<(f64, f64)> {
+    points
+        .into_iter()
+        .map(|(x, y)| (to_f64(x), to_f64(y)))
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .collect()
+}
+
+/// `(min, max)` of the values; `None` if there aren't any.
+fn extent(it: impl IntoIterator<Item = f64>) -> Option<(f64, f64)> {
+    it.into_iter().fold(None, |acc, v| match acc {
+        None => Some((v, v)),
+        Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+    })
+}
+
+/// Maps `v` in `lo..=hi` onto `0..n`.
+fn scale(v: f64, (lo, hi): (f64, f64), n: usize) -> usize {
+    if hi <= lo || n == 0 {
+        0
+    } else {
+        ((v - lo) / (hi - lo) * (n - 1) as f64).round() as usize
+    }
+}
+
+/// Splits `values` into `bins` equal-width buckets spanning their range.
+///
+/// Yields `(start, end, count)` per bucket; the last bucket includes its end.
+pub fn histogram_bins<T: ToPrimitive>(
+    values: impl IntoIterator<Item = T>,
+    bins: usize,
+) -> Vec<(f64, f64, usize)> {
+    assert!(bins > 0, "need at least one bin");
+
+    let values: Vec<f64> = values
+        .into_iter()
+        .map(to_f64)
+        .filter(|v| v.is_finite())
+        .collect();
+    let Some((lo, hi)) = extent(values.iter().copied()) else {
+        return vec![];
+    };
+
+    let width = if hi > lo {
+        (hi - lo) / bins as f64
+    } else {
+        1.0
+    };
+    let mut counts = vec![0; bins];
+    for v in values {
+        let idx = ((v - lo) / width) as usize;
+        counts[idx.min(bins - 1)] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, n)| {
+            let start = lo + width * i as f64;
+            (start, start + width, n)
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A one-line bar chart of `values`, scaled so the smallest is `▁` and the
+/// largest `█`.
+///
+/// Values that aren't representable as finite `f64`s are left as spaces.
+pub fn sparkline<T: ToPrimitive>(values: impl IntoIterator<Item = T>) -> String {
+    let values: Vec<f64> = values.into_iter().map(to_f64).collect();
+    let range = extent(values.iter().copied().filter(|v| v.is_finite()));
+
+    values
+        .into_iter()
+        .map(|v| match range {
+            Some(r) if v.is_finite() => BARS[scale(v, r, BARS.len())],
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// A grid of [braille] characters, each holding 2×4 dots.
+///
+/// Dot coordinates start at the top left; a `cols × rows` canvas is
+/// `2 * cols` dots wide and `4 * rows` dots tall.
+///
+/// [braille]: https://en.wikipedia.org/wiki/Braille_Patterns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    cols: usize,
+    rows: usize,
+    cells: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            cells: vec![0; cols * rows],
+        }
+    }
+
+    /// Width in dots.
+    pub fn width(&self) -> usize {
+        self.cols * 2
+    }
+
+    /// Height in dots.
+    pub fn height(&self) -> usize {
+        self.rows * 4
+    }
+
+    /// The cell holding dot `(x, y)` and the dot's bit within it.
+    fn cell(&self, x: usize, y: usize) -> Option<(usize, u8)> {
+        // Braille numbers its dots down the left column first, then down the
+        // right; the bottom row (dots 7 and 8) was added later.
+        const BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+        (x < self.width() && y < self.height())
+            .then(|| ((y / 4) * self.cols + x / 2, BITS[x % 2][y % 4]))
+    }
+
+    /// Sets the dot at `(x, y)`; out of bounds dots are ignored.
+    pub fn set(&mut self, x: usize, y: usize) {
+        if let Some((idx, bit)) = self.cell(x, y) {
+            self.cells[idx] |= bit;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.cell(x, y)
+            .is_some_and(|(idx, bit)| self.cells[idx] & bit != 0)
+    }
+
+    /// Draws a straight line between two dots (inclusive).
+    pub fn line(&mut self, (x0, y0): (usize, usize), (x1, y1): (usize, usize)) {
+        let (mut x, mut y) = (x0 as isize, y0 as isize);
+        let (x1, y1) = (x1 as isize, y1 as isize);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut err = dx + dy;
+
+        loop {
+            self.set(x as usize, y as usize);
+            if (x, y) == (x1, y1) {
+                break;
+            }
+
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Scales `points` to fill the canvas (with `y` pointing up) and draws
+    /// them, optionally joined by lines.
+    fn plot(mut self, points: Vec<(f64, f64)>, join: bool) -> Self {
+        let xs = extent(points.iter().map(|p| p.0));
+        let ys = extent(points.iter().map(|p| p.1));
+        let (Some(xs), Some(ys)) = (xs, ys) else {
+            return self;
+        };
+
+        let (w, h) = (self.width(), self.height());
+        let dots = points
+            .into_iter()
+            .map(|(x, y)| (scale(x, xs, w), h.saturating_sub(1) - scale(y, ys, h)));
+
+        let mut prev = None;
+        for p in dots {
+            match prev {
+                Some(q) if join => self.line(q, p),
+                _ => self.set(p.0, p.1),
+            }
+            prev = Some(p);
+        }
+
+        self
+    }
+}
+
+impl Display for Canvas {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.cells.chunks(self.cols.max(1)).take(self.rows) {
+            for &bits in row {
+                let c = char::from_u32(0x2800 + bits as u32).unwrap();
+                write!(fmt, "{c}")?;
+            }
+            writeln!(fmt)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A line chart of `(x, y)` points on a `cols × rows` character [`Canvas`].
+pub fn braille_line<X: ToPrimitive, Y: ToPrimitive>(
+    points: impl IntoIterator<Item = (X, Y)>,
+    cols: usize,
+    rows: usize,
+) -> Canvas {
+    Canvas::new(cols, rows).plot(self::points(points), true)
+}
+
+/// A scatter plot of `(x, y)` points on a `cols × rows` character [`Canvas`].
+pub fn braille_scatter<X: ToPrimitive, Y: ToPrimitive>(
+    points: impl IntoIterator<Item = (X, Y)>,
+    cols: usize,
+    rows: usize,
+) -> Canvas {
+    Canvas::new(cols, rows).plot(self::points(points), false)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(feature = "plot")]
+pub use files::*;
+
+#[cfg(feature = "plot")]
+mod files {
+    use std::error::Error;
+    use std::ops::Range;
+    use std::path::{Path, PathBuf};
+
+    use num_traits::ToPrimitive;
+    use plotters::coord::Shift;
+    use plotters::prelude::*;
+
+    use super::{extent, histogram_bins, points};
+
+    pub type PlotResult = Result<(), Box<dyn Error>>;
+
+    enum Series {
+        Line(Vec<(f64, f64)>),
+        Scatter(Vec<(f64, f64)>),
+        Histogram(Vec<(f64, f64, usize)>),
+    }
+
+    /// A chart written to a file; SVG if the path ends in `.svg`, PNG
+    /// otherwise.
+    ///
+    /// The free functions ([`line`], [`scatter`], [`histogram`]) cover the
+    /// common case; use this to set a title or size first.
+    #[derive(Debug, Clone)]
+    pub struct Chart {
+        path: PathBuf,
+        title: Option<String>,
+        size: (u32, u32),
+    }
+
+    impl Chart {
+        pub fn new(path: impl AsRef<Path>) -> Self {
+            Self {
+                path: path.as_ref().to_path_buf(),
+                title: None,
+                size: (1280, 720),
+            }
+        }
+
+        pub fn title(mut self, title: impl Into<String>) -> Self {
+            self.title = Some(title.into());
+            self
+        }
+
+        pub fn size(mut self, width: u32, height: u32) -> Self {
+            self.size = (width, height);
+            self
+        }
+
+        pub fn line<X: ToPrimitive, Y: ToPrimitive>(
+            &self,
+            points: impl IntoIterator<Item = (X, Y)>,
+        ) -> PlotResult {
+            self.write(Series::Line(self::points(points)))
+        }
+
+        pub fn scatter<X: ToPrimitive, Y: ToPrimitive>(
+            &self,
+            points: impl IntoIterator<Item = (X, Y)>,
+        ) -> PlotResult {
+            self.write(Series::Scatter(self::points(points)))
+        }
+
+        /// See [`histogram_bins`](super::histogram_bins).
+        pub fn histogram<T: ToPrimitive>(
+            &self,
+            values: impl IntoIterator<Item = T>,
+            bins: usize,
+        ) -> PlotResult {
+            self.write(Series::Histogram(histogram_bins(values, bins)))
+        }
+
+        fn write(&self, series: Series) -> PlotResult {
+            let svg = self
+                .path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("svg"));
+
+            if svg {
+                self.draw(
+                    SVGBackend::new(&self.path, self.size).into_drawing_area(),
+                    series,
+                )
+            } else {
+                self.draw(
+                    BitMapBackend::new(&self.path, self.size).into_drawing_area(),
+                    series,
+                )
+            }
+        }
+
+        fn draw<DB: DrawingBackend>(
+            &self,
+            root: DrawingArea<DB, Shift>,
+            series: Series,
+        ) -> PlotResult
+        where
+            DB::ErrorType: 'static,
+        {
+            // Pads degenerate (single value) ranges so there's something to draw.
+            fn range(r: Option<(f64, f64)>) -> Result<Range<f64>, &'static str> {
+                match r.ok_or("nothing to plot")? {
+                    (lo, hi) if lo < hi => Ok(lo..hi),
+                    (v, _) => Ok(v - 1.0..v + 1.0),
+                }
+            }
+
+            let (xs, ys) = match &series {
+                Series::Line(p) | Series::Scatter(p) => {
+                    (extent(p.iter().map(|p| p.0)), extent(p.iter().map(|p| p.1)))
+                }
+                Series::Histogram(b) => (
+                    extent(b.iter().flat_map(|&(lo, hi, _)| [lo, hi])),
+                    extent(b.iter().flat_map(|&(.., n)| [0.0, n as f64])),
+                ),
+            };
+            let (xs, ys) = (range(xs)?, range(ys)?);
+
+            root.fill(&WHITE)?;
+            let mut builder = ChartBuilder::on(&root);
+            if let Some(title) = &self.title {
+                builder.caption(title, ("sans-serif", 30));
+            }
+            let mut chart = builder
+                .margin(10)
+                .x_label_area_size(40)
+                .y_label_area_size(60)
+                .build_cartesian_2d(xs, ys)?;
+            chart.configure_mesh().draw()?;
+
+            match series {
+                Series::Line(p) => {
+                    chart.draw_series(LineSeries::new(p, &RED))?;
+                }
+                Series::Scatter(p) => {
+                    chart.draw_series(p.into_iter().map(|p| Circle::new(p, 2, RED.filled())))?;
+                }
+                Series::Histogram(b) => {
+                    chart.draw_series(b.into_iter().map(|(lo, hi, n)| {
+                        Rectangle::new([(lo, 0.0), (hi, n as f64)], BLUE.filled())
+                    }))?;
+                }
+            }
+
+            root.present()?;
+            Ok(())
+        }
+    }
+
+    /// Writes a line chart of `(x, y)` points to `path` (PNG or SVG).
+    pub fn line<X: ToPrimitive, Y: ToPrimitive>(
+        path: impl AsRef<Path>,
+        points: impl IntoIterator<Item = (X, Y)>,
+    ) -> PlotResult {
+        Chart::new(path).line(points)
+    }
+
+    /// Writes a scatter plot of `(x, y)` points to `path` (PNG or SVG).
+    pub fn scatter<X: ToPrimitive, Y: ToPrimitive>(
+        path: impl AsRef<Path>,
+        points: impl IntoIterator<Item = (X, Y)>,
+    ) -> PlotResult {
+        Chart::new(path).scatter(points)
+    }
+
+    /// Writes a histogram of `values` split into `bins` buckets to `path`
+    /// (PNG or SVG).
+    pub fn histogram<T: ToPrimitive>(
+        path: impl AsRef<Path>,
+        values: impl IntoIterator<Item = T>,
+        bins: usize,
+    ) -> PlotResult {
+        Chart::new(path).histogram(values, bins)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparklines() {
+        assert_eq!(sparkline([1, 2, 3, 4, 5, 6, 7, 8]), "▁▂▃▄▅▆▇█");
+        assert_eq!(sparkline([0.0, f64::NAN, 10.0]), "▁ █");
+        assert_eq!(sparkline([3, 3]), "▁▁");
+        assert_eq!(sparkline(Vec::<u8>::new()), "");
+    }
+
+    #[test]
+    fn braille() {
+        let mut c = Canvas::new(2, 1);
+        c.set(0, 0);
+        c.set(3, 3);
+        c.set(4, 0);
+        assert!(c.get(3, 3) && !c.get(2, 3));
+        assert_eq!(c.to_string(), "⠁⢀\n");
+
+        // A diagonal from the bottom left to the top right.
+        let c = braille_line([(0, 0), (3, 7)], 2, 2);
+        assert_eq!(c.to_string(), "⠀⡜\n⡜⠀\n");
+        assert_eq!(
+            braille_scatter([(0, 0), (3, 7)], 2, 2).to_string(),
+            "⠀⠈\n⡀⠀\n"
+        );
+
+        assert_eq!(
+            braille_line(Vec::<(u8, u8)>::new(), 1, 1).to_string(),
+            "⠀\n"
+        );
+    }
+
+    #[test]
+    fn bins() {
+        let b = histogram_bins([0, 1, 2, 3, 4, 10], 2);
+        assert_eq!(b, [(0.0, 5.0, 5), (5.0, 10.0, 1)]);
+        assert_eq!(histogram_bins([7, 7], 3)[0], (7.0, 8.0, 2));
+    }
+
+    #[cfg(feature = "plot")]
+    #[test]
+    fn files() {
+        let dir = std::env::temp_dir();
+        let svg = dir.join("aoc-plot-test.svg");
+        let png = dir.join("aoc-plot-test.png");
+
+        Chart::new(&svg)
+            .title("squares")
+            .line((0..10).map(|x| (x, x * x)))
+            .unwrap();
+        assert!(std::fs::read_to_string(&svg).unwrap().contains("<svg"));
+
+        histogram(&png, [1, 1, 2, 3, 5, 8], 4).unwrap();
+        assert!(std::fs::metadata(&png).unwrap().len() > 0);
+
+        assert!(scatter(&svg, Vec::<(u8, u8)>::new()).is_err());
+    }
+}
@@ -0,0 +1,653 @@
+//! Running and cross-checking a day's solutions in other languages.
+//!
+//! Implementations live in the day's directory (`<year>/<day>/`), either as
+//! files with a known extension (`captcha.py`) or in a directory named after
+//! the language (`Python/captcha.py`, like 2017). Each one gets the input on
+//! stdin and should print its answers on stdout, ideally as `p1: <answer>`
+//! and `p2: <answer>` lines (`Part 1:` and friends are fine too); otherwise the
+//! last two non-empty lines are taken as the answers.
+//!
+//! Rust solutions are run through Cargo with `--stdin --output print`, as are
+//! Python scripts that use the `aoc` bindings. Languages whose toolchain isn't
+//! installed are skipped and implementations that take longer than the
+//! timeout (see [`TIMEOUT`]) are killed and count as failures.
+//!
+//! See [`check`] (and the `aoc` binary, which wraps it).
+
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cargo_metadata::MetadataCommand;
+
+/// How long [`check`] gives each implementation by default; this includes
+/// building it (i.e. `cargo run --release`).
+pub const TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How to run a [`Lang`]'s source files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runner {
+    /// `<cmd...> <file>`
+    Interpret(&'static [&'static str]),
+    /// `<cmd...> -o <exe> <file>`, then `<exe>`.
+    Compile(&'static [&'static str]),
+    /// The `[[bin]]` target with this source file, via `cargo run`.
+    Cargo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lang {
+    pub name: &'static str,
+    /// Directory names holding implementations in this language.
+    pub dirs: &'static [&'static str],
+    pub exts: &'static [&'static str],
+    pub runner: Runner,
+}
+
+impl Lang {
+    /// The program that has to be on the `PATH` to run this language.
+    pub fn tool(&self) -> &'static str {
+        match self.runner {
+            Runner::Interpret(cmd) | Runner::Compile(cmd) => cmd[0],
+            Runner::Cargo => "cargo",
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        find_on_path(self.tool()).is_some()
+    }
+}
+
+macro_rules! langs {
+    ($($name:literal [$($dir:literal),*] [$($ext:literal),*] => $runner:expr),* $(,)?) => {
+        /// Languages we know how to run.
+        pub const LANGS: &[Lang] = {
+            use Runner::*;
+            &[$(
+                Lang { name: $name, dirs: &[$($dir),*], exts: &[$($ext),*], runner: $runner },
+            )*]
+        };
+    };
+}
+
+langs! {
+    "Rust" ["Rust"] ["rs"] => Cargo,
+    "Python" ["Python"] ["py"] => Interpret(&["python3"]),
+    "Haskell" ["Haskell"] ["hs"] => Interpret(&["runghc"]),
+    "C" ["C"] ["c"] => Compile(&["cc", "-O2"]),
+    "C++" ["C++", "Cpp"] ["cpp", "cc", "cxx"] => Compile(&["c++", "-O2", "-std=c++17"]),
+    "Bash" ["Bash"] ["sh", "bash"] => Interpret(&["bash"]),
+    "JavaScript" ["JavaScript"] ["js", "mjs"] => Interpret(&["node"]),
+    "Scala" ["Scala"] ["scala"] => Interpret(&["scala"]),
+    "Ruby" ["Ruby"] ["rb"] => Interpret(&["ruby"]),
+    "Awk" ["Awk"] ["awk"] => Interpret(&["awk", "-f"]),
+    "jq" ["jq"] ["jq"] => Interpret(&["jq", "-R", "-s", "-r", "-f"]),
+    "Nix" ["Nix"] ["nix"] => Interpret(&["nix-instantiate", "--eval", "--strict"]),
+    "Zig" ["Zig"] ["zig"] => Interpret(&["zig", "run"]),
+    "Idris" ["Idris"] ["idr"] => Interpret(&["idris2", "--exec", "main"]),
+    "Uiua" ["Uiua"] ["ua"] => Interpret(&["uiua", "run"]),
+    "Prolog" ["Prolog"] ["pl"] => Interpret(&["swipl"]),
+}
+
+fn find_on_path(tool: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(tool))
+        .find(|p| p.is_file())
+}
+
+fn lang_for(path: &Path, dir_lang: Option<&'static Lang>) -> Option<&'static Lang> {
+    let ext = path.extension()?.to_str()?;
+    match dir_lang {
+        Some(lang) => lang.exts.contains(&ext).then_some(lang),
+        None => LANGS.iter().find(|l| l.exts.contains(&ext)),
+    }
+}
+
+/// One implementation of a day's solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impl {
+    pub lang: &'static Lang,
+    pub path: PathBuf,
+}
+
+/// Finds the implementations in a day's directory, sorted by language (in
+/// [`LANGS`] order) and then path.
+pub fn detect(day_dir: &Path) -> Vec<Impl> {
+    fn files(dir: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return vec![];
+        };
+        entries.filter_map(|e| e.ok()).map(|e| e.path()).collect()
+    }
+
+    let mut impls = vec![];
+    for path in files(day_dir) {
+        if path.is_dir() {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let Some(lang) = LANGS.iter().find(|l| l.dirs.contains(&name)) else {
+                continue;
+            };
+
+            impls.extend(
+                files(&path)
+                    .into_iter()
+                    .filter(|f| f.is_file() && lang_for(f, Some(lang)).is_some())
+                    .map(|path| Impl { lang, path }),
+            );
+        } else if let Some(lang) = lang_for(&path, None) {
+            impls.push(Impl { lang, path });
+        }
+    }
+
+    let rank = |i: &Impl| LANGS.iter().position(|l| l == i.lang);
+    impls.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.path.cmp(&b.path)));
+    impls
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    pub p1: Option<String>,
+    pub p2: Option<String>,
+}
+
+/// Pulls the answers out of an implementation's output; see the
+/// [module docs](self).
+pub fn parse_answers(stdout: &str) -> Answers {
+    fn labelled(line: &str) -> Option<(u8, &str)> {
+        let lower = line.to_ascii_lowercase();
+        let rest = lower
+            .strip_prefix("part")
+            .or_else(|| lower.strip_prefix('p'))?;
+        let digits = rest.trim_start();
+        let part = match digits.as_bytes().first()? {
+            b'1' => 1,
+            b'2' => 2,
+            _ => return None,
+        };
+        let sep = digits[1..].trim_start();
+        if !sep.starts_with([':', '=']) {
+            return None;
+        }
+
+        // Same offset in the original line (lowercasing ASCII keeps lengths).
+        let value = &line[line.len() - sep.len() + 1..];
+        Some((part, value.trim()))
+    }
+
+    let mut ans = Answers::default();
+    for (part, val) in stdout.lines().filter_map(labelled) {
+        let slot = if part == 1 { &mut ans.p1 } else { &mut ans.p2 };
+        *slot = Some(val.to_string());
+    }
+
+    if ans == Answers::default() {
+        let mut lines = stdout.lines().map(str::trim).filter(|l| !l.is_empty());
+        let (last, second_last) = (lines.next_back(), lines.next_back());
+        ans = match (second_last, last) {
+            (Some(a), Some(b)) => Answers {
+                p1: Some(a.into()),
+                p2: Some(b.into()),
+            },
+            (None, a) => Answers {
+                p1: a.map(Into::into),
+                p2: None,
+            },
+            _ => unreachable!(),
+        };
+    }
+
+    ans
+}
+
+/// What happened when running an [`Impl`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Ran(Answers),
+    Failed(String),
+    Skipped(String),
+}
+
+/// Runs `cmd` with `input` on stdin; errors if it can't be started or takes
+/// longer than `timeout` (in which case it, and everything it started, is
+/// killed).
+fn output_with_timeout(mut cmd: Command, input: &str, timeout: Duration) -> Result<Output, String> {
+    // Put the program in its own process group so that we can get rid of
+    // whatever it starts too (i.e. the solution `cargo run` is running).
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("couldn't start `{:?}`: {e}", cmd.get_program()))?;
+
+    // Write from another thread so a chatty program can't fill its stdout
+    // pipe while we're blocked on its stdin. Write errors are ignored:
+    // programs that don't read all their input close stdin early.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+
+    // Likewise, the output is read on other threads so that we can keep an
+    // eye on the clock.
+    fn drain(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut buf = vec![];
+            let _ = pipe.read_to_end(&mut buf);
+            buf
+        })
+    }
+    let stdout = drain(child.stdout.take().unwrap());
+    let stderr = drain(child.stderr.take().unwrap());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            // The readers are left behind: anything the program started may
+            // still be holding its pipes open.
+            #[cfg(unix)]
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("timed out after {timeout:?}"));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let _ = writer.join();
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn run_with_input(cmd: Command, input: &str, timeout: Duration) -> Result<Output, String> {
+    let out = output_with_timeout(cmd, input, timeout)?;
+    if out.status.success() {
+        Ok(out)
+    } else {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let last = stderr.lines().rev().find(|l| !l.trim().is_empty());
+        Err(match last {
+            Some(line) => format!("{} ({})", out.status, line.trim()),
+            None => out.status.to_string(),
+        })
+    }
+}
+
+impl Impl {
+    /// Python scripts that use the `aoc` bindings read their input through the
+    /// Rust client and so need the same flags as Rust solutions.
+    fn uses_aoc_client(&self) -> bool {
+        match self.lang.runner {
+            Runner::Cargo => true,
+            _ if self.lang.name == "Python" => fs::read_to_string(&self.path)
+                .map(|src| src.contains("from aoc") || src.contains("import aoc"))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Where [`Runner::Compile`] languages put the executable.
+    fn exe(&self) -> PathBuf {
+        env::temp_dir().join(format!(
+            "aoc-polyglot-{}-{}",
+            std::process::id(),
+            self.path.file_stem().unwrap_or_default().to_string_lossy(),
+        ))
+    }
+
+    /// Makes the command to run; for [`Runner::Compile`] languages this does
+    /// the compiling (which gets `timeout` too).
+    fn command(&self, timeout: Duration) -> Result<Command, Outcome> {
+        if !self.lang.is_available() {
+            return Err(Outcome::Skipped(format!(
+                "`{}` not found",
+                self.lang.tool()
+            )));
+        }
+
+        // We run things from the implementation's directory so the path has
+        // to work from there too.
+        let path = self
+            .path
+            .canonicalize()
+            .map_err(|e| Outcome::Failed(format!("`{}`: {e}", self.path.display())))?;
+
+        let mut cmd = match self.lang.runner {
+            Runner::Interpret(args) => {
+                let mut cmd = Command::new(args[0]);
+                cmd.args(&args[1..]).arg(&path);
+                cmd
+            }
+            Runner::Compile(args) => {
+                let exe = self.exe();
+                let mut compile = Command::new(args[0]);
+                compile.args(&args[1..]).arg("-o").arg(&exe).arg(&path);
+                let out = output_with_timeout(compile, "", timeout)
+                    .map_err(|e| Outcome::Failed(format!("compile error: {e}")))?;
+                if !out.status.success() {
+                    let err = String::from_utf8_lossy(&out.stderr);
+                    let first = err.lines().next().unwrap_or("").trim().to_string();
+                    return Err(Outcome::Failed(format!("compile error: {first}")));
+                }
+
+                Command::new(exe)
+            }
+            Runner::Cargo => {
+                let bin = cargo_bin(&path)
+                    .ok_or_else(|| Outcome::Skipped("not a Cargo `[[bin]]`".into()))?;
+                let mut cmd = Command::new("cargo");
+                cmd.args(["run", "--quiet", "--release", "--manifest-path"])
+                    .arg(bin.manifest)
+                    .args(["--bin", &bin.name, "--"])
+                    .args(bin.args);
+                cmd
+            }
+        };
+
+        if self.uses_aoc_client() {
+            cmd.args(["--stdin", "--output", "print"]);
+        }
+        if let Some(dir) = path.parent() {
+            cmd.current_dir(dir);
+        }
+
+        Ok(cmd)
+    }
+
+    /// Runs the implementation on `input`; it fails if it takes longer than
+    /// `timeout`.
+    pub fn run(&self, input: &str, timeout: Duration) -> Outcome {
+        let outcome = match self.command(timeout) {
+            Err(outcome) => outcome,
+            Ok(cmd) => match run_with_input(cmd, input, timeout) {
+                Ok(out) => Outcome::Ran(parse_answers(&String::from_utf8_lossy(&out.stdout))),
+                Err(err) => Outcome::Failed(err),
+            },
+        };
+
+        if let Runner::Compile(_) = self.lang.runner {
+            let _ = fs::remove_file(self.exe());
+        }
+        outcome
+    }
+}
+
+struct CargoBin {
+    manifest: PathBuf,
+    name: String,
+    args: Vec<String>,
+}
+
+/// The binary target (and its package's manifest) built from `src`.
+///
+/// Days registered with `#[aoc::solution]` are run through their package's
+/// `aoc::main!()` binary instead, which takes the year and day (from the
+/// `<year>/<day>/` path, like `sub!`) as arguments.
+fn cargo_bin(src: &Path) -> Option<CargoBin> {
+    let src = src.canonicalize().ok()?;
+    let meta = MetadataCommand::new()
+        .current_dir(src.parent()?)
+        .no_deps()
+        .exec()
+        .ok()?;
+
+    let contains = |path: &Path, pat: &str| fs::read_to_string(path).is_ok_and(|s| s.contains(pat));
+    let registered = contains(&src, "solution(");
+    let year_day = || -> Option<Vec<String>> {
+        let day = src.parent()?;
+        let year = day.parent()?;
+        [year, day]
+            .iter()
+            .map(|p| Some(p.file_name()?.to_str()?.parse::<u16>().ok()?.to_string()))
+            .collect()
+    };
+
+    meta.packages.into_iter().find_map(|pkg| {
+        let bins = || {
+            pkg.targets
+                .iter()
+                .filter(|t| t.kind.iter().any(|k| k == "bin"))
+        };
+        let (bin, args) = match bins()
+            .find(|t| t.src_path.canonicalize().ok().as_deref() == Some(src.as_path()))
+        {
+            Some(bin) => (bin, vec![]),
+            None if registered && src.starts_with(pkg.manifest_path.parent()?) => {
+                let bin = bins().find(|t| contains(t.src_path.as_std_path(), "main!("))?;
+                (bin, year_day()?)
+            }
+            None => return None,
+        };
+
+        Some(CargoBin {
+            manifest: pkg.manifest_path.clone().into(),
+            name: bin.name.clone(),
+            args,
+        })
+    })
+}
+
+/// The results of [`check`]ing one day.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub year: u16,
+    pub day: u8,
+    pub results: Vec<(Impl, Outcome)>,
+    /// The answers everything is compared against: the (first) Rust
+    /// solution's if it ran, otherwise the first implementation that did.
+    pub reference: Option<usize>,
+}
+
+impl Report {
+    fn answers(&self, idx: usize) -> Option<&Answers> {
+        match &self.results.get(idx)?.1 {
+            Outcome::Ran(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Whether the implementation at `idx` agrees with the reference.
+    pub fn agrees(&self, idx: usize) -> Option<bool> {
+        Some(self.answers(idx)? == self.answers(self.reference?)?)
+    }
+
+    /// `true` if every implementation that ran agrees with the reference.
+    pub fn is_consistent(&self) -> bool {
+        (0..self.results.len()).all(|i| self.agrees(i) != Some(false))
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(fmt, "{} day {:02}:", self.year, self.day)?;
+        if self.results.is_empty() {
+            return writeln!(fmt, "  (no implementations found)");
+        }
+
+        let width = self
+            .results
+            .iter()
+            .map(|(i, _)| i.path.file_name().map_or(0, |n| n.len()) + i.lang.name.len() + 3)
+            .max()
+            .unwrap_or(0);
+
+        for (idx, (imp, outcome)) in self.results.iter().enumerate() {
+            let file = imp.path.file_name().unwrap_or_default().to_string_lossy();
+            let name = format!("{} ({})", imp.lang.name, file);
+            write!(fmt, "  {name:width$}  ")?;
+
+            let show = |a: &Option<String>| a.clone().unwrap_or_else(|| "?".into());
+            match outcome {
+                Outcome::Ran(a) => {
+                    write!(fmt, "p1: {:<16} p2: {:<16}", show(&a.p1), show(&a.p2))?;
+                    match (Some(idx) == self.reference, self.agrees(idx)) {
+                        (true, _) => writeln!(fmt, " (reference)")?,
+                        (_, Some(true)) => writeln!(fmt, " ✓")?,
+                        _ => writeln!(fmt, " ✗ mismatch")?,
+                    }
+                }
+                Outcome::Failed(err) => writeln!(fmt, "failed: {err}")?,
+                Outcome::Skipped(why) => writeln!(fmt, "skipped: {why}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs every implementation in `<root>/<year>/<day>` on `input` and compares
+/// their answers; each one gets `timeout` (i.e. [`TIMEOUT`]) to finish.
+pub fn check(root: &Path, year: u16, day: u8, input: &str, timeout: Duration) -> Report {
+    let dir = root.join(year.to_string()).join(format!("{day:02}"));
+    let results: Vec<_> = detect(&dir)
+        .into_iter()
+        .map(|imp| {
+            let outcome = imp.run(input, timeout);
+            (imp, outcome)
+        })
+        .collect();
+
+    let ran = |(_, (_, o)): &(usize, &(Impl, Outcome))| matches!(o, Outcome::Ran(_));
+    let reference = results
+        .iter()
+        .enumerate()
+        .filter(ran)
+        .find(|(_, (i, _))| i.lang.runner == Runner::Cargo)
+        .or_else(|| results.iter().enumerate().find(ran))
+        .map(|(idx, _)| idx);
+
+    Report {
+        year,
+        day,
+        results,
+        reference,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers() {
+        let a = |p1: &str, p2: &str| Answers {
+            p1: Some(p1.into()),
+            p2: Some(p2.into()),
+        };
+
+        assert_eq!(parse_answers("p1:  1044\np2:  1054\n"), a("1044", "1054"));
+        assert_eq!(
+            parse_answers("debug\nP1: 12\nPart 2 = Some Text\n"),
+            a("12", "Some Text")
+        );
+        assert_eq!(parse_answers("noise\n\n12\n34\n\n"), a("12", "34"));
+        assert_eq!(
+            parse_answers("42\n"),
+            Answers {
+                p1: Some("42".into()),
+                p2: None
+            }
+        );
+        assert_eq!(parse_answers("pipe: 3\n7\n"), a("pipe: 3", "7"));
+        assert_eq!(parse_answers(""), Answers::default());
+    }
+
+    #[test]
+    fn detection() {
+        let dir = env::temp_dir().join(format!("aoc-polyglot-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for f in [
+            "Python/a.py",
+            "Haskell/a.hs",
+            "Haskell/README",
+            "b.c",
+            "input",
+            "x.rs",
+        ] {
+            let path = dir.join(f);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let found: Vec<_> = detect(&dir)
+            .into_iter()
+            .map(|i| (i.lang.name, i.path.strip_prefix(&dir).unwrap().to_owned()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Rust", "x.rs".into()),
+                ("Python", "Python/a.py".into()),
+                ("Haskell", "Haskell/a.hs".into()),
+                ("C", "b.c".into()),
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cross_check() {
+        if find_on_path("bash").is_none() {
+            return;
+        }
+
+        // Implementations are run from their own directory; relative roots
+        // (i.e. `.`) should still work. Tests run in the package's directory.
+        assert_eq!(
+            env::current_dir().unwrap(),
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+        );
+        let relative = PathBuf::from(format!(".aoc-polyglot-check-{}", std::process::id()));
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(&relative);
+        let day = dir.join("2017").join("01");
+        fs::create_dir_all(day.join("Bash")).unwrap();
+        let script = |name: &str, body: &str| fs::write(day.join(name), body).unwrap();
+        script(
+            "Bash/good.sh",
+            "read x; echo \"p1: $x\"; echo \"p2: $((x * 2))\"",
+        );
+        script("Bash/wrong.sh", "read x; echo $x; echo 0");
+        script("Bash/broken.sh", "echo oops >&2; exit 3");
+        script("Bash/slow.sh", "(sleep 2; touch orphaned) & sleep 10");
+
+        let report = check(&relative, 2017, 1, "21\n", Duration::from_secs(1));
+        let outcomes: Vec<_> = report.results.iter().map(|(_, o)| o.clone()).collect();
+        assert!(matches!(&outcomes[0], Outcome::Failed(e) if e.contains("oops")));
+        assert_eq!(
+            outcomes[1],
+            Outcome::Ran(Answers {
+                p1: Some("21".into()),
+                p2: Some("42".into())
+            })
+        );
+        assert_eq!(report.reference, Some(1));
+        assert!(matches!(&outcomes[2], Outcome::Failed(e) if e.contains("timed out")));
+        assert_eq!(report.agrees(3), Some(false));
+        assert!(!report.is_consistent());
+        assert!(report.to_string().contains("✗ mismatch"));
+
+        // The slow one's `sleep` got killed too:
+        thread::sleep(Duration::from_secs(2));
+        assert!(!day.join("Bash/orphaned").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
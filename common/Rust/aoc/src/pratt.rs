@@ -0,0 +1,627 @@
+//! A Pratt (precedence climbing) parser for infix expressions.
+//!
+//! The operators are data: an [`OpTable`] maps symbols to a precedence, an
+//! associativity and an `Op` value of your choosing. Swapping the table is all
+//! it takes to parse the same text under different rules (2020/18):
+//!
+//! ```
+//! use aoc::expr::BinOp::{Add, Mul};
+//! use aoc::pratt::{eval, Arith, Assoc::Left, OpTable};
+//!
+//! let same = OpTable::new()
+//!     .infix("+", 1, Left, Arith::Bin(Add))
+//!     .infix("*", 1, Left, Arith::Bin(Mul));
+//! let add_first = OpTable::new()
+//!     .infix("+", 2, Left, Arith::Bin(Add))
+//!     .infix("*", 1, Left, Arith::Bin(Mul));
+//!
+//! let no_vars = |_: &str| None;
+//! assert_eq!(eval::<i64>(&same, "2 * 3 + (4 * 5)", no_vars), Ok(26));
+//! assert_eq!(eval::<i64>(&add_first, "2 * 3 + (4 * 5)", no_vars), Ok(46));
+//! ```
+//!
+//! What gets built is up to a [`Builder`]: [`Exprs`] builds nodes in the
+//! shared expression DAG and [`Eval`] evaluates as it goes.
+//!
+//! The lexer is deliberately simple: whitespace is skipped, runs of digits are
+//! numbers, `[A-Za-z_][A-Za-z0-9_]*` are identifiers, `(` and `)` group, and
+//! anything else has to be one of the table's symbols (longest match wins).
+
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use crate::expr::{BinOp, ExprId, Exprs, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+/// The operators a parser knows about.
+///
+/// Precedences are relative: higher binds tighter. A symbol can be both a
+/// prefix operator and an infix or postfix operator (i.e. `-`).
+#[derive(Debug, Clone)]
+pub struct OpTable<Op> {
+    prefix: Vec<(String, u8, Op)>,
+    infix: Vec<(String, u8, Assoc, Op)>,
+    postfix: Vec<(String, u8, Op)>,
+}
+
+impl<Op> Default for OpTable<Op> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Op> OpTable<Op> {
+    pub fn new() -> Self {
+        Self {
+            prefix: vec![],
+            infix: vec![],
+            postfix: vec![],
+        }
+    }
+
+    pub fn prefix(mut self, sym: impl Into<String>, prec: u8, op: Op) -> Self {
+        self.prefix.push((sym.into(), prec, op));
+        self
+    }
+
+    pub fn infix(mut self, sym: impl Into<String>, prec: u8, assoc: Assoc, op: Op) -> Self {
+        self.infix.push((sym.into(), prec, assoc, op));
+        self
+    }
+
+    pub fn postfix(mut self, sym: impl Into<String>, prec: u8, op: Op) -> Self {
+        self.postfix.push((sym.into(), prec, op));
+        self
+    }
+
+    fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.prefix
+            .iter()
+            .map(|(s, ..)| s)
+            .chain(self.infix.iter().map(|(s, ..)| s))
+            .chain(self.postfix.iter().map(|(s, ..)| s))
+            .map(|s| s.as_str())
+    }
+
+    // Binding powers: `2 * prec (+ 1)` so that associativity can break ties.
+
+    fn prefix_bp(&self, sym: &str) -> Option<(u32, &Op)> {
+        self.prefix
+            .iter()
+            .find(|(s, ..)| s == sym)
+            .map(|(_, p, op)| (2 * *p as u32 + 1, op))
+    }
+
+    fn infix_bp(&self, sym: &str) -> Option<(u32, u32, &Op)> {
+        self.infix
+            .iter()
+            .find(|(s, ..)| s == sym)
+            .map(|(_, p, a, op)| {
+                let p = 2 * *p as u32;
+                match a {
+                    Assoc::Left => (p, p + 1, op),
+                    Assoc::Right => (p + 1, p, op),
+                }
+            })
+    }
+
+    fn postfix_bp(&self, sym: &str) -> Option<(u32, &Op)> {
+        self.postfix
+            .iter()
+            .find(|(s, ..)| s == sym)
+            .map(|(_, p, op)| (2 * *p as u32, op))
+    }
+}
+
+/// Operators understood by the builtin builders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Arith {
+    Bin(BinOp),
+    Neg,
+}
+
+impl OpTable<Arith> {
+    /// The usual: `==` below `+`/`-` below `*`/`/`/`%`, all left
+    /// associative, with prefix `-` binding tightest.
+    pub fn arithmetic() -> Self {
+        use Assoc::Left;
+        use BinOp::*;
+
+        Self::new()
+            .infix("==", 1, Left, Arith::Bin(Eql))
+            .infix("+", 2, Left, Arith::Bin(Add))
+            .infix("-", 2, Left, Arith::Bin(Sub))
+            .infix("*", 3, Left, Arith::Bin(Mul))
+            .infix("/", 3, Left, Arith::Bin(Div))
+            .infix("%", 3, Left, Arith::Bin(Rem))
+            .prefix("-", 4, Arith::Neg)
+    }
+}
+
+/// Turns parsed pieces into an output. Errors are plain messages; the parser
+/// attaches the span of the offending token.
+pub trait Builder<Op> {
+    type Output;
+
+    fn num(&mut self, text: &str) -> Result<Self::Output, String>;
+
+    fn ident(&mut self, name: &str) -> Result<Self::Output, String> {
+        Err(format!("unexpected identifier `{name}`"))
+    }
+
+    fn prefix(&mut self, op: &Op, x: Self::Output) -> Result<Self::Output, String>;
+
+    fn infix(&mut self, op: &Op, l: Self::Output, r: Self::Output) -> Result<Self::Output, String>;
+
+    fn postfix(&mut self, _op: &Op, _x: Self::Output) -> Result<Self::Output, String> {
+        Err("postfix operators aren't supported".to_string())
+    }
+}
+
+impl<T: Value> Builder<Arith> for Exprs<T> {
+    type Output = ExprId;
+
+    fn num(&mut self, text: &str) -> Result<ExprId, String> {
+        let n = T::from_str_radix(text, 10).map_err(|_| format!("`{text}` is out of range"))?;
+        Ok(self.constant(n))
+    }
+
+    fn ident(&mut self, name: &str) -> Result<ExprId, String> {
+        Ok(self.sym(name))
+    }
+
+    fn prefix(&mut self, op: &Arith, x: ExprId) -> Result<ExprId, String> {
+        match op {
+            Arith::Neg => Ok(self.neg(x)),
+            Arith::Bin(b) => Err(format!("`{b}` isn't a prefix operator")),
+        }
+    }
+
+    fn infix(&mut self, op: &Arith, l: ExprId, r: ExprId) -> Result<ExprId, String> {
+        match op {
+            Arith::Bin(b) => Ok(self.bin(*b, l, r)),
+            Arith::Neg => Err("negation isn't an infix operator".to_string()),
+        }
+    }
+}
+
+/// Evaluates directly, looking variables up with `F`.
+pub struct Eval<T, F>(F, PhantomData<T>);
+
+impl<T: Value, F: FnMut(&str) -> Option<T>> Eval<T, F> {
+    pub fn new(vars: F) -> Self {
+        Self(vars, PhantomData)
+    }
+}
+
+impl<T: Value, F: FnMut(&str) -> Option<T>> Builder<Arith> for Eval<T, F> {
+    type Output = T;
+
+    fn num(&mut self, text: &str) -> Result<T, String> {
+        T::from_str_radix(text, 10).map_err(|_| format!("`{text}` is out of range"))
+    }
+
+    fn ident(&mut self, name: &str) -> Result<T, String> {
+        (self.0)(name).ok_or_else(|| format!("unbound variable `{name}`"))
+    }
+
+    fn prefix(&mut self, op: &Arith, x: T) -> Result<T, String> {
+        match op {
+            Arith::Neg => x.checked_neg().ok_or_else(|| "overflow".to_string()),
+            Arith::Bin(b) => Err(format!("`{b}` isn't a prefix operator")),
+        }
+    }
+
+    fn infix(&mut self, op: &Arith, l: T, r: T) -> Result<T, String> {
+        match op {
+            Arith::Bin(b) => b
+                .apply(l, r)
+                .ok_or_else(|| format!("`{l} {b} {r}` overflows or divides by zero")),
+            Arith::Neg => Err("negation isn't an infix operator".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    /// Got an operand (or `(`) where an operator was expected.
+    ExpectedOperator,
+    /// Got an operator (or `)`) where an operand was expected.
+    ExpectedOperand,
+    UnmatchedParen,
+    /// The symbol exists in the table but not in this position.
+    NotInfix(String),
+    /// An error from the [`Builder`].
+    Build(String),
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ErrorKind::*;
+        match self {
+            UnexpectedChar(c) => write!(f, "unexpected character `{c}`"),
+            UnexpectedEnd => write!(f, "unexpected end of input"),
+            ExpectedOperator => write!(f, "expected an operator"),
+            ExpectedOperand => write!(f, "expected an operand"),
+            UnmatchedParen => write!(f, "unmatched parenthesis"),
+            NotInfix(s) => write!(f, "`{s}` can't be used as an infix or postfix operator"),
+            Build(m) => f.write_str(m),
+        }
+    }
+}
+
+/// A parse error along with the byte range in the source that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ErrorKind,
+    pub span: Range<usize>,
+}
+
+impl ParseError {
+    /// The offending line of `src` with the span underlined.
+    pub fn render(&self, src: &str) -> String {
+        let line_start = src[..self.span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[line_start..]
+            .find('\n')
+            .map_or(src.len(), |i| line_start + i);
+
+        let col = src[line_start..self.span.start].chars().count();
+        let width = src[self.span.start..self.span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        format!(
+            "{}\n{}{} {}",
+            &src[line_start..line_end],
+            " ".repeat(col),
+            "^".repeat(width),
+            self.kind
+        )
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tok<'s> {
+    Num(&'s str),
+    Ident(&'s str),
+    Op(&'s str),
+    Open,
+    Close,
+    End,
+}
+
+struct Parser<'t, 's, Op, B> {
+    table: &'t OpTable<Op>,
+    src: &'s str,
+    pos: usize,
+    peeked: Option<(Tok<'s>, Range<usize>)>,
+    builder: &'t mut B,
+}
+
+impl<'t, 's, Op, B: Builder<Op>> Parser<'t, 's, Op, B> {
+    fn lex(&self) -> Result<(Tok<'s>, Range<usize>), ParseError> {
+        let rest = &self.src[self.pos..];
+        let start = self.pos + (rest.len() - rest.trim_start().len());
+        let rest = &self.src[start..];
+
+        let Some(c) = rest.chars().next() else {
+            return Ok((Tok::End, start..start));
+        };
+
+        let word = |pred: fn(char) -> bool| {
+            let len = rest.find(|c: char| !pred(c)).unwrap_or(rest.len());
+            (&rest[..len], start..start + len)
+        };
+        let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+        // Operators first so that tables can use words (`and`) as symbols:
+        let op = self
+            .table
+            .symbols()
+            .filter(|s| !s.is_empty() && rest.starts_with(s))
+            .filter(|s| !s.ends_with(is_ident) || !rest[s.len()..].starts_with(is_ident))
+            .max_by_key(|s| s.len());
+
+        if let Some(op) = op {
+            return Ok((Tok::Op(&rest[..op.len()]), start..start + op.len()));
+        }
+
+        Ok(match c {
+            '(' => (Tok::Open, start..start + 1),
+            ')' => (Tok::Close, start..start + 1),
+            '0'..='9' => {
+                let (w, span) = word(|c| c.is_ascii_digit());
+                (Tok::Num(w), span)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let (w, span) = word(is_ident);
+                (Tok::Ident(w), span)
+            }
+            c => {
+                return Err(ParseError {
+                    kind: ErrorKind::UnexpectedChar(c),
+                    span: start..start + c.len_utf8(),
+                })
+            }
+        })
+    }
+
+    fn peek(&mut self) -> Result<(Tok<'s>, Range<usize>), ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+
+        Ok(self.peeked.clone().unwrap())
+    }
+
+    fn next(&mut self) -> Result<(Tok<'s>, Range<usize>), ParseError> {
+        let t = self.peek()?;
+        self.peeked = None;
+        self.pos = t.1.end;
+        Ok(t)
+    }
+
+    fn expr(&mut self, min_bp: u32) -> Result<B::Output, ParseError> {
+        let err = |kind, span| Err(ParseError { kind, span });
+        let build = |res: Result<B::Output, String>, span: Range<usize>| {
+            res.map_err(|m| ParseError {
+                kind: ErrorKind::Build(m),
+                span,
+            })
+        };
+
+        let (tok, span) = self.next()?;
+        let mut lhs = match tok {
+            Tok::Num(n) => build(self.builder.num(n), span)?,
+            Tok::Ident(i) => build(self.builder.ident(i), span)?,
+            Tok::Open => {
+                let inner = self.expr(0)?;
+                match self.next()? {
+                    (Tok::Close, _) => inner,
+                    (Tok::End, _) => return err(ErrorKind::UnmatchedParen, span),
+                    _ => unreachable!(),
+                }
+            }
+            Tok::Op(sym) => match self.table.prefix_bp(sym) {
+                Some((r_bp, op)) => {
+                    let x = self.expr(r_bp)?;
+                    build(self.builder.prefix(op, x), span)?
+                }
+                None => return err(ErrorKind::ExpectedOperand, span),
+            },
+            Tok::Close => return err(ErrorKind::ExpectedOperand, span),
+            Tok::End => return err(ErrorKind::UnexpectedEnd, span),
+        };
+
+        loop {
+            let (tok, span) = self.peek()?;
+            let sym = match tok {
+                Tok::End | Tok::Close => break,
+                Tok::Op(sym) => sym,
+                Tok::Num(_) | Tok::Ident(_) | Tok::Open => {
+                    return err(ErrorKind::ExpectedOperator, span)
+                }
+            };
+
+            if let Some((l_bp, op)) = self.table.postfix_bp(sym) {
+                if l_bp < min_bp {
+                    break;
+                }
+
+                self.next()?;
+                lhs = build(self.builder.postfix(op, lhs), span)?;
+            } else if let Some((l_bp, r_bp, op)) = self.table.infix_bp(sym) {
+                if l_bp < min_bp {
+                    break;
+                }
+
+                self.next()?;
+                let rhs = self.expr(r_bp)?;
+                lhs = build(self.builder.infix(op, lhs, rhs), span)?;
+            } else {
+                return err(ErrorKind::NotInfix(sym.to_string()), span);
+            }
+        }
+
+        Ok(lhs)
+    }
+}
+
+/// Parses all of `src` using the operators in `table`.
+pub fn parse<Op, B: Builder<Op>>(
+    table: &OpTable<Op>,
+    src: &str,
+    builder: &mut B,
+) -> Result<B::Output, ParseError> {
+    let mut p = Parser {
+        table,
+        src,
+        pos: 0,
+        peeked: None,
+        builder,
+    };
+
+    let out = p.expr(0)?;
+    match p.next()? {
+        (Tok::End, _) => Ok(out),
+        (_, span) => Err(ParseError {
+            kind: ErrorKind::UnmatchedParen,
+            span,
+        }),
+    }
+}
+
+/// Parses and evaluates `src`; `vars` supplies the values of identifiers.
+pub fn eval<T: Value>(
+    table: &OpTable<Arith>,
+    src: &str,
+    vars: impl FnMut(&str) -> Option<T>,
+) -> Result<T, ParseError> {
+    parse(table, src, &mut Eval::new(vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Assoc::*;
+
+    /// Renders the tree as an s-expression.
+    struct SExpr;
+
+    impl Builder<&'static str> for SExpr {
+        type Output = String;
+
+        fn num(&mut self, text: &str) -> Result<String, String> {
+            Ok(text.to_string())
+        }
+
+        fn ident(&mut self, name: &str) -> Result<String, String> {
+            Ok(name.to_string())
+        }
+
+        fn prefix(&mut self, op: &&str, x: String) -> Result<String, String> {
+            Ok(format!("({op} {x})"))
+        }
+
+        fn infix(&mut self, op: &&str, l: String, r: String) -> Result<String, String> {
+            Ok(format!("({op} {l} {r})"))
+        }
+
+        fn postfix(&mut self, op: &&str, x: String) -> Result<String, String> {
+            Ok(format!("({x} {op})"))
+        }
+    }
+
+    fn table() -> OpTable<&'static str> {
+        OpTable::new()
+            .infix("=", 1, Right, "=")
+            .infix("+", 2, Left, "+")
+            .infix("-", 2, Left, "-")
+            .infix("*", 3, Left, "*")
+            .infix("**", 5, Right, "**")
+            .prefix("-", 4, "neg")
+            .postfix("!", 6, "!")
+            .infix("and", 0, Left, "and")
+    }
+
+    fn sexpr(src: &str) -> Result<String, ParseError> {
+        parse(&table(), src, &mut SExpr)
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(sexpr("1 + 2 * 3").unwrap(), "(+ 1 (* 2 3))");
+        assert_eq!(sexpr("1 - 2 - 3").unwrap(), "(- (- 1 2) 3)");
+        assert_eq!(sexpr("a = b = c").unwrap(), "(= a (= b c))");
+        assert_eq!(sexpr("2 ** 3 ** 2").unwrap(), "(** 2 (** 3 2))");
+        assert_eq!(sexpr("--x * 2").unwrap(), "(* (neg (neg x)) 2)");
+        assert_eq!(sexpr("-x ** 2").unwrap(), "(neg (** x 2))");
+        assert_eq!(sexpr("-3! + 1").unwrap(), "(+ (neg (3 !)) 1)");
+        assert_eq!(sexpr("(1 + 2) * 3").unwrap(), "(* (+ 1 2) 3)");
+        assert_eq!(sexpr("a and band").unwrap(), "(and a band)");
+        assert_eq!(sexpr("  x_1  ").unwrap(), "x_1");
+    }
+
+    #[test]
+    fn operation_order() {
+        use BinOp::*;
+
+        let same = OpTable::new().infix("+", 1, Left, Arith::Bin(Add)).infix(
+            "*",
+            1,
+            Left,
+            Arith::Bin(Mul),
+        );
+        let add_first = OpTable::new().infix("+", 2, Left, Arith::Bin(Add)).infix(
+            "*",
+            1,
+            Left,
+            Arith::Bin(Mul),
+        );
+
+        let cases = [
+            ("1 + (2 * 3) + (4 * (5 + 6))", 51, 51),
+            ("2 * 3 + (4 * 5)", 26, 46),
+            ("5 + (8 * 3 + 9 + 3 * 4 * 3)", 437, 1445),
+            ("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", 12_240, 669_060),
+            (
+                "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
+                13_632,
+                23_340,
+            ),
+        ];
+
+        for (src, p1, p2) in cases {
+            assert_eq!(eval::<i64>(&same, src, |_| None), Ok(p1), "{src}");
+            assert_eq!(eval::<i64>(&add_first, src, |_| None), Ok(p2), "{src}");
+        }
+    }
+
+    #[test]
+    fn into_exprs() {
+        let mut g: Exprs = Exprs::new();
+        let t = OpTable::arithmetic();
+
+        let e = parse(&t, "(x + 1) * 2 == 10", &mut g).unwrap();
+        let x = g.lookup_var("x").unwrap();
+        assert_eq!(g.solve_for(x, e), Ok(4));
+
+        let e = parse(&t, "-(3 - 10) % 4", &mut g).unwrap();
+        assert_eq!(g.as_const(e), Some(3));
+
+        assert_eq!(eval(&t, "y * -y", |_| Some(7)), Ok(-49));
+    }
+
+    #[test]
+    fn errors() {
+        let kind = |src| sexpr(src).unwrap_err().kind;
+        let span = |src| sexpr(src).unwrap_err().span;
+
+        assert_eq!(kind("1 + "), ErrorKind::UnexpectedEnd);
+        assert_eq!(kind("1 $ 2"), ErrorKind::UnexpectedChar('$'));
+        assert_eq!(span("1 $ 2"), 2..3);
+        assert_eq!(kind("(1 + 2"), ErrorKind::UnmatchedParen);
+        assert_eq!(span("(1 + 2"), 0..1);
+        assert_eq!(kind("1 + 2)"), ErrorKind::UnmatchedParen);
+        assert_eq!(span("1 + 2)"), 5..6);
+        assert_eq!(kind("1 2"), ErrorKind::ExpectedOperator);
+        assert_eq!(kind("* 2"), ErrorKind::ExpectedOperand);
+        assert_eq!(kind("()"), ErrorKind::ExpectedOperand);
+        assert_eq!(kind("1 + 2 -"), ErrorKind::UnexpectedEnd);
+
+        let t = OpTable::new().prefix("~", 1, Arith::Neg);
+        assert_eq!(
+            eval::<i64>(&t, "1 ~ 2", |_| None).unwrap_err().kind,
+            ErrorKind::NotInfix("~".to_string())
+        );
+
+        let src = "3 * 4 / (2 - 2)";
+        let e = eval::<i64>(&OpTable::arithmetic(), src, |_| None).unwrap_err();
+        assert_eq!(e.span, 6..7);
+        assert_eq!(
+            e.render(src),
+            "3 * 4 / (2 - 2)\n      ^ `12 / 0` overflows or divides by zero"
+        );
+
+        let e = eval::<i64>(&OpTable::arithmetic(), "q + 1", |_| None).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Build("unbound variable `q`".to_string()));
+        assert_eq!(e.span, 0..1);
+    }
+}
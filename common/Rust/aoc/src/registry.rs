@@ -0,0 +1,593 @@
+//! A registry of solutions, filled in by [`#[aoc::solution]`](crate::solution).
+//!
+//! This lets one binary host every day of a year (see [`main!`](crate::main))
+//! and lets runners, benchmarks and tests call solutions directly instead of
+//! spawning a process per day.
+//!
+//! The attribute goes on an inline module with a `p1` function and, optionally,
+//! `parse` and `p2` functions:
+//!
+//! ```ignore
+//! #[aoc::solution(year = 2022, day = 1)]
+//! mod calorie_counting {
+//!     use aoc::*;
+//!
+//!     fn parse(inp: &str) -> Vec<usize> {
+//!         inp.split("\n\n")
+//!             .map(|elf| elf.lines().map(|c| c.parse::<usize>().unwrap()).sum())
+//!             .collect()
+//!     }
+//!
+//!     fn p1(elves: &[usize]) -> usize { *elves.iter().max().unwrap() }
+//!     fn p2(elves: &[usize]) -> usize { elves.iter().sorted().rev().take(3).sum() }
+//! }
+//!
+//! aoc::main!();
+//! ```
+//!
+//! The parts get a reference to whatever `parse` returns (or the input itself,
+//! as a `&str`, if there's no `parse`) and can return anything that's
+//! `ToString`. The functions don't need to be `pub`.
+//!
+//! A part can have other implementations too; these are functions marked with
+//! `#[variant(p1)]` or `#[variant(p2)]` and are named after the function:
+//!
+//! ```ignore
+//! #[variant(p2)]
+//! fn p2_naive(elves: &[usize]) -> usize { ... }
+//! ```
+//!
+//! The function named `p1` (or `p2`) is the default, if there is one; otherwise
+//! it's the first variant. `--variant NAME` submits a different one and
+//! `--all-variants` runs all of them, checks that they agree and reports how
+//! long each took (see [`Variants`]).
+//!
+//! Each part also gets a regression test (`regression_tests::p1` and
+//! `regression_tests::p2`, in the module) that runs the default variant on the
+//! cached input and checks it against the answer adventofcode.com accepted.
+//! Answers are recorded next to the cached inputs whenever they're submitted
+//! or looked up; `aoc fetch <YEAR>` grabs the inputs and answers for the days
+//! that are already solved. Tests for days that don't have both are skipped,
+//! so `cargo test` works without any of this too (see [`regression_test`]).
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::iter::once;
+use std::process::exit;
+use std::time::{Duration, Instant};
+
+use crate::client::{get_cached_input_and_answer, Config, Part};
+use crate::{AdventOfCode, Itertools};
+
+#[doc(hidden)]
+pub use inventory;
+
+/// A registered solution; made by [`#[aoc::solution]`](crate::solution).
+#[derive(Debug)]
+pub struct Solution {
+    pub year: u16,
+    pub day: u8,
+    /// The path of the module the solution lives in.
+    pub name: &'static str,
+    /// The names of each part's variants, starting with the default.
+    pub variants: [&'static [&'static str]; 2],
+    #[doc(hidden)]
+    pub func: RunFn,
+}
+
+/// Runs the variants of each part for which the given closure returns true.
+#[doc(hidden)]
+pub type RunFn = fn(&str, &dyn Fn(Part, &str) -> bool) -> Run;
+
+inventory::collect!(Solution);
+
+impl Solution {
+    pub fn variants(&self, part: Part) -> &'static [&'static str] {
+        match part {
+            Part::One => self.variants[0],
+            Part::Two => self.variants[1],
+        }
+    }
+
+    /// Parses `input` and runs the default variant of each part.
+    pub fn run(&self, input: &str) -> Run {
+        self.run_with(input, &Variants::default()).unwrap()
+    }
+
+    /// Parses `input` and runs just the default variant of `part`; `None` if
+    /// the solution doesn't have that part.
+    pub fn run_part(&self, input: &str, part: Part) -> Option<Answer> {
+        let default = self.variants(part).first()?;
+        let mut run = (self.func)(input, &|p, name| p == part && name == *default);
+        run.answers_mut(part).pop()
+    }
+
+    /// Parses `input` and runs the variants picked by `variants`.
+    ///
+    /// Errors if `variants` names variants that this solution doesn't have.
+    pub fn run_with(&self, input: &str, variants: &Variants) -> Result<Run, String> {
+        variants.check(self)?;
+        let mut run = (self.func)(input, &|part, name| variants.runs(self, part, name));
+
+        // Put the answers to submit first:
+        for part in [Part::One, Part::Two] {
+            let chosen = variants.chosen(self, part);
+            let answers = run.answers_mut(part);
+            if let Some(idx) = answers.iter().position(|a| Some(a.variant) == chosen) {
+                answers[..=idx].rotate_right(1);
+            }
+        }
+
+        Ok(run)
+    }
+}
+
+/// Which variants of each part to run and submit; see
+/// [`Solution::run_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variants {
+    /// Run every variant, not just the ones being submitted.
+    pub all: bool,
+    /// Variants to submit instead of the defaults; at most one per part.
+    pub chosen: Vec<String>,
+}
+
+impl Variants {
+    /// The variant of `part` to submit.
+    fn chosen(&self, solution: &Solution, part: Part) -> Option<&'static str> {
+        let variants = solution.variants(part);
+        let chosen = variants
+            .iter()
+            .find(|v| self.chosen.iter().any(|c| c == *v));
+        chosen.or(variants.first()).copied()
+    }
+
+    fn runs(&self, solution: &Solution, part: Part, variant: &str) -> bool {
+        self.all || self.chosen(solution, part) == Some(variant)
+    }
+
+    fn check(&self, solution: &Solution) -> Result<(), String> {
+        let all = || solution.variants.iter().flat_map(|v| v.iter());
+        if let Some(unknown) = self.chosen.iter().find(|c| !all().any(|v| v == c)) {
+            return Err(format!(
+                "{}/{:02} has no `{unknown}` variant (has: {})",
+                solution.year,
+                solution.day,
+                all().join(", "),
+            ));
+        }
+
+        for part in solution.variants {
+            let chosen = part
+                .iter()
+                .filter(|v| self.chosen.iter().any(|c| c == *v))
+                .copied()
+                .collect_vec();
+            if chosen.len() > 1 {
+                return Err(format!(
+                    "can only submit one variant per part (got: {})",
+                    chosen.join(", ")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An answer and how long it took to get it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    /// The name of the function that produced this answer.
+    pub variant: &'static str,
+    pub answer: String,
+    pub time: Duration,
+}
+
+impl Answer {
+    #[doc(hidden)]
+    pub fn time<T: ToString>(variant: &'static str, func: impl FnOnce() -> T) -> Self {
+        let (answer, time) = time(func);
+        Self {
+            variant,
+            answer: answer.to_string(),
+            time,
+        }
+    }
+}
+
+/// The results of running a [`Solution`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    /// How long `parse` took (zero for solutions without a `parse`).
+    pub parse: Duration,
+    /// The answers from each variant of part 1 that was run, starting with
+    /// the one to submit.
+    pub p1: Vec<Answer>,
+    /// Empty for solutions without a `p2` (i.e. day 25).
+    pub p2: Vec<Answer>,
+}
+
+impl Run {
+    pub fn answers(&self, part: Part) -> &[Answer] {
+        match part {
+            Part::One => &self.p1,
+            Part::Two => &self.p2,
+        }
+    }
+
+    fn answers_mut(&mut self, part: Part) -> &mut Vec<Answer> {
+        match part {
+            Part::One => &mut self.p1,
+            Part::Two => &mut self.p2,
+        }
+    }
+
+    /// The answer to submit for `part`.
+    pub fn answer(&self, part: Part) -> Option<&Answer> {
+        self.answers(part).first()
+    }
+
+    /// The parts whose variants didn't all get the same answer.
+    pub fn disagreements(&self) -> Vec<Part> {
+        [Part::One, Part::Two]
+            .into_iter()
+            .filter(|&p| !self.answers(p).iter().map(|a| &a.answer).all_equal())
+            .collect()
+    }
+}
+
+/// A table of the answers and timings; the answers being submitted are marked
+/// with a `*` and ones that disagree with them with a `✗`.
+impl fmt::Display for Run {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.parse > Duration::ZERO {
+            writeln!(fmt, "parse: [{:?}]", self.parse)?;
+        }
+
+        let width = (self.p1.iter().chain(&self.p2))
+            .map(|a| a.variant.len())
+            .max()
+            .unwrap_or(0);
+        for part in [Part::One, Part::Two] {
+            let answers = self.answers(part);
+            let Some(submitted) = answers.first() else {
+                continue;
+            };
+
+            writeln!(fmt, "part {}:", part.to_string())?;
+            for (i, a) in answers.iter().enumerate() {
+                let mark = match i {
+                    0 => '*',
+                    _ if a.answer != submitted.answer => '✗',
+                    _ => ' ',
+                };
+                writeln!(
+                    fmt,
+                    "  {mark} {:width$}  `{}` [{:?}]",
+                    a.variant, a.answer, a.time
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn time<T>(func: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let ret = func();
+    (ret, start.elapsed())
+}
+
+/// Checks the default variant of a registered solution's `part` against the
+/// recorded answer, using the cached input; called by the tests that
+/// [`#[aoc::solution]`](crate::solution) makes.
+///
+/// Does nothing (other than saying so) if the input or the answer isn't
+/// cached.
+pub fn regression_test(year: u16, day: u8, part: Part) {
+    let solution =
+        find(year, day).unwrap_or_else(|| panic!("no solution is registered for {year}/{day:02}"));
+    let Some((input, expected)) = get_cached_input_and_answer(year, day, part) else {
+        eprintln!(
+            "skipping {year}/{day:02} part {}: no cached input and answer",
+            part.to_string()
+        );
+        return;
+    };
+
+    check_answer(solution, part, &input, &expected);
+}
+
+fn check_answer(solution: &Solution, part: Part, input: &str, expected: &str) {
+    let (year, day) = (solution.year, solution.day);
+    let answer = solution
+        .run_part(input, part)
+        .unwrap_or_else(|| panic!("{year}/{day:02} has no part {}", part.to_string()));
+    assert_eq!(
+        answer.answer.trim(),
+        expected.trim(),
+        "wrong answer for {year}/{day:02} part {} (from `{}`)",
+        part.to_string(),
+        answer.variant,
+    );
+}
+
+/// Every registered solution, ordered by year and then day.
+pub fn all() -> Vec<&'static Solution> {
+    let mut all: Vec<_> = inventory::iter::<Solution>.into_iter().collect();
+    all.sort_by_key(|s| (s.year, s.day));
+    all
+}
+
+pub fn find(year: u16, day: u8) -> Option<&'static Solution> {
+    inventory::iter::<Solution>
+        .into_iter()
+        .find(|s| s.year == year && s.day == day)
+}
+
+/// The years that have registered solutions.
+pub fn years() -> Vec<u16> {
+    let mut years: Vec<_> = all().into_iter().map(|s| s.year).collect();
+    years.dedup();
+    years
+}
+
+/// Picks the solution to run from the first arguments (`[YEAR] DAY`); the
+/// year can be left out when all the registered solutions are from one year.
+///
+/// Returns the solution and the number of arguments used.
+fn select(args: &[String]) -> Result<(&'static Solution, usize), String> {
+    let num = |a: &String| a.parse::<u16>().ok();
+    let (year, day, used) = match (args.first().and_then(num), args.get(1).and_then(num)) {
+        (Some(year), Some(day)) => (Some(year), day, 2),
+        (Some(day), _) => (None, day, 1),
+        (None, _) => return Err("missing day".into()),
+    };
+
+    let year = match (year, &*years()) {
+        (Some(year), _) => year,
+        (None, [year]) => *year,
+        (None, []) => return Err("no solutions are registered".into()),
+        (None, _) => return Err("there are solutions from several years; please give one".into()),
+    };
+
+    u8::try_from(day)
+        .ok()
+        .and_then(|day| find(year, day))
+        .map(|s| (s, used))
+        .ok_or_else(|| {
+            let have = all()
+                .iter()
+                .map(|s| format!("{}/{:02}", s.year, s.day))
+                .join(", ");
+            format!("no solution for {year}/{day:02} (have: {have})")
+        })
+}
+
+/// The `main` for binaries that host registered solutions; see
+/// [`main!`](crate::main).
+///
+/// Takes `[YEAR] DAY` and then the usual options (i.e. for the input, output
+/// and variants) which are passed along to [`Config`].
+pub fn main() {
+    let mut args = env::args_os();
+    let prog = args.next().unwrap_or_else(|| "aoc".into());
+
+    let args: Vec<OsString> = args.collect();
+    let leading: Vec<String> = args
+        .iter()
+        .take(2)
+        .map_while(|a| a.to_str().map(String::from))
+        .collect();
+    let (solution, used) = select(&leading).unwrap_or_else(|err| {
+        eprintln!(
+            "{err}\nusage: {} [YEAR] DAY [options...]",
+            prog.to_string_lossy()
+        );
+        exit(2)
+    });
+
+    let (year, day) = (solution.year, solution.day);
+    let args = once(prog).chain(args.into_iter().skip(used));
+    let config = Config::get_config_with_custom_args(year, day, None, args);
+    let mut aoc = AdventOfCode::new_from_config(config);
+
+    // (`run` has already reported any errors)
+    if aoc.run(solution).is_err() {
+        exit(1)
+    }
+}
+
+/// Makes a `main` that runs the registered solution picked on the command
+/// line (`[YEAR] DAY [options...]`); see [`registry`](crate::registry).
+#[macro_export]
+macro_rules! main {
+    () => {
+        fn main() {
+            $crate::registry::main()
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[crate::solution(year = 1999, day = 1)]
+    mod sums {
+        fn parse(inp: &str) -> Vec<u64> {
+            inp.split_whitespace().map(|n| n.parse().unwrap()).collect()
+        }
+
+        fn p1(nums: &[u64]) -> u64 {
+            nums.iter().sum()
+        }
+
+        fn p2(nums: &[u64]) -> String {
+            format!("{:?}", nums.iter().max())
+        }
+    }
+
+    #[crate::solution(year = 1999, day = 2)]
+    mod variants {
+        fn p1(inp: &str) -> usize {
+            inp.len()
+        }
+
+        #[variant(p1)]
+        fn p1_fold(inp: &str) -> usize {
+            inp.bytes().fold(0, |n, _| n + 1)
+        }
+
+        #[variant(p2)]
+        fn p2_chars(inp: &str) -> usize {
+            inp.chars().count()
+        }
+
+        #[variant(p2)]
+        fn p2_lines(inp: &str) -> usize {
+            inp.lines().count()
+        }
+    }
+
+    #[crate::solution(year = 1999, day = 25)]
+    mod unparsed {
+        fn p1(inp: &str) -> usize {
+            inp.lines().count()
+        }
+    }
+
+    #[test]
+    fn registered() {
+        let days: Vec<_> = all()
+            .into_iter()
+            .filter(|s| s.year == 1999)
+            .map(|s| (s.day, s.name))
+            .collect();
+        assert_eq!(
+            days,
+            [
+                (1, "aoc::registry::tests::sums"),
+                (2, "aoc::registry::tests::variants"),
+                (25, "aoc::registry::tests::unparsed")
+            ]
+        );
+        assert!(years().contains(&1999));
+        assert!(find(1999, 3).is_none());
+    }
+
+    #[test]
+    fn runs() {
+        let run = find(1999, 1).unwrap().run("1 2\n3");
+        assert_eq!(run.answer(Part::One).unwrap().answer, "6");
+        assert_eq!(run.answer(Part::Two).unwrap().answer, "Some(3)");
+
+        let run = find(1999, 25).unwrap().run("a\nb\n");
+        assert_eq!(run.p1[0].answer, "2");
+        assert_eq!(run.parse, Duration::ZERO);
+        assert_eq!(run.p2, []);
+    }
+
+    #[test]
+    fn variants() {
+        let sol = find(1999, 2).unwrap();
+        assert_eq!(
+            sol.variants,
+            [&["p1", "p1_fold"][..], &["p2_chars", "p2_lines"]]
+        );
+
+        let ran = |run: &Run, part| {
+            let answers = run.answers(part).iter();
+            answers
+                .map(|a| (a.variant, a.answer.clone()))
+                .collect::<Vec<_>>()
+        };
+        let run_with = |all, chosen: &[&str]| {
+            let chosen = chosen.iter().map(|c| c.to_string()).collect();
+            sol.run_with("ab\ncd", &Variants { all, chosen })
+        };
+
+        // Just the defaults:
+        let run = sol.run("ab\ncd");
+        assert_eq!(ran(&run, Part::One), [("p1", "5".into())]);
+        assert_eq!(ran(&run, Part::Two), [("p2_chars", "5".into())]);
+        assert!(run.disagreements().is_empty());
+
+        // Everything:
+        let run = run_with(true, &[]).unwrap();
+        assert_eq!(
+            ran(&run, Part::One),
+            [("p1", "5".into()), ("p1_fold", "5".into())]
+        );
+        assert_eq!(
+            ran(&run, Part::Two),
+            [("p2_chars", "5".into()), ("p2_lines", "2".into())]
+        );
+        assert_eq!(run.disagreements(), [Part::Two]);
+
+        let table = run.to_string();
+        assert!(table.contains("* p1        `5`"), "{table}");
+        assert!(table.contains("  p1_fold   `5`"), "{table}");
+        assert!(table.contains("✗ p2_lines  `2`"), "{table}");
+
+        // Picking what to submit:
+        let run = run_with(true, &["p2_lines"]).unwrap();
+        assert_eq!(run.answer(Part::Two).unwrap().variant, "p2_lines");
+        assert_eq!(run.p2.len(), 2);
+
+        let run = run_with(false, &["p1_fold"]).unwrap();
+        assert_eq!(ran(&run, Part::One), [("p1_fold", "5".into())]);
+        assert_eq!(ran(&run, Part::Two), [("p2_chars", "5".into())]);
+
+        assert!(run_with(false, &["p3"])
+            .unwrap_err()
+            .contains("no `p3` variant"));
+        assert!(run_with(false, &["p2_chars", "p2_lines"]).is_err());
+    }
+
+    #[test]
+    fn run_part() {
+        let sol = find(1999, 2).unwrap();
+        let answer = sol.run_part("ab\ncd", Part::Two).unwrap();
+        assert_eq!((answer.variant, &*answer.answer), ("p2_chars", "5"));
+
+        assert_eq!(find(1999, 25).unwrap().run_part("a", Part::Two), None);
+    }
+
+    #[test]
+    fn regression_checks() {
+        let sol = find(1999, 1).unwrap();
+        check_answer(sol, Part::One, "1 2\n3", "6\n");
+        check_answer(sol, Part::Two, "1 2\n3", "Some(3)");
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong answer for 1999/01 part one (from `p1`)")]
+    fn regression_check_failures() {
+        check_answer(find(1999, 1).unwrap(), Part::One, "1 2\n3", "7");
+    }
+
+    #[test]
+    fn selection() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let (s, used) = select(&args(&["1999", "25", "--stdin"])).unwrap();
+        assert_eq!((s.year, s.day, used), (1999, 25, 2));
+
+        let (s, used) = select(&args(&["1999", "1"])).unwrap();
+        assert_eq!((s.day, used), (1, 2));
+
+        // Only one year is registered in tests:
+        let (s, used) = select(&args(&["25", "--stdin"])).unwrap();
+        assert_eq!((s.year, s.day, used), (1999, 25, 1));
+
+        assert!(select(&args(&["1999", "3"]))
+            .unwrap_err()
+            .contains("1999/01, 1999/02, 1999/25"));
+        assert!(select(&args(&["--stdin"])).is_err());
+    }
+}
@@ -0,0 +1,660 @@
+//! A circular doubly linked list stored in an arena.
+//!
+//! Nodes are addressed by [`Handle`]s which stay valid until the node is
+//! removed (after which the slot may be reused). Every operation that doesn't
+//! need to walk the ring is O(1), including cutting out a run of nodes and
+//! splicing it back in somewhere else ([`Chain`]).
+//!
+//! For dense integer payloads (marble numbers, cup labels) a
+//! [`Ring::with_lookup`] ring also keeps a value → handle table.
+
+use std::fmt::{self, Debug};
+use std::ops::{Index, IndexMut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle(u32);
+
+impl Handle {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Gets told about values as they enter and leave a [`Ring`].
+pub trait Lookup<T> {
+    fn inserted(&mut self, val: &T, h: Handle);
+    fn removed(&mut self, val: &T);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoLookup;
+
+impl<T> Lookup<T> for NoLookup {
+    fn inserted(&mut self, _: &T, _: Handle) {}
+    fn removed(&mut self, _: &T) {}
+}
+
+/// A `Vec` indexed by value; values must convert to `usize`.
+#[derive(Debug, Clone, Default)]
+pub struct DenseLookup(Vec<Option<Handle>>);
+
+impl<T: Copy + TryInto<usize>> Lookup<T> for DenseLookup {
+    fn inserted(&mut self, val: &T, h: Handle) {
+        let Ok(idx) = (*val).try_into() else {
+            panic!("value can't be used as an index")
+        };
+        if idx >= self.0.len() {
+            self.0.resize(idx + 1, None);
+        }
+
+        self.0[idx] = Some(h);
+    }
+
+    fn removed(&mut self, val: &T) {
+        if let Ok(idx) = (*val).try_into() {
+            self.0[idx] = None;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    val: Option<T>,
+    prev: Handle,
+    next: Handle,
+}
+
+/// A run of nodes cut out of a [`Ring`] with [`Ring::splice_out`].
+///
+/// The nodes (and their handles) stay alive until the chain is either spliced
+/// back in or [discarded](Ring::discard).
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chain {
+    pub first: Handle,
+    pub last: Handle,
+    pub len: usize,
+}
+
+#[derive(Clone)]
+pub struct Ring<T, L = NoLookup> {
+    slots: Vec<Slot<T>>,
+    free: Vec<Handle>,
+    head: Option<Handle>,
+    len: usize,
+    lookup: L,
+}
+
+impl<T> Default for Ring<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Ring<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(cap: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(cap),
+            free: vec![],
+            head: None,
+            len: 0,
+            lookup: NoLookup,
+        }
+    }
+
+    // Only rings without a lookup table hand out `&mut T`; changing a value
+    // would leave the table stale.
+
+    pub fn get_mut(&mut self, h: Handle) -> &mut T {
+        self.slots[h.index()].val.as_mut().expect("stale handle")
+    }
+}
+
+impl<T: Copy + TryInto<usize>> Ring<T, DenseLookup> {
+    pub fn with_lookup() -> Self {
+        Self {
+            slots: vec![],
+            free: vec![],
+            head: None,
+            len: 0,
+            lookup: DenseLookup::default(),
+        }
+    }
+
+    /// The node holding `val`, if any (including nodes in detached chains).
+    pub fn find(&self, val: T) -> Option<Handle> {
+        let idx = val.try_into().ok()?;
+        self.lookup.0.get(idx).copied().flatten()
+    }
+}
+
+impl<T, L: Lookup<T>> Ring<T, L> {
+    /// Number of nodes in the ring; nodes in detached chains don't count.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The first node inserted (or its successor if it has been removed).
+    pub fn head(&self) -> Option<Handle> {
+        self.head
+    }
+
+    pub fn get(&self, h: Handle) -> &T {
+        self.slots[h.index()].val.as_ref().expect("stale handle")
+    }
+
+    pub fn next(&self, h: Handle) -> Handle {
+        self.slots[h.index()].next
+    }
+
+    pub fn prev(&self, h: Handle) -> Handle {
+        self.slots[h.index()].prev
+    }
+
+    /// The node `n` places after (or before, if negative) `h`, modulo the
+    /// length of the ring. Walks whichever way round is shorter.
+    pub fn nth(&self, mut h: Handle, n: isize) -> Handle {
+        if self.len == 0 {
+            return h;
+        }
+
+        let fwd = n.rem_euclid(self.len as isize) as usize;
+        if fwd <= self.len / 2 {
+            (0..fwd).for_each(|_| h = self.next(h));
+        } else {
+            (fwd..self.len).for_each(|_| h = self.prev(h));
+        }
+
+        h
+    }
+
+    fn alloc(&mut self, val: T) -> Handle {
+        let h = match self.free.pop() {
+            Some(h) => h,
+            None => {
+                let h = Handle(self.slots.len() as u32);
+                self.slots.push(Slot {
+                    val: None,
+                    prev: h,
+                    next: h,
+                });
+                h
+            }
+        };
+
+        self.lookup.inserted(&val, h);
+        self.slots[h.index()] = Slot {
+            val: Some(val),
+            prev: h,
+            next: h,
+        };
+        h
+    }
+
+    /// Links the (self-contained) run `first..=last` in after `at`.
+    fn link_after(&mut self, at: Handle, first: Handle, last: Handle) {
+        let next = self.next(at);
+        self.slots[at.index()].next = first;
+        self.slots[first.index()].prev = at;
+        self.slots[last.index()].next = next;
+        self.slots[next.index()].prev = last;
+    }
+
+    /// Unlinks `first..=last` (`len` nodes), leaving it as a little ring of
+    /// its own.
+    fn unlink(&mut self, first: Handle, last: Handle, len: usize) {
+        let (prev, next) = (self.prev(first), self.next(last));
+        self.slots[prev.index()].next = next;
+        self.slots[next.index()].prev = prev;
+        self.slots[first.index()].prev = last;
+        self.slots[last.index()].next = first;
+
+        self.len -= len;
+        if self.len == 0 {
+            self.head = None;
+        } else if let Some(head) = self.head {
+            if Iter::new(self, first, len).any(|h| h == head) {
+                self.head = Some(next);
+            }
+        }
+    }
+
+    /// Inserts `val` before the head (i.e. at the "end").
+    pub fn push_back(&mut self, val: T) -> Handle {
+        match self.head {
+            Some(head) => {
+                let tail = self.prev(head);
+                self.insert_after(tail, val)
+            }
+            None => {
+                let h = self.alloc(val);
+                self.head = Some(h);
+                self.len = 1;
+                h
+            }
+        }
+    }
+
+    pub fn insert_after(&mut self, at: Handle, val: T) -> Handle {
+        let h = self.alloc(val);
+        self.link_after(at, h, h);
+        self.len += 1;
+        h
+    }
+
+    pub fn insert_before(&mut self, at: Handle, val: T) -> Handle {
+        let prev = self.prev(at);
+        self.insert_after(prev, val)
+    }
+
+    pub fn remove(&mut self, h: Handle) -> T {
+        self.unlink(h, h, 1);
+        self.release(h)
+    }
+
+    fn release(&mut self, h: Handle) -> T {
+        let val = self.slots[h.index()].val.take().expect("stale handle");
+        self.lookup.removed(&val);
+        self.free.push(h);
+        val
+    }
+
+    /// Cuts out the `k` nodes following `after`.
+    pub fn splice_out(&mut self, after: Handle, k: usize) -> Chain {
+        assert!(
+            k > 0 && k < self.len,
+            "can only splice out 1 to {} nodes",
+            self.len.saturating_sub(1)
+        );
+
+        let first = self.next(after);
+        let last = self.nth(after, k as isize);
+        self.unlink(first, last, k);
+
+        Chain {
+            first,
+            last,
+            len: k,
+        }
+    }
+
+    /// Puts a chain back in, after `at`.
+    pub fn splice_in(&mut self, at: Handle, chain: Chain) {
+        self.link_after(at, chain.first, chain.last);
+        self.len += chain.len;
+    }
+
+    /// Frees the nodes in a chain, returning their values in order.
+    pub fn discard(&mut self, chain: Chain) -> Vec<T> {
+        let handles: Vec<_> = self.chain(&chain).collect();
+        handles.into_iter().map(|h| self.release(h)).collect()
+    }
+
+    /// The handles in a detached chain, in order.
+    pub fn chain(&self, chain: &Chain) -> impl Iterator<Item = Handle> + '_ {
+        Iter::new(self, chain.first, chain.len)
+    }
+
+    /// Moves `h` `n` places forwards (or backwards, if negative) past the
+    /// other nodes; i.e. modulo `len - 1`. This is the "mixing" move from
+    /// 2022/20.
+    pub fn shift(&mut self, h: Handle, n: isize) {
+        if self.len < 2 {
+            return;
+        }
+
+        let steps = n.rem_euclid(self.len as isize - 1);
+        if steps == 0 {
+            return;
+        }
+
+        let prev = self.prev(h);
+        self.unlink(h, h, 1);
+        let at = self.nth(prev, steps);
+        self.link_after(at, h, h);
+        self.len += 1;
+    }
+
+    /// Every handle in the ring, starting from `h` and going forwards.
+    pub fn handles_from(&self, h: Handle) -> impl Iterator<Item = Handle> + '_ {
+        Iter::new(self, h, self.len)
+    }
+
+    /// Every value in the ring, starting from `h` and going forwards.
+    pub fn iter_from(&self, h: Handle) -> impl Iterator<Item = &T> + '_ {
+        self.handles_from(h).map(|h| self.get(h))
+    }
+
+    /// Every value in the ring, starting from the head.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let len = if self.head.is_some() { self.len } else { 0 };
+        Iter::new(self, self.head.unwrap_or(Handle(0)), len).map(|h| self.get(h))
+    }
+
+    pub fn cursor(&mut self, at: Handle) -> Cursor<'_, T, L> {
+        Cursor {
+            at: Some(at),
+            ring: self,
+        }
+    }
+
+    pub fn cursor_at_head(&mut self) -> Cursor<'_, T, L> {
+        Cursor {
+            at: self.head,
+            ring: self,
+        }
+    }
+}
+
+impl<T, L: Lookup<T>> Index<Handle> for Ring<T, L> {
+    type Output = T;
+
+    fn index(&self, h: Handle) -> &T {
+        self.get(h)
+    }
+}
+
+impl<T> IndexMut<Handle> for Ring<T> {
+    fn index_mut(&mut self, h: Handle) -> &mut T {
+        self.get_mut(h)
+    }
+}
+
+impl<T> FromIterator<T> for Ring<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut r = Ring::new();
+        r.extend(iter);
+        r
+    }
+}
+
+impl<T: Copy + TryInto<usize>> FromIterator<T> for Ring<T, DenseLookup> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut r = Ring::with_lookup();
+        r.extend(iter);
+        r
+    }
+}
+
+impl<T, L: Lookup<T>> Extend<T> for Ring<T, L> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for v in iter {
+            self.push_back(v);
+        }
+    }
+}
+
+impl<T: Debug, L: Lookup<T>> Debug for Ring<T, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+struct Iter<'r, T, L> {
+    ring: &'r Ring<T, L>,
+    at: Handle,
+    remaining: usize,
+}
+
+impl<'r, T, L: Lookup<T>> Iter<'r, T, L> {
+    fn new(ring: &'r Ring<T, L>, at: Handle, remaining: usize) -> Self {
+        Self {
+            ring,
+            at,
+            remaining,
+        }
+    }
+}
+
+impl<T, L: Lookup<T>> Iterator for Iter<'_, T, L> {
+    type Item = Handle;
+
+    fn next(&mut self) -> Option<Handle> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let h = self.at;
+        self.at = self.ring.next(h);
+        self.remaining -= 1;
+        Some(h)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// A position in a [`Ring`]; only empty when the ring is.
+pub struct Cursor<'r, T, L = NoLookup> {
+    ring: &'r mut Ring<T, L>,
+    at: Option<Handle>,
+}
+
+impl<T, L: Lookup<T>> Cursor<'_, T, L> {
+    pub fn handle(&self) -> Option<Handle> {
+        self.at
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.at.map(|h| self.ring.get(h))
+    }
+
+    pub fn ring(&self) -> &Ring<T, L> {
+        self.ring
+    }
+
+    /// Moves `n` places forwards (or backwards, if negative), modulo the
+    /// length of the ring.
+    pub fn move_by(&mut self, n: isize) -> &mut Self {
+        self.at = self.at.map(|h| self.ring.nth(h, n));
+        self
+    }
+
+    pub fn move_to(&mut self, h: Handle) -> &mut Self {
+        self.at = Some(h);
+        self
+    }
+
+    /// Inserts after the current node (or as the only node if the ring is
+    /// empty) without moving the cursor.
+    pub fn insert_after(&mut self, val: T) -> Handle {
+        match self.at {
+            Some(at) => self.ring.insert_after(at, val),
+            None => {
+                let h = self.ring.push_back(val);
+                self.at = Some(h);
+                h
+            }
+        }
+    }
+
+    /// Like [`Cursor::insert_after`] but then moves onto the new node.
+    pub fn push_after(&mut self, val: T) -> Handle {
+        let h = self.insert_after(val);
+        self.at = Some(h);
+        h
+    }
+
+    /// Removes the current node and moves on to the one after it.
+    ///
+    /// Panics if the ring is empty.
+    pub fn remove(&mut self) -> T {
+        let h = self.at.expect("cursor on an empty ring");
+        let next = self.ring.next(h);
+        let val = self.ring.remove(h);
+        self.at = (!self.ring.is_empty()).then_some(next);
+        val
+    }
+
+    /// Cuts out the `k` nodes after the current one.
+    pub fn splice_out(&mut self, k: usize) -> Chain {
+        self.ring
+            .splice_out(self.at.expect("cursor on an empty ring"), k)
+    }
+
+    /// Puts a chain back in after the current node.
+    pub fn splice_in(&mut self, chain: Chain) {
+        match self.at {
+            Some(at) => self.ring.splice_in(at, chain),
+            None => {
+                self.ring.head = Some(chain.first);
+                self.ring.len = chain.len;
+                self.at = Some(chain.first);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() {
+        let mut r: Ring<u8> = (1..=5).collect();
+        assert_eq!(format!("{r:?}"), "[1, 2, 3, 4, 5]");
+
+        let head = r.head().unwrap();
+        let four = r.nth(head, -2);
+        assert_eq!(r[four], 4);
+        assert_eq!(r.nth(head, 13), four);
+        assert_eq!(r.nth(four, 0), four);
+
+        r[four] = 40;
+        assert_eq!(r.remove(head), 1);
+        assert_eq!(r.iter().copied().collect::<Vec<_>>(), [2, 3, 40, 5]);
+
+        let two = r.head().unwrap();
+        let six = r.insert_before(two, 6);
+        assert_eq!(
+            r.iter_from(six).copied().collect::<Vec<_>>(),
+            [6, 2, 3, 40, 5]
+        );
+        assert_eq!(six, head, "slots are reused");
+
+        let c = r.splice_out(two, 2);
+        assert_eq!(r.len(), 3);
+        assert_eq!(r.chain(&c).map(|h| r[h]).collect::<Vec<_>>(), [3, 40]);
+
+        r.splice_in(six, c);
+        assert_eq!(
+            r.iter_from(six).copied().collect::<Vec<_>>(),
+            [6, 3, 40, 2, 5]
+        );
+
+        let c = r.splice_out(six, 4);
+        assert_eq!(r.iter().copied().collect::<Vec<_>>(), [6]);
+        assert_eq!(r.discard(c), [3, 40, 2, 5]);
+
+        let mut cur = r.cursor_at_head();
+        assert_eq!(cur.remove(), 6);
+        assert_eq!(cur.handle(), None);
+        assert!(r.is_empty());
+        assert_eq!(r.iter().count(), 0);
+    }
+
+    fn marble_game(players: usize, marbles: u32) -> u32 {
+        let mut r = Ring::with_capacity(marbles as usize);
+        let mut scores = vec![0; players];
+
+        let mut cur = r.cursor_at_head();
+        cur.push_after(0);
+        for m in 1..=marbles {
+            if m % 23 == 0 {
+                cur.move_by(-7);
+                scores[m as usize % players] += m + cur.remove();
+            } else {
+                cur.move_by(1).push_after(m);
+            }
+        }
+
+        scores.into_iter().max().unwrap()
+    }
+
+    #[test]
+    fn marble_mania() {
+        assert_eq!(marble_game(9, 25), 32);
+        assert_eq!(marble_game(10, 1618), 8317);
+        assert_eq!(marble_game(13, 7999), 146373);
+        assert_eq!(marble_game(30, 5807), 37305);
+    }
+
+    fn crab_cups(labels: &str, extra: usize, moves: usize) -> Ring<usize, DenseLookup> {
+        let labels = labels.bytes().map(|b| (b - b'0') as usize);
+        let mut r: Ring<_, _> = labels.chain(10..=extra).collect();
+        let max = r.len();
+
+        let mut cur = r.cursor_at_head();
+        for _ in 0..moves {
+            let current = *cur.get().unwrap();
+            let picked = cur.splice_out(3);
+            let held: Vec<_> = cur.ring().chain(&picked).map(|h| cur.ring()[h]).collect();
+
+            let mut dest = current;
+            loop {
+                dest = if dest == 1 { max } else { dest - 1 };
+                if !held.contains(&dest) {
+                    break;
+                }
+            }
+
+            let back = cur.handle().unwrap();
+            let dest = cur.ring().find(dest).unwrap();
+            cur.move_to(dest).splice_in(picked);
+            cur.move_to(back).move_by(1);
+        }
+
+        r
+    }
+
+    #[test]
+    fn cup_game() {
+        let after_one = |r: &Ring<usize, DenseLookup>| {
+            r.iter_from(r.find(1).unwrap())
+                .skip(1)
+                .map(|c| c.to_string())
+                .collect::<String>()
+        };
+
+        assert_eq!(after_one(&crab_cups("389125467", 0, 10)), "92658374");
+        assert_eq!(after_one(&crab_cups("389125467", 0, 100)), "67384529");
+
+        let r = crab_cups("389125467", 1_000, 10_000);
+        assert_eq!(r.len(), 1_000);
+        assert!(r.find(0).is_none());
+        assert!(r.find(1_000).is_some());
+    }
+
+    #[test]
+    fn mixing() {
+        let nums = [1, 2, -3, 3, -2, 0, 4];
+        let mut r: Ring<isize> = nums.into_iter().collect();
+        let order: Vec<_> = r.handles_from(r.head().unwrap()).collect();
+
+        for h in order {
+            r.shift(h, r[h]);
+        }
+
+        let zero = r.handles_from(r.head().unwrap()).find(|&h| r[h] == 0);
+        let zero = zero.unwrap();
+        let grove: isize = [1000, 2000, 3000]
+            .into_iter()
+            .map(|n| r[r.nth(zero, n)])
+            .sum();
+        assert_eq!(grove, 3);
+
+        let mut mixed: Vec<_> = r.iter().copied().collect();
+        let z = mixed.iter().position(|&n| n == 0).unwrap();
+        mixed.rotate_left(z);
+        assert_eq!(mixed, [0, 3, -2, 1, 2, -3, 4]);
+    }
+}
@@ -0,0 +1,451 @@
+//! Graph searches over implicit graphs.
+//!
+//! States are anything that's `Clone + Eq + Hash`; edges come from a
+//! `neighbours` closure so nothing needs to be built up front. All the searches
+//! take any number of starting states and an `is_goal` predicate; the search
+//! stops once a goal state is settled (pass `|_| false` to explore everything
+//! reachable). [`dijkstra`] and [`astar`] first finish off the states that tie
+//! with the goal so that none of its predecessors are missed.
+//!
+//! All of these record every predecessor that lies on a shortest path so that
+//! [`SearchResult::all_paths_to`] can enumerate ties. Note that this is only
+//! complete when edge costs are positive: with zero cost edges a predecessor
+//! can be found after its successor was settled.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+use num_traits::Zero;
+
+/// Counters describing how much work a search did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// States popped off of the frontier (stale entries are not counted).
+    pub expanded: usize,
+    /// States pushed onto the frontier, including starting states.
+    pub pushed: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult<S: Eq + Hash, C> {
+    /// Best known cost for every state that was reached.
+    ///
+    /// For states that were never expanded (i.e. still on the frontier when a
+    /// goal was found) this may not be the optimal cost.
+    pub dist: HashMap<S, C>,
+    /// For each reached state, every predecessor it can be reached from at
+    /// `dist` cost. Starting states have no entry.
+    pub preds: HashMap<S, Vec<S>>,
+    /// The goal state the search stopped at, if any.
+    pub goal: Option<S>,
+    pub stats: Stats,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> SearchResult<S, C> {
+    pub fn cost(&self, s: &S) -> Option<C> {
+        self.dist.get(s).copied()
+    }
+
+    /// The cost of reaching the goal the search stopped at.
+    pub fn goal_cost(&self) -> Option<C> {
+        self.goal.as_ref().and_then(|g| self.cost(g))
+    }
+
+    /// A single shortest path from one of the starting states to `to`
+    /// (inclusive on both ends).
+    pub fn path_to(&self, to: &S) -> Option<Vec<S>> {
+        if !self.dist.contains_key(to) {
+            return None;
+        }
+
+        let mut path = vec![to.clone()];
+        let mut curr = to;
+        while let Some(prev) = self.preds.get(curr).and_then(|p| p.first()) {
+            path.push(prev.clone());
+            curr = prev;
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
+    /// A single shortest path to the goal state the search stopped at.
+    pub fn goal_path(&self) -> Option<Vec<S>> {
+        self.goal.as_ref().and_then(|g| self.path_to(g))
+    }
+
+    /// Every shortest path from a starting state to `to`.
+    ///
+    /// The number of paths can grow exponentially; see
+    /// [`SearchResult::states_on_paths_to`] if only the states are needed.
+    pub fn all_paths_to(&self, to: &S) -> Vec<Vec<S>> {
+        if !self.dist.contains_key(to) {
+            return vec![];
+        }
+
+        let mut out = vec![];
+        let mut stack = vec![vec![to.clone()]];
+        while let Some(partial) = stack.pop() {
+            match self.preds.get(partial.last().unwrap()) {
+                Some(preds) if !preds.is_empty() => {
+                    for p in preds {
+                        let mut next = partial.clone();
+                        next.push(p.clone());
+                        stack.push(next);
+                    }
+                }
+                _ => out.push(partial.into_iter().rev().collect()),
+            }
+        }
+
+        out
+    }
+
+    /// Every state that lies on at least one shortest path to `to`.
+    pub fn states_on_paths_to(&self, to: &S) -> HashSet<S> {
+        let mut seen = HashSet::new();
+        if !self.dist.contains_key(to) {
+            return seen;
+        }
+
+        let mut stack = vec![to.clone()];
+        while let Some(s) = stack.pop() {
+            if let Some(preds) = self.preds.get(&s) {
+                stack.extend(preds.iter().filter(|p| !seen.contains(*p)).cloned());
+            }
+            seen.insert(s);
+        }
+
+        seen
+    }
+}
+
+/// Breadth first search; every edge costs 1.
+pub fn bfs<S, N, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbours: N,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> SearchResult<S, usize>
+where
+    S: Clone + Eq + Hash,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = S>,
+{
+    let mut res = SearchResult {
+        dist: HashMap::new(),
+        preds: HashMap::new(),
+        goal: None,
+        stats: Stats::default(),
+    };
+
+    let mut frontier = VecDeque::new();
+    for s in starts {
+        if !res.dist.contains_key(&s) {
+            res.dist.insert(s.clone(), 0);
+            frontier.push_back((0, s));
+            res.stats.pushed += 1;
+        }
+    }
+
+    while let Some((cost, curr)) = frontier.pop_front() {
+        res.stats.expanded += 1;
+        if is_goal(&curr) {
+            res.goal = Some(curr);
+            break;
+        }
+
+        let next_cost = cost + 1;
+        for next in neighbours(&curr) {
+            match res.dist.get(&next) {
+                None => {
+                    res.dist.insert(next.clone(), next_cost);
+                    res.preds.insert(next.clone(), vec![curr.clone()]);
+                    frontier.push_back((next_cost, next));
+                    res.stats.pushed += 1;
+                }
+                Some(&c) if c == next_cost => {
+                    res.preds.entry(next).or_default().push(curr.clone());
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    res
+}
+
+/// Dijkstra's algorithm; `neighbours` yields `(state, edge cost)` pairs.
+pub fn dijkstra<S, C, N, I>(
+    starts: impl IntoIterator<Item = S>,
+    neighbours: N,
+    is_goal: impl FnMut(&S) -> bool,
+) -> SearchResult<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero + Add<Output = C>,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(starts, neighbours, |_| C::zero(), is_goal)
+}
+
+/// A* search; `heuristic` must never overestimate the remaining cost to a
+/// goal (and must be consistent for [`SearchResult::preds`] to be complete).
+///
+/// Once a goal is found, the states whose estimate ties with its cost are still
+/// expanded: any of them may be another predecessor of the goal.
+pub fn astar<S, C, N, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut neighbours: N,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> SearchResult<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero + Add<Output = C>,
+    N: FnMut(&S) -> I,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut res = SearchResult {
+        dist: HashMap::new(),
+        preds: HashMap::new(),
+        goal: None,
+        stats: Stats::default(),
+    };
+
+    let mut goal_cost = None;
+    let mut frontier = BinaryHeap::new();
+    for s in starts {
+        if !res.dist.contains_key(&s) {
+            res.dist.insert(s.clone(), C::zero());
+            frontier.push(Frontier {
+                estimate: heuristic(&s),
+                cost: C::zero(),
+                state: s,
+            });
+            res.stats.pushed += 1;
+        }
+    }
+
+    while let Some(Frontier {
+        estimate,
+        cost,
+        state,
+    }) = frontier.pop()
+    {
+        if goal_cost.is_some_and(|g| estimate > g) {
+            break; // nothing left can be on a shortest path to the goal
+        }
+        if res.dist.get(&state).map(|&c| c < cost).unwrap_or(false) {
+            continue; // we've already done better so move on
+        }
+
+        res.stats.expanded += 1;
+        if is_goal(&state) {
+            if goal_cost.is_none() {
+                goal_cost = Some(cost);
+                res.goal = Some(state);
+            }
+            continue;
+        }
+
+        for (next, edge) in neighbours(&state) {
+            let next_cost = cost + edge;
+            match res.dist.get(&next).map(|c| next_cost.cmp(c)) {
+                None | Some(Ordering::Less) => {
+                    res.dist.insert(next.clone(), next_cost);
+                    res.preds.insert(next.clone(), vec![state.clone()]);
+                    frontier.push(Frontier {
+                        estimate: next_cost + heuristic(&next),
+                        cost: next_cost,
+                        state: next,
+                    });
+                    res.stats.pushed += 1;
+                }
+                Some(Ordering::Equal) => {
+                    res.preds.entry(next).or_default().push(state.clone());
+                }
+                Some(Ordering::Greater) => {}
+            }
+        }
+    }
+
+    res
+}
+
+/// An entry in the priority queue; ordered so that [`BinaryHeap`] (a max heap)
+/// pops the lowest estimate first, breaking ties in favour of the entry that's
+/// furthest along.
+struct Frontier<S, C> {
+    estimate: C,
+    cost: C,
+    state: S,
+}
+
+impl<S, C: Ord> Ord for Frontier<S, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+impl<S, C: Ord> PartialOrd for Frontier<S, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S, C: Ord> Eq for Frontier<S, C> {}
+impl<S, C: Ord> PartialEq for Frontier<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HILL: &str = "Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi";
+
+    type Pos = (usize, usize);
+
+    fn hill() -> (Vec<Vec<u8>>, Pos, Pos) {
+        let mut start = (0, 0);
+        let mut end = (0, 0);
+        let grid = HILL
+            .lines()
+            .enumerate()
+            .map(|(y, l)| {
+                l.bytes()
+                    .enumerate()
+                    .map(|(x, b)| match b {
+                        b'S' => {
+                            start = (y, x);
+                            b'a'
+                        }
+                        b'E' => {
+                            end = (y, x);
+                            b'z'
+                        }
+                        b => b,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        (grid, start, end)
+    }
+
+    fn climbable(g: &[Vec<u8>], (y, x): Pos) -> Vec<Pos> {
+        [(0, 1), (1, 0), (0, -1), (-1, 0)]
+            .into_iter()
+            .filter_map(|(dy, dx)| {
+                let ny = y.checked_add_signed(dy)?;
+                let nx = x.checked_add_signed(dx)?;
+                let h = *g.get(ny)?.get(nx)?;
+                (h <= g[y][x] + 1).then_some((ny, nx))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hill_climbing() {
+        let (g, start, end) = hill();
+
+        let res = bfs([start], |&p| climbable(&g, p), |&p| p == end);
+        assert_eq!(res.goal_cost(), Some(31));
+        assert_eq!(res.goal_path().unwrap().len(), 32);
+
+        let res = dijkstra(
+            [start],
+            |&p| climbable(&g, p).into_iter().map(|n| (n, 1usize)),
+            |&p| p == end,
+        );
+        assert_eq!(res.goal_cost(), Some(31));
+
+        // Multi-source: start from every `a`.
+        let all_a = (0..g.len())
+            .flat_map(|y| (0..g[0].len()).map(move |x| (y, x)))
+            .filter(|&(y, x)| g[y][x] == b'a');
+        let res = bfs(all_a, |&p| climbable(&g, p), |&p| p == end);
+        assert_eq!(res.goal_cost(), Some(29));
+    }
+
+    #[test]
+    fn astar_agrees_with_dijkstra() {
+        let (g, start, end) = hill();
+        let n = |&p: &Pos| climbable(&g, p).into_iter().map(|n| (n, 1usize));
+        let h = |&(y, x): &Pos| y.abs_diff(end.0) + x.abs_diff(end.1);
+
+        let d = dijkstra([start], n, |&p| p == end);
+        let a = astar([start], n, h, |&p| p == end);
+
+        assert_eq!(d.goal_cost(), a.goal_cost());
+        assert!(a.stats.expanded <= d.stats.expanded);
+    }
+
+    #[test]
+    fn all_shortest_paths() {
+        // 0 → 1 → 3
+        // 0 → 2 → 3
+        // 3 → 4
+        let edges = |n: &u8| -> Vec<(u8, u32)> {
+            match n {
+                0 => vec![(1, 1), (2, 1)],
+                1 | 2 => vec![(3, 1)],
+                3 => vec![(4, 5)],
+                _ => vec![],
+            }
+        };
+
+        let res = dijkstra([0], edges, |_| false);
+        assert_eq!(res.goal, None);
+        assert_eq!(res.cost(&4), Some(7));
+
+        let mut paths = res.all_paths_to(&4);
+        paths.sort();
+        assert_eq!(paths, vec![vec![0, 1, 3, 4], vec![0, 2, 3, 4]]);
+        assert_eq!(res.states_on_paths_to(&3).len(), 4);
+        assert_eq!(res.all_paths_to(&9), Vec::<Vec<u8>>::new());
+    }
+
+    #[test]
+    fn all_shortest_paths_with_tied_estimates() {
+        // 0 → 1 → 3 and 0 → 2 → 3, with a heuristic that makes 2 look better
+        // than 1. The goal (reached through 2) then ties with 1 and, being
+        // further along, is popped first.
+        let edges = |n: &u8| -> Vec<(u8, u32)> {
+            match n {
+                0 => vec![(1, 1), (2, 1)],
+                1 | 2 => vec![(3, 1)],
+                _ => vec![],
+            }
+        };
+        let h = |n: &u8| u32::from(*n == 1);
+
+        let res = astar([0], edges, h, |&n| n == 3);
+        assert_eq!(res.goal, Some(3));
+        assert_eq!(res.goal_cost(), Some(2));
+
+        let mut paths = res.all_paths_to(&3);
+        paths.sort();
+        assert_eq!(paths, vec![vec![0, 1, 3], vec![0, 2, 3]]);
+    }
+
+    #[test]
+    fn stats() {
+        let res = bfs([0u32], |&n| [n + 1, n + 2], |&n| n == 10);
+        assert_eq!(res.goal_cost(), Some(5));
+        assert!(res.stats.pushed >= res.stats.expanded);
+        assert_eq!(res.stats.pushed, res.dist.len());
+    }
+}
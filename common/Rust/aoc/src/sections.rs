@@ -0,0 +1,410 @@
+//! Splitting inputs into blank line separated sections.
+//!
+//! ```
+//! use aoc::sections::{Lines, Sections};
+//!
+//! let input = "seeds: 79 14\r\n\r\nsoil:\r\n50 98 2\r\n52 50 48\r\n\r\nfert:\r\n0 15 37\r\n";
+//! let (seeds, maps): (String, Vec<Lines<String>>) = input.sections().unwrap();
+//!
+//! assert_eq!(seeds, "seeds: 79 14");
+//! assert_eq!(maps.len(), 2);
+//! assert_eq!(maps[0].0, ["soil:", "50 98 2", "52 50 48"]);
+//! ```
+//!
+//! Lines are split with [`str::lines`] (so `\r\n` is fine) and any line that's
+//! only whitespace separates sections. Leading and trailing blank lines are
+//! ignored.
+//!
+//! What a section turns into is up to [`FromSection`]: strings, numbers and
+//! `#[derive(AocParse)]` types parse the whole section (lines joined with
+//! `\n`), [`Lines`] parses each line and [`Columns`] reads the section
+//! column-wise. In a tuple every element takes one section except for a
+//! `Vec<T>` which takes all the sections that are left.
+
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::ops::Deref;
+use std::str::FromStr;
+
+use crate::parse::ParseError;
+
+/// A run of non-blank lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'s> {
+    /// 0-based.
+    pub index: usize,
+    /// 1-based line number (in the whole input) of the first line.
+    pub start_line: usize,
+    pub lines: Vec<&'s str>,
+}
+
+impl Section<'_> {
+    /// The lines joined with `\n`.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// An error pointing at the `line`th (0-based) line of this section.
+    pub fn error_at(&self, line: usize, message: impl Into<String>) -> SectionError {
+        SectionError {
+            section: self.index + 1,
+            line: self.start_line + line,
+            message: message.into(),
+        }
+    }
+
+    /// Parses all of [`Section::text`] with `T`'s [`FromStr`] impl.
+    ///
+    /// [`ParseError`]s (i.e. from `#[derive(AocParse)]`) are mapped back to
+    /// the line they came from.
+    pub fn parse<T: FromStr>(&self) -> Result<T, SectionError>
+    where
+        T::Err: Display + 'static,
+    {
+        self.text().parse().map_err(|e: T::Err| {
+            match (&e as &dyn Any).downcast_ref::<ParseError>() {
+                Some(p) => {
+                    // Find the line the (1-based) column lands on:
+                    let mut col = p.column;
+                    let mut line = 0;
+                    while line + 1 < self.lines.len() && col > self.lines[line].chars().count() + 1
+                    {
+                        col -= self.lines[line].chars().count() + 1;
+                        line += 1;
+                    }
+
+                    self.error_at(line, format!("{} (column {col})", p.message))
+                }
+                None => self.error_at(0, e.to_string()),
+            }
+        })
+    }
+}
+
+/// Which section (and line) couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionError {
+    /// 1-based.
+    pub section: usize,
+    /// 1-based, in the whole input.
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for SectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "section {}, line {}: {}",
+            self.section, self.line, self.message
+        )
+    }
+}
+
+impl Error for SectionError {}
+
+/// Something that can be parsed from a single section.
+pub trait FromSection: Sized {
+    fn from_section(section: &Section<'_>) -> Result<Self, SectionError>;
+}
+
+macro_rules! from_section_via_from_str {
+    ($($ty:ty)*) => {$(
+        impl FromSection for $ty {
+            fn from_section(section: &Section<'_>) -> Result<Self, SectionError> {
+                section.parse()
+            }
+        }
+    )*};
+}
+
+from_section_via_from_str! {
+    String char bool f32 f64
+    u8 u16 u32 u64 u128 usize
+    i8 i16 i32 i64 i128 isize
+}
+
+/// Any `FromStr` type, parsed from the whole section.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Whole<T>(pub T);
+
+impl<T: FromStr> FromSection for Whole<T>
+where
+    T::Err: Display + 'static,
+{
+    fn from_section(section: &Section<'_>) -> Result<Self, SectionError> {
+        section.parse().map(Whole)
+    }
+}
+
+/// Each line of a section parsed on its own.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Lines<T>(pub Vec<T>);
+
+impl<T> Deref for Lines<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+
+impl<T> IntoIterator for Lines<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<T: FromStr> FromSection for Lines<T>
+where
+    T::Err: Display,
+{
+    fn from_section(section: &Section<'_>) -> Result<Self, SectionError> {
+        section
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                l.parse()
+                    .map_err(|e| section.error_at(i, format!("{l:?}: {e}")))
+            })
+            .collect::<Result<_, _>>()
+            .map(Lines)
+    }
+}
+
+/// A section read column by column, top to bottom; short lines are padded
+/// with spaces.
+///
+/// For a diagram like the one in 2022/05, [`Columns::alphanumeric`] picks out
+/// the stacks:
+///
+/// ```text
+///     [D]
+/// [N] [C]
+/// [Z] [M] [P]
+///  1   2   3
+/// ```
+///
+/// gives `"NZ1"`, `"DCM2"` and `"P3"`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Columns(pub Vec<String>);
+
+impl Columns {
+    /// The trimmed columns that only contain letters and digits.
+    pub fn alphanumeric(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0
+            .iter()
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty() && c.chars().all(char::is_alphanumeric))
+    }
+}
+
+impl FromSection for Columns {
+    fn from_section(section: &Section<'_>) -> Result<Self, SectionError> {
+        let rows: Vec<Vec<char>> = section.lines.iter().map(|l| l.chars().collect()).collect();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+        Ok(Columns(
+            (0..width)
+                .map(|c| rows.iter().map(|r| r.get(c).unwrap_or(&' ')).collect())
+                .collect(),
+        ))
+    }
+}
+
+/// Something that takes one or more sections off the front of `rest`.
+pub trait FromSections<'s>: Sized {
+    fn from_sections(rest: &mut &[Section<'s>]) -> Result<Self, SectionError>;
+}
+
+// `Sections::sections` fills in the location.
+fn missing(what: &str) -> SectionError {
+    SectionError {
+        section: 0,
+        line: 0,
+        message: format!("missing section ({what})"),
+    }
+}
+
+impl<'s, T: FromSection> FromSections<'s> for T {
+    fn from_sections(rest: &mut &[Section<'s>]) -> Result<Self, SectionError> {
+        let (first, tail) = rest
+            .split_first()
+            .ok_or_else(|| missing(std::any::type_name::<T>()))?;
+        *rest = tail;
+        T::from_section(first)
+    }
+}
+
+/// Takes all of the remaining sections.
+impl<'s, T: FromSection> FromSections<'s> for Vec<T> {
+    fn from_sections(rest: &mut &[Section<'s>]) -> Result<Self, SectionError> {
+        let all = std::mem::take(rest);
+        all.iter().map(T::from_section).collect()
+    }
+}
+
+macro_rules! tuple_impl {
+    ($($t:ident)+) => {
+        impl<'s, $($t: FromSections<'s>),+> FromSections<'s> for ($($t,)+) {
+            fn from_sections(rest: &mut &[Section<'s>]) -> Result<Self, SectionError> {
+                Ok(($( $t::from_sections(rest)?, )+))
+            }
+        }
+    };
+}
+
+tuple_impl!(A);
+tuple_impl!(A B);
+tuple_impl!(A B C);
+tuple_impl!(A B C D);
+tuple_impl!(A B C D E);
+tuple_impl!(A B C D E F);
+
+pub trait Sections: AsRef<str> {
+    /// The sections in the input; see the [module docs](self).
+    fn split_sections(&self) -> Vec<Section<'_>> {
+        let mut sections = vec![];
+        let mut current: Option<Section<'_>> = None;
+
+        for (idx, line) in self.as_ref().lines().enumerate() {
+            if line.trim().is_empty() {
+                sections.extend(current.take());
+                continue;
+            }
+
+            current
+                .get_or_insert_with(|| Section {
+                    index: sections.len(),
+                    start_line: idx + 1,
+                    lines: vec![],
+                })
+                .lines
+                .push(line);
+        }
+
+        sections.extend(current);
+        sections
+    }
+
+    /// Parses every section; errors if any are left over.
+    fn sections<'s, T: FromSections<'s>>(&'s self) -> Result<T, SectionError> {
+        let all = self.split_sections();
+        let mut rest = &all[..];
+
+        let res = T::from_sections(&mut rest).map_err(|mut e| {
+            if e.line == 0 {
+                e.section = all.len() - rest.len() + 1;
+                e.line = all.last().map_or(1, |s| s.start_line + s.lines.len());
+            }
+            e
+        })?;
+
+        match rest.first() {
+            None => Ok(res),
+            Some(s) => Err(s.error_at(0, "unexpected extra section")),
+        }
+    }
+}
+
+impl<S: AsRef<str>> Sections for S {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AocParse;
+
+    #[test]
+    fn splitting() {
+        let s = "\n\na\nb\n\n\n  \nc\r\n\r\nd\n\n";
+        let secs = s.split_sections();
+
+        assert_eq!(secs.len(), 3);
+        assert_eq!(secs[0].lines, ["a", "b"]);
+        assert_eq!(secs[1].lines, ["c"]);
+        assert_eq!((secs[2].index, secs[2].start_line), (2, 10));
+        assert!("\n \n".split_sections().is_empty());
+    }
+
+    #[derive(Debug, PartialEq, AocParse)]
+    #[aoc(fmt = "move {n} from {from} to {to}")]
+    struct Move {
+        n: usize,
+        from: usize,
+        to: usize,
+    }
+
+    #[test]
+    fn supply_stacks() {
+        let input = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\n";
+        let (stacks, moves): (Columns, Lines<Move>) = input.sections().unwrap();
+
+        assert_eq!(
+            stacks.alphanumeric().collect::<Vec<_>>(),
+            ["NZ1", "DCM2", "P3"]
+        );
+        assert_eq!(
+            moves[1],
+            Move {
+                n: 3,
+                from: 1,
+                to: 3
+            }
+        );
+    }
+
+    #[derive(Debug, PartialEq, AocParse)]
+    #[aoc(fmt = "{left}\n{right}")]
+    struct Pair {
+        left: String,
+        right: String,
+    }
+
+    #[test]
+    fn paragraphs() {
+        let input = "[1,1]\n[1,2]\n\n[[1]]\n[4]\n";
+        let pairs: Vec<Pair> = input.sections().unwrap();
+
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[1].right, "[4]");
+
+        let (nums, rest): (Lines<u8>, Vec<Lines<String>>) = "1\n2\n\na\n\nb\nc".sections().unwrap();
+        assert_eq!(nums.0, [1, 2]);
+        assert_eq!(rest[1].0, ["b", "c"]);
+    }
+
+    #[derive(Debug, PartialEq, AocParse)]
+    #[aoc(fmt = "{a}\n{b}")]
+    struct NumPair {
+        a: u8,
+        b: u8,
+    }
+
+    #[test]
+    fn errors() {
+        let e = "1\n2\n\n3\nx\n4"
+            .sections::<(Lines<u8>, Lines<u8>)>()
+            .unwrap_err();
+        assert_eq!((e.section, e.line), (2, 5));
+        assert!(e.to_string().starts_with("section 2, line 5: \"x\""));
+
+        let e = "1\n\n2\n\n3".sections::<(u8, u8)>().unwrap_err();
+        assert_eq!((e.section, e.line), (3, 5));
+        assert_eq!(e.message, "unexpected extra section");
+
+        let e = "1\n\n2".sections::<(u8, u8, String)>().unwrap_err();
+        assert_eq!((e.section, e.line), (3, 4));
+        assert!(e.message.starts_with("missing section"));
+
+        // Errors inside multi-line `AocParse` sections point at the right
+        // line:
+        let e = "1\n2\n\n3\nx".sections::<Vec<NumPair>>().unwrap_err();
+        assert_eq!((e.section, e.line), (2, 5));
+        assert!(e.message.ends_with("(column 1)"), "{}", e.message);
+    }
+}
@@ -0,0 +1,600 @@
+//! Recording simulations frame by frame.
+//!
+//! A [`Recorder`] collects frames (anything that's [`Display`], ANSI colors
+//! and all) and, once [finished](Recorder::finish), writes them out as an
+//! [asciicast] file, an animated GIF (with the `gif` feature) or plays them
+//! back in the terminal with pause, step and seek.
+//!
+//! Recorders made with [`Recorder::new`] do nothing unless the binary was run
+//! with `--record`:
+//!   - `--record`: play the frames back in the terminal at the end
+//!   - `--record=FILE.cast`: write an asciicast file (`asciinema play FILE.cast`)
+//!   - `--record=FILE.gif`: write an animated GIF
+//!
+//! ```
+//! use aoc::viz::Recorder;
+//!
+//! let mut rec = Recorder::new();
+//! for i in 0..10 {
+//!     rec.frame(format_args!("step {i}"));
+//! }
+//! rec.finish().unwrap(); // no `--record`, so this does nothing
+//! ```
+//!
+//! [asciicast]: https://docs.asciinema.org/manual/asciicast/v2/
+
+use std::fmt::{Display, Write as _};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+
+/// Where a [`Recorder`]'s frames end up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Cast(PathBuf),
+    Gif(PathBuf),
+    Play,
+}
+
+impl Target {
+    /// Picks a target from the `--record` flag's value: the extension of the
+    /// path, or the terminal player if there isn't one.
+    pub fn from_arg(arg: Option<&str>) -> Result<Self, String> {
+        let Some(path) = arg else {
+            return Ok(Target::Play);
+        };
+
+        let ext = Path::new(path).extension().and_then(|e| e.to_str());
+        match ext.map(str::to_ascii_lowercase).as_deref() {
+            Some("cast") => Ok(Target::Cast(path.into())),
+            Some("gif") if cfg!(feature = "gif") => Ok(Target::Gif(path.into())),
+            Some("gif") => Err("writing GIFs needs the `gif` feature".into()),
+            _ => Err(format!(
+                "don't know how to record to `{path}`; expected a `.cast` or `.gif` file"
+            )),
+        }
+    }
+}
+
+/// Set by [`Config`](crate::client::Config) when `--record` is passed.
+static TARGET: OnceCell<Target> = OnceCell::new();
+
+pub(crate) fn set_target(target: Target) {
+    let _ = TARGET.set(target);
+}
+
+/// Collects frames; see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    target: Option<Target>,
+    delay: Duration,
+    frames: Vec<String>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    /// A recorder that's only enabled if `--record` was passed.
+    pub fn new() -> Self {
+        Self {
+            target: TARGET.get().cloned(),
+            delay: Duration::from_millis(100),
+            frames: vec![],
+        }
+    }
+
+    /// A recorder that always records to `target`.
+    pub fn to(target: Target) -> Self {
+        Self {
+            target: Some(target),
+            ..Self::disabled()
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            target: None,
+            ..Self::new()
+        }
+    }
+
+    /// How long each frame is shown for; 100ms by default.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Use this to skip building expensive frames when not recording.
+    pub fn is_enabled(&self) -> bool {
+        self.target.is_some()
+    }
+
+    pub fn frame(&mut self, frame: impl Display) {
+        if self.is_enabled() {
+            self.frames.push(frame.to_string());
+        }
+    }
+
+    /// Records a grid given as rows of characters.
+    pub fn frame_grid<R, I>(&mut self, rows: R)
+    where
+        R: IntoIterator<Item = I>,
+        I: IntoIterator<Item = char>,
+    {
+        if self.is_enabled() {
+            let mut frame = String::new();
+            for row in rows {
+                frame.extend(row);
+                frame.push('\n');
+            }
+            self.frames.push(frame);
+        }
+    }
+
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+
+    /// Writes out or plays back the recorded frames.
+    pub fn finish(self) -> io::Result<()> {
+        match self.target {
+            None => Ok(()),
+            Some(Target::Cast(path)) => {
+                let file = io::BufWriter::new(std::fs::File::create(&path)?);
+                write_cast(&self.frames, self.delay, file)?;
+                eprintln!(
+                    "Wrote {} frames to `{}`.",
+                    self.frames.len(),
+                    path.display()
+                );
+                Ok(())
+            }
+            #[cfg(feature = "gif")]
+            Some(Target::Gif(path)) => {
+                let file = io::BufWriter::new(std::fs::File::create(&path)?);
+                write_gif(&self.frames, self.delay, file)?;
+                eprintln!(
+                    "Wrote {} frames to `{}`.",
+                    self.frames.len(),
+                    path.display()
+                );
+                Ok(())
+            }
+            #[cfg(not(feature = "gif"))]
+            Some(Target::Gif(_)) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "writing GIFs needs the `gif` feature",
+            )),
+            Some(Target::Play) => play(&self.frames, self.delay),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Drops ANSI escape sequences (colors, cursor movement).
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+/// `(columns, rows)` needed to show every frame.
+fn dimensions(frames: &[String]) -> (usize, usize) {
+    frames.iter().fold((1, 1), |(w, h), f| {
+        let f = strip_ansi(f);
+        let lines = f.lines();
+        let width = lines.clone().map(|l| l.chars().count()).max().unwrap_or(0);
+        (w.max(width), h.max(lines.count()))
+    })
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// Writes `frames` as an [asciicast v2] recording, one frame every `delay`.
+///
+/// [asciicast v2]: https://docs.asciinema.org/manual/asciicast/v2/
+pub fn write_cast(frames: &[String], delay: Duration, mut w: impl Write) -> io::Result<()> {
+    let (width, height) = dimensions(frames);
+    writeln!(
+        w,
+        r#"{{"version": 2, "width": {width}, "height": {height}}}"#
+    )?;
+
+    for (i, frame) in frames.iter().enumerate() {
+        let time = (delay * i as u32).as_secs_f64();
+        let data = format!("{CLEAR}{}", frame.replace('\n', "\r\n"));
+        writeln!(w, "[{time:.3}, \"o\", {}]", json_str(&data))?;
+    }
+
+    w.flush()
+}
+
+/// Writes `frames` as a looping animated GIF, one frame every `delay`.
+///
+/// Text doesn't make for a legible GIF so each character becomes a colored
+/// square instead; whitespace and `.` are background. This suits grid
+/// simulations.
+#[cfg(feature = "gif")]
+pub fn write_gif(frames: &[String], delay: Duration, w: impl Write) -> io::Result<()> {
+    use gif::{Encoder, Frame, Repeat};
+
+    const CELL: usize = 4;
+    #[rustfmt::skip]
+    const PALETTE: [u8; 16 * 3] = [
+        0x1e, 0x1e, 0x1e, // background
+        0xee, 0xee, 0xee, // `#` and friends
+        0xe0, 0x6c, 0x75, 0x98, 0xc3, 0x79, 0xe5, 0xc0, 0x7b, 0x61, 0xaf, 0xef,
+        0xc6, 0x78, 0xdd, 0x56, 0xb6, 0xc2, 0xd1, 0x9a, 0x66, 0xbe, 0x50, 0x46,
+        0x7a, 0x9f, 0x60, 0x4d, 0x78, 0xcc, 0xa6, 0x26, 0xa4, 0x0e, 0x87, 0x97,
+        0x99, 0x99, 0x99, 0x55, 0x55, 0x55,
+    ];
+    fn color(c: char) -> u8 {
+        match c {
+            c if c.is_whitespace() => 0,
+            '.' => 0,
+            '#' | '█' | '@' => 1,
+            c => 2 + (c as u32).wrapping_mul(2654435761).rotate_left(8) as u8 % 14,
+        }
+    }
+
+    let (cols, rows) = dimensions(frames);
+    let (width, height) = (cols * CELL, rows * CELL);
+    let too_big = |_| io::Error::new(io::ErrorKind::InvalidInput, "frames are too big for a GIF");
+    let (w16, h16) = (
+        u16::try_from(width).map_err(too_big)?,
+        u16::try_from(height).map_err(too_big)?,
+    );
+    let gif_err = |e: gif::EncodingError| io::Error::new(io::ErrorKind::Other, e);
+
+    let mut enc = Encoder::new(w, w16, h16, &PALETTE).map_err(gif_err)?;
+    enc.set_repeat(Repeat::Infinite).map_err(gif_err)?;
+
+    let mut pixels = vec![0; width * height];
+    for frame in frames {
+        pixels.fill(0);
+        for (y, line) in strip_ansi(frame).lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let idx = color(c);
+                for row in 0..CELL {
+                    let start = (y * CELL + row) * width + x * CELL;
+                    pixels[start..start + CELL].fill(idx);
+                }
+            }
+        }
+
+        let mut frame = Frame::from_indexed_pixels(w16, h16, &pixels, None);
+        frame.delay = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+        enc.write_frame(&frame).map_err(gif_err)?;
+    }
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Pause,
+    Next,
+    Prev,
+    /// Jump back/forward by a tenth of the recording.
+    Back,
+    Forward,
+    /// Jump to the given tenth of the recording.
+    Seek(u8),
+    Faster,
+    Slower,
+    Quit,
+}
+
+fn parse_keys(buf: &[u8]) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut it = buf.iter().copied();
+    while let Some(b) = it.next() {
+        let key = match b {
+            b' ' => Key::Pause,
+            b'l' | b'.' => Key::Next,
+            b'h' | b',' => Key::Prev,
+            b'[' => Key::Back,
+            b']' => Key::Forward,
+            b'0'..=b'9' => Key::Seek(b - b'0'),
+            b'+' | b'=' => Key::Faster,
+            b'-' => Key::Slower,
+            b'q' | 0x04 => Key::Quit,
+            // Arrow keys: `ESC [ C` and `ESC [ D`.
+            0x1b => match (it.next(), it.next()) {
+                (Some(b'['), Some(b'C')) => Key::Next,
+                (Some(b'['), Some(b'D')) => Key::Prev,
+                (None, _) => Key::Quit,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        keys.push(key);
+    }
+
+    keys
+}
+
+/// Playback state for [`play`].
+#[derive(Debug)]
+struct Player {
+    len: usize,
+    idx: usize,
+    paused: bool,
+    /// `delay` is divided by `2^speed`.
+    speed: i32,
+}
+
+impl Player {
+    fn new(len: usize) -> Self {
+        Self {
+            len,
+            idx: 0,
+            paused: false,
+            speed: 0,
+        }
+    }
+
+    fn seek(&mut self, idx: isize) {
+        self.idx = idx.clamp(0, self.len as isize - 1) as usize;
+    }
+
+    /// Returns `false` on quit.
+    fn key(&mut self, key: Key) -> bool {
+        let tenth = (self.len / 10).max(1) as isize;
+        let idx = self.idx as isize;
+
+        match key {
+            Key::Pause => self.paused = !self.paused,
+            Key::Next | Key::Prev => {
+                self.paused = true;
+                self.seek(idx + if key == Key::Next { 1 } else { -1 });
+            }
+            Key::Back => self.seek(idx - tenth),
+            Key::Forward => self.seek(idx + tenth),
+            Key::Seek(n) => self.seek(n as isize * self.len as isize / 10),
+            Key::Faster => self.speed = (self.speed + 1).min(8),
+            Key::Slower => self.speed = (self.speed - 1).max(-8),
+            Key::Quit => return false,
+        }
+
+        true
+    }
+
+    /// Advances one frame unless paused; pauses on the last frame.
+    fn tick(&mut self) {
+        if !self.paused {
+            self.seek(self.idx as isize + 1);
+            self.paused = self.idx + 1 == self.len;
+        }
+    }
+
+    fn delay(&self, delay: Duration) -> Duration {
+        if self.speed >= 0 {
+            delay / (1 << self.speed)
+        } else {
+            delay * (1 << -self.speed)
+        }
+    }
+}
+
+/// Puts the terminal into non-canonical mode (keypresses are sent right away
+/// and not echoed) until dropped.
+struct RawMode(Option<String>);
+
+impl RawMode {
+    fn stty(args: &[&str]) -> Option<String> {
+        let out = Command::new("stty")
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        out.status
+            .success()
+            .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+
+    fn enable() -> Self {
+        let saved = Self::stty(&["-g"]);
+        if saved.is_some() {
+            Self::stty(&["-icanon", "-echo", "min", "1"]);
+        }
+        RawMode(saved)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.0 {
+            Self::stty(&[saved]);
+        }
+    }
+}
+
+/// Plays `frames` back in the terminal.
+///
+/// Keys: `space` pauses, `←`/`→` (or `h`/`l`) step, `[`/`]` seek by a tenth,
+/// `0`-`9` jump to that tenth, `+`/`-` change the speed and `q` quits. If
+/// stdin isn't a terminal the frames just play through once.
+pub fn play(frames: &[String], delay: Duration) -> io::Result<()> {
+    if frames.is_empty() {
+        return Ok(());
+    }
+
+    let _raw = RawMode::enable();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = [0; 16];
+        let mut stdin = io::stdin();
+        while let Ok(n @ 1..) = stdin.read(&mut buf) {
+            for key in parse_keys(&buf[..n]) {
+                if tx.send(key).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut player = Player::new(frames.len());
+    let mut out = io::stdout().lock();
+    loop {
+        write!(out, "{CLEAR}{}", frames[player.idx])?;
+        writeln!(
+            out,
+            "\n\x1b[2m[{}/{}{}{}] space: pause, ←/→: step, [/]/0-9: seek, +/-: speed, q: quit\x1b[0m",
+            player.idx + 1,
+            player.len,
+            if player.paused { ", paused" } else { "" },
+            match player.speed {
+                0 => String::new(),
+                s => format!(", {}x", 2f64.powi(s)),
+            },
+        )?;
+        out.flush()?;
+
+        match rx.recv_timeout(player.delay(delay)) {
+            Ok(key) => {
+                if !player.key(key) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => player.tick(),
+            Err(RecvTimeoutError::Disconnected) => {
+                if player.paused {
+                    break;
+                }
+                thread::sleep(player.delay(delay));
+                player.tick();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets() {
+        assert_eq!(Target::from_arg(None), Ok(Target::Play));
+        assert_eq!(
+            Target::from_arg(Some("out/run.CAST")),
+            Ok(Target::Cast("out/run.CAST".into()))
+        );
+        assert!(Target::from_arg(Some("run.mp4")).is_err());
+        assert_eq!(
+            Target::from_arg(Some("a.gif")).is_ok(),
+            cfg!(feature = "gif")
+        );
+
+        let mut rec = Recorder::disabled();
+        rec.frame("nope");
+        assert!(rec.frames().is_empty());
+    }
+
+    #[test]
+    fn cast() {
+        let mut rec = Recorder::to(Target::Play).delay(Duration::from_millis(250));
+        rec.frame("\x1b[31m#\x1b[0m.\n..\n");
+        rec.frame_grid(["a\"b", "\\"].iter().map(|r| r.chars()));
+        assert_eq!(dimensions(rec.frames()), (3, 2));
+
+        let mut out = vec![];
+        write_cast(rec.frames(), rec.delay, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines[0], r#"{"version": 2, "width": 3, "height": 2}"#);
+        assert_eq!(
+            lines[1],
+            r#"[0.000, "o", "\u001b[H\u001b[2J\u001b[31m#\u001b[0m.\r\n..\r\n"]"#
+        );
+        assert_eq!(
+            lines[2],
+            r#"[0.250, "o", "\u001b[H\u001b[2Ja\"b\r\n\\\r\n"]"#
+        );
+    }
+
+    #[test]
+    fn player() {
+        assert_eq!(
+            parse_keys(b" l\x1b[D9q\x1b[A"),
+            [Key::Pause, Key::Next, Key::Prev, Key::Seek(9), Key::Quit]
+        );
+
+        let mut p = Player::new(20);
+        p.tick();
+        assert_eq!((p.idx, p.paused), (1, false));
+        assert!(p.key(Key::Prev));
+        assert_eq!((p.idx, p.paused), (0, true));
+        p.tick();
+        assert_eq!(p.idx, 0);
+
+        p.key(Key::Seek(9));
+        assert_eq!(p.idx, 18);
+        p.key(Key::Forward);
+        assert_eq!(p.idx, 19);
+        p.key(Key::Pause);
+        p.tick();
+        assert_eq!((p.idx, p.paused), (19, true));
+
+        p.key(Key::Slower);
+        assert_eq!(
+            p.delay(Duration::from_millis(100)),
+            Duration::from_millis(200)
+        );
+        assert!(!p.key(Key::Quit));
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn gif() {
+        let mut out = vec![];
+        let frames = ["#.\n.#".to_string(), "\x1b[1m.#\x1b[0m\n#.".to_string()];
+        write_gif(&frames, Duration::from_millis(50), &mut out).unwrap();
+        assert!(out.starts_with(b"GIF89a"));
+    }
+}
@@ -0,0 +1,651 @@
+//! A tiny framework for the "elf CPU" puzzles.
+//!
+//! Instruction sets are declared with [`instruction_set!`](crate::instruction_set)
+//! from a table of mnemonics, operands and bodies; this generates the enum,
+//! its [`FromStr`] impl and its [`Instruction`] impl:
+//!
+//! ```
+//! use aoc::vm::{Outcome, Vm};
+//!
+//! #[derive(Debug, Clone, Default)]
+//! struct State {
+//!     acc: i64,
+//! }
+//!
+//! aoc::instruction_set! {
+//!     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//!     enum Insn for State {
+//!         "nop" Nop(n: i64) => |cpu| {},
+//!         "acc" Acc(n: i64) => |cpu| { cpu.state.acc += n },
+//!         "jmp" Jmp(off: i64) => |cpu| { cpu.jump(off) },
+//!     }
+//! }
+//!
+//! let prog = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+//! let mut vm: Vm<Insn> = Vm::parse(prog, State::default()).unwrap();
+//! assert_eq!(vm.run_until_pc_repeats(), Outcome::Looped { pc: 1 });
+//! assert_eq!(vm.cpu.state.acc, 5);
+//! ```
+//!
+//! Every instruction advances the pc by one unless its body calls
+//! [`Cpu::jump`]/[`Cpu::jump_to`]; the machine halts when the pc leaves the
+//! program or an instruction calls [`Cpu::halt`].
+//!
+//! Instructions can take more than one cycle (`"addx" [2] Addx(n: i64) => ...`);
+//! the `_with` variants of the run methods call a hook on every cycle, _before_
+//! the instruction's effects are applied.
+
+use std::fmt::{self, Debug, Display};
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+use crate::iterator_map_ext::{IterMapExt, Report};
+
+/// Implemented by [`instruction_set!`](crate::instruction_set).
+pub trait Instruction {
+    /// Whatever the instructions operate on besides the pc (registers, an
+    /// accumulator, etc.).
+    type State;
+
+    fn cycles(&self) -> u32 {
+        1
+    }
+
+    fn exec(&self, cpu: &mut Cpu<Self::State>);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cpu<S> {
+    pub pc: i64,
+    /// The number of cycles that have started.
+    pub cycle: u64,
+    pub state: S,
+    jumped: bool,
+    halted: bool,
+}
+
+impl<S> Cpu<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            pc: 0,
+            cycle: 0,
+            state,
+            jumped: false,
+            halted: false,
+        }
+    }
+
+    /// Relative to the current instruction.
+    pub fn jump(&mut self, offset: i64) {
+        self.jump_to(self.pc + offset)
+    }
+
+    pub fn jump_to(&mut self, pc: i64) {
+        self.pc = pc;
+        self.jumped = true;
+    }
+
+    /// Stops the machine, leaving the pc on this instruction.
+    pub fn halt(&mut self) {
+        self.halted = true;
+    }
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Halted,
+    /// The instruction at `pc` was about to run for a second time.
+    Looped {
+        pc: i64,
+    },
+    OutOfBudget,
+}
+
+pub struct Vm<I: Instruction> {
+    program: Vec<I>,
+    initial: I::State,
+    pub cpu: Cpu<I::State>,
+}
+
+impl<I: Instruction + Debug> Debug for Vm<I>
+where
+    I::State: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vm")
+            .field("cpu", &self.cpu)
+            .field("program", &self.program)
+            .finish()
+    }
+}
+
+impl<I: Instruction> Vm<I> {
+    pub fn new(program: Vec<I>, state: I::State) -> Self
+    where
+        I::State: Clone,
+    {
+        Self {
+            program,
+            initial: state.clone(),
+            cpu: Cpu::new(state),
+        }
+    }
+
+    /// One instruction per line; reports every line that doesn't parse.
+    pub fn parse(src: &str, state: I::State) -> Result<Self, Report<I::Err, &str>>
+    where
+        I: FromStr,
+        I::State: Clone,
+    {
+        let program = src.lines().try_map_parse().collect_report()?;
+        Ok(Self::new(program, state))
+    }
+
+    pub fn program(&self) -> &[I] {
+        &self.program
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.cpu.halted || usize::try_from(self.cpu.pc).map_or(true, |pc| pc >= self.program.len())
+    }
+
+    /// Puts the CPU back in its initial state (leaving any patches in place).
+    pub fn reset(&mut self)
+    where
+        I::State: Clone,
+    {
+        self.cpu = Cpu::new(self.initial.clone());
+    }
+
+    /// Replaces the instruction at `idx`, returning the old one.
+    pub fn patch(&mut self, idx: usize, insn: I) -> I {
+        std::mem::replace(&mut self.program[idx], insn)
+    }
+
+    /// Runs one instruction; `false` if the machine had already halted.
+    pub fn step(&mut self) -> bool {
+        self.step_with(|_| {})
+    }
+
+    pub fn step_with(&mut self, mut hook: impl FnMut(&Cpu<I::State>)) -> bool {
+        if self.is_halted() {
+            return false;
+        }
+
+        let insn = &self.program[self.cpu.pc as usize];
+        for _ in 0..insn.cycles() {
+            self.cpu.cycle += 1;
+            hook(&self.cpu);
+        }
+
+        self.cpu.jumped = false;
+        insn.exec(&mut self.cpu);
+        if !(self.cpu.jumped || self.cpu.halted) {
+            self.cpu.pc += 1;
+        }
+
+        true
+    }
+
+    pub fn run_until_halt(&mut self) {
+        self.run_until_halt_with(|_| {})
+    }
+
+    pub fn run_until_halt_with(&mut self, mut hook: impl FnMut(&Cpu<I::State>)) {
+        while self.step_with(&mut hook) {}
+    }
+
+    /// Stops just before an instruction would run for the second time.
+    pub fn run_until_pc_repeats(&mut self) -> Outcome {
+        let mut seen = vec![false; self.program.len()];
+
+        loop {
+            if self.is_halted() {
+                break Outcome::Halted;
+            }
+
+            let pc = self.cpu.pc;
+            if std::mem::replace(&mut seen[pc as usize], true) {
+                break Outcome::Looped { pc };
+            }
+
+            self.step();
+        }
+    }
+
+    /// Runs at most `steps` instructions.
+    pub fn run_with_budget(&mut self, steps: usize) -> Outcome {
+        self.run_with_budget_with(steps, |_| {})
+    }
+
+    pub fn run_with_budget_with(
+        &mut self,
+        steps: usize,
+        mut hook: impl FnMut(&Cpu<I::State>),
+    ) -> Outcome {
+        for _ in 0..steps {
+            if !self.step_with(&mut hook) {
+                return Outcome::Halted;
+            }
+        }
+
+        if self.is_halted() {
+            Outcome::Halted
+        } else {
+            Outcome::OutOfBudget
+        }
+    }
+
+    /// Tries replacing each instruction that `candidate` has a replacement for
+    /// (one at a time, from a fresh CPU) until `check` returns something.
+    ///
+    /// The program is left unpatched and the CPU in whatever state the
+    /// successful `check` left it in.
+    pub fn find_patch<T>(
+        &mut self,
+        mut candidate: impl FnMut(&I) -> Option<I>,
+        mut check: impl FnMut(&mut Self) -> Option<T>,
+    ) -> Option<(usize, T)>
+    where
+        I::State: Clone,
+    {
+        for idx in 0..self.program.len() {
+            let Some(new) = candidate(&self.program[idx]) else {
+                continue;
+            };
+
+            let old = self.patch(idx, new);
+            self.reset();
+            let res = check(self);
+            self.patch(idx, old);
+
+            if let Some(res) = res {
+                return Some((idx, res));
+            }
+        }
+
+        None
+    }
+}
+
+/// A register name: `a` through `z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reg(pub u8);
+
+impl FromStr for Reg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.as_bytes() {
+            &[c @ b'a'..=b'z'] => Ok(Reg(c - b'a')),
+            _ => Err(format!("{s:?} isn't a register")),
+        }
+    }
+}
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", (b'a' + self.0) as char)
+    }
+}
+
+/// An operand that's either a register or an immediate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Src<T = i64> {
+    Reg(Reg),
+    Imm(T),
+}
+
+impl<T: FromStr> FromStr for Src<T> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        s.parse()
+            .map(Src::Imm)
+            .or_else(|_| s.parse().map(Src::Reg))
+            .map_err(|_| format!("{s:?} isn't a register or an immediate"))
+    }
+}
+
+impl<T: Display> Display for Src<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Src::Reg(r) => write!(f, "{r}"),
+            Src::Imm(i) => write!(f, "{i}"),
+        }
+    }
+}
+
+/// `N` registers, indexed by [`Reg`] (or `usize`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Regs<const N: usize, T = i64>(pub [T; N]);
+
+impl<const N: usize, T: Default + Copy> Default for Regs<N, T> {
+    fn default() -> Self {
+        Self([T::default(); N])
+    }
+}
+
+impl<const N: usize, T: Copy> Regs<N, T> {
+    pub fn val(&self, src: Src<T>) -> T {
+        match src {
+            Src::Reg(r) => self[r],
+            Src::Imm(i) => i,
+        }
+    }
+}
+
+impl<const N: usize, T> Index<Reg> for Regs<N, T> {
+    type Output = T;
+
+    fn index(&self, r: Reg) -> &T {
+        &self.0[r.0 as usize]
+    }
+}
+
+impl<const N: usize, T> IndexMut<Reg> for Regs<N, T> {
+    fn index_mut(&mut self, r: Reg) -> &mut T {
+        &mut self.0[r.0 as usize]
+    }
+}
+
+impl<const N: usize, T> Index<usize> for Regs<N, T> {
+    type Output = T;
+
+    fn index(&self, idx: usize) -> &T {
+        &self.0[idx]
+    }
+}
+
+impl<const N: usize, T> IndexMut<usize> for Regs<N, T> {
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        &mut self.0[idx]
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::*;
+    use crate::parse::ParseError;
+
+    /// Splits a line into its mnemonic and operands (separated by spaces
+    /// and/or commas) along with their byte offsets.
+    pub fn split(s: &str) -> (&str, std::vec::IntoIter<(usize, &str)>) {
+        let mut toks = s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty())
+            .map(|t| (t.as_ptr() as usize - s.as_ptr() as usize, t));
+
+        let mn = toks.next().map_or("", |(_, t)| t);
+        (mn, toks.collect::<Vec<_>>().into_iter())
+    }
+
+    fn err(ty: &'static str, input: &str, at: usize, message: String) -> ParseError {
+        ParseError {
+            ty,
+            column: input[..at].chars().count() + 1,
+            message,
+            input: input.to_string(),
+        }
+    }
+
+    pub fn operand<T: FromStr>(
+        ty: &'static str,
+        input: &str,
+        name: &str,
+        ops: &mut std::vec::IntoIter<(usize, &str)>,
+    ) -> Result<T, ParseError>
+    where
+        T::Err: Display,
+    {
+        let Some((at, tok)) = ops.next() else {
+            return Err(err(ty, input, input.len(), format!("missing `{name}`")));
+        };
+
+        tok.parse()
+            .map_err(|e| err(ty, input, at, format!("invalid `{name}` ({tok:?}): {e}")))
+    }
+
+    pub fn finish(
+        ty: &'static str,
+        input: &str,
+        mut ops: std::vec::IntoIter<(usize, &str)>,
+    ) -> Result<(), ParseError> {
+        match ops.next() {
+            None => Ok(()),
+            Some((at, tok)) => Err(err(ty, input, at, format!("unexpected operand {tok:?}"))),
+        }
+    }
+
+    pub fn unknown(ty: &'static str, input: &str, mn: &str, known: &[&str]) -> ParseError {
+        let at = input.find(mn).unwrap_or(0);
+        err(
+            ty,
+            input,
+            at,
+            format!("unknown instruction {mn:?}; expected one of {known:?}"),
+        )
+    }
+}
+
+/// Declares an instruction set; see the [`vm`](crate::vm) module docs.
+///
+/// ```ignore
+/// instruction_set! {
+///     #[derive(Debug, Clone, Copy)]
+///     pub enum Insn for State {
+///         "mnemonic" [cycles] Variant(operand: Type, ...) => |cpu| { body },
+///         ...
+///     }
+/// }
+/// ```
+///
+/// `[cycles]` is optional and defaults to 1. Operands are parsed with
+/// [`FromStr`] and are separated by whitespace and/or commas. Bodies get the
+/// operands by value (so they should be `Clone`) and `cpu`, a
+/// `&mut Cpu<State>`.
+#[macro_export]
+macro_rules! instruction_set {
+    (@cycles) => { 1 };
+    (@cycles $c:expr) => { $c };
+
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident for $state:ty {
+            $(
+                $(#[$vmeta:meta])*
+                $mn:literal $([$cycles:expr])? $var:ident($($arg:ident: $ty:ty),* $(,)?)
+                    => |$cpu:ident| $body:block
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$vmeta])*
+                $var($($ty),*),
+            )*
+        }
+
+        impl $name {
+            #[allow(dead_code)]
+            pub fn mnemonic(&self) -> &'static str {
+                match self {
+                    $( $name::$var(..) => $mn, )*
+                }
+            }
+        }
+
+        impl $crate::vm::Instruction for $name {
+            type State = $state;
+
+            fn cycles(&self) -> u32 {
+                match self {
+                    $( $name::$var(..) => $crate::instruction_set!(@cycles $($cycles)?), )*
+                }
+            }
+
+            #[allow(unused_variables, clippy::unused_unit)]
+            fn exec(&self, cpu: &mut $crate::vm::Cpu<$state>) {
+                match self {
+                    $(
+                        $name::$var($($arg),*) => {
+                            $( let $arg = ::std::clone::Clone::clone($arg); )*
+                            let $cpu = cpu;
+                            $body
+                        }
+                    )*
+                }
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = $crate::parse::ParseError;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                use $crate::vm::__private as p;
+                const TY: &str = ::std::stringify!($name);
+
+                #[allow(unused_mut)]
+                let (mn, mut ops) = p::split(s);
+                match mn {
+                    $(
+                        $mn => {
+                            let insn = $name::$var(
+                                $( p::operand::<$ty>(TY, s, ::std::stringify!($arg), &mut ops)? ),*
+                            );
+                            p::finish(TY, s, ops)?;
+                            ::std::result::Result::Ok(insn)
+                        }
+                    )*
+                    other => ::std::result::Result::Err(p::unknown(TY, s, other, &[$($mn),*])),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type R = Regs<4>;
+
+    crate::instruction_set! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Asm for R {
+            "cpy" Cpy(src: Src, dst: Reg) => |cpu| { cpu.state[dst] = cpu.state.val(src) },
+            "inc" Inc(r: Reg) => |cpu| { cpu.state[r] += 1 },
+            "dec" Dec(r: Reg) => |cpu| { cpu.state[r] -= 1 },
+            "jnz" Jnz(c: Src, off: Src) => |cpu| {
+                if cpu.state.val(c) != 0 {
+                    cpu.jump(cpu.state.val(off))
+                }
+            },
+            "hlt" Hlt() => |cpu| { cpu.halt() },
+        }
+    }
+
+    #[test]
+    fn registers() {
+        let mut vm: Vm<Asm> = Vm::parse(
+            "cpy 41 a\ninc a\ninc a\ndec a\njnz a 2\ndec a",
+            R::default(),
+        )
+        .unwrap();
+        vm.run_until_halt();
+        assert_eq!(vm.cpu.state[Reg(0)], 42);
+        assert_eq!((vm.cpu.pc, vm.cpu.cycle), (6, 5));
+        assert!(!vm.step());
+
+        let mut vm: Vm<Asm> = Vm::parse("inc b\nhlt\ninc b", R::default()).unwrap();
+        assert_eq!(vm.run_with_budget(10), Outcome::Halted);
+        assert_eq!((vm.cpu.state[1], vm.cpu.pc), (1, 1));
+
+        let mut vm: Vm<Asm> = Vm::parse("inc c\njnz 1 -1", R::default()).unwrap();
+        assert_eq!(vm.run_with_budget(7), Outcome::OutOfBudget);
+        assert_eq!(vm.cpu.state[2], 4);
+        vm.reset();
+        assert_eq!(vm.run_until_pc_repeats(), Outcome::Looped { pc: 0 });
+        assert_eq!(vm.cpu.state[2], 1);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "cpy b, c".parse::<Asm>(),
+            Ok(Asm::Cpy(Src::Reg(Reg(1)), Reg(2)))
+        );
+        assert_eq!(Asm::Hlt().mnemonic(), "hlt");
+
+        let e = "cpy 1 2".parse::<Asm>().unwrap_err();
+        assert_eq!((e.ty, e.column), ("Asm", 7));
+        assert!(e.message.starts_with("invalid `dst`"));
+        assert!("inc"
+            .parse::<Asm>()
+            .unwrap_err()
+            .message
+            .contains("missing `r`"));
+        assert_eq!("inc a b".parse::<Asm>().unwrap_err().column, 7);
+        assert!("mul a b"
+            .parse::<Asm>()
+            .unwrap_err()
+            .message
+            .contains("unknown"));
+
+        let report = Vm::<Asm>::parse("inc a\nnop\ninc 3", R::default()).unwrap_err();
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.errors[1].line, 3);
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct Acc(i64);
+
+    crate::instruction_set! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Handheld for Acc {
+            "nop" Nop(n: i64) => |cpu| {},
+            "acc" Add(n: i64) => |cpu| { cpu.state.0 += n },
+            "jmp" Jmp(off: i64) => |cpu| { cpu.jump(off) },
+        }
+    }
+
+    #[test]
+    fn patching() {
+        let prog = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+        let mut vm: Vm<Handheld> = Vm::parse(prog, Acc::default()).unwrap();
+
+        let flip = |i: &Handheld| match *i {
+            Handheld::Nop(n) => Some(Handheld::Jmp(n)),
+            Handheld::Jmp(n) => Some(Handheld::Nop(n)),
+            Handheld::Add(_) => None,
+        };
+        let fixed = vm.find_patch(flip, |vm| {
+            (vm.run_until_pc_repeats() == Outcome::Halted).then_some(vm.cpu.state.0)
+        });
+        assert_eq!(fixed, Some((7, 8)));
+        assert_eq!(vm.program()[7], Handheld::Jmp(-4));
+    }
+
+    #[derive(Debug, Clone)]
+    struct X(i64);
+
+    crate::instruction_set! {
+        #[derive(Debug, Clone, Copy)]
+        enum Crt for X {
+            "noop" Noop() => |cpu| {},
+            "addx" [2] Addx(n: i64) => |cpu| { cpu.state.0 += n },
+        }
+    }
+
+    #[test]
+    fn cycles() {
+        let mut vm: Vm<Crt> = Vm::parse("noop\naddx 3\naddx -5", X(1)).unwrap();
+        let mut during = vec![];
+        vm.run_until_halt_with(|cpu| during.push((cpu.cycle, cpu.state.0)));
+
+        assert_eq!(during, [(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(vm.cpu.state.0, -1);
+    }
+}
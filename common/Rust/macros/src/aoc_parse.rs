@@ -0,0 +1,296 @@
+//! `#[derive(AocParse)]`; see `aoc::parse` for the runtime half and docs.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Attribute, Data, DeriveInput, Error, Fields, Lit, Meta, NestedMeta};
+
+enum Seg {
+    Lit(String),
+    Field { name: String, sep: Option<String> },
+}
+
+/// Splits a format string into literals and `{field}`/`{field:sep}`
+/// placeholders.
+fn parse_fmt(fmt: &str, span: Span) -> Result<Vec<Seg>, Error> {
+    let err = |msg: &str| Error::new(span, format!("{msg} in format string {fmt:?}"));
+
+    let mut segs = vec![];
+    let mut lit = String::new();
+    let mut chars = fmt.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                lit.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                lit.push('}');
+            }
+            '}' => return Err(err("unmatched `}`")),
+            '{' => {
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(err("unterminated `{`")),
+                    }
+                }
+
+                let (name, sep) = match inner.split_once(':') {
+                    Some((n, s)) => (n, Some(s.to_string())),
+                    None => (inner.as_str(), None),
+                };
+                if name.is_empty() {
+                    return Err(err("empty placeholder"));
+                }
+
+                if !lit.is_empty() {
+                    segs.push(Seg::Lit(std::mem::take(&mut lit)));
+                } else if let Some(Seg::Field { .. }) = segs.last() {
+                    return Err(err("placeholders must be separated by some literal text"));
+                }
+
+                segs.push(Seg::Field {
+                    name: name.trim().to_string(),
+                    sep,
+                });
+            }
+            c => lit.push(c),
+        }
+    }
+
+    if !lit.is_empty() {
+        segs.push(Seg::Lit(lit));
+    }
+
+    Ok(segs)
+}
+
+/// The `fmt` in an `#[aoc(fmt = "...")]` attribute, if there is one.
+fn fmt_attr(attrs: &[Attribute]) -> Result<Option<(String, Span)>, Error> {
+    let mut found = None;
+    for attr in attrs.iter().filter(|a| a.path.is_ident("aoc")) {
+        let Meta::List(list) = attr.parse_meta()? else {
+            return Err(Error::new_spanned(attr, "expected `#[aoc(fmt = \"...\")]`"));
+        };
+
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("fmt") => {
+                    let Lit::Str(s) = &nv.lit else {
+                        return Err(Error::new_spanned(nv.lit, "`fmt` must be a string"));
+                    };
+                    if found.is_some() {
+                        return Err(Error::new_spanned(nv, "`fmt` given more than once"));
+                    }
+
+                    found = Some((s.value(), s.span()));
+                }
+                other => {
+                    return Err(Error::new_spanned(other, "unknown option; expected `fmt`"));
+                }
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// If `ty` is `Vec<T>`, `T`.
+fn vec_elem(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(p) = ty else { return None };
+    let last = p.path.segments.last()?;
+    if last.ident != "Vec" {
+        return None;
+    }
+
+    let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(t) if args.args.len() == 1 => Some(t),
+        _ => None,
+    }
+}
+
+/// Code that parses `fields` out of a `Cursor` called `c` following `fmt`
+/// and then evaluates to `ctor { fields }`.
+fn parse_fields(
+    fields: &Fields,
+    fmt: &str,
+    span: Span,
+    ctor: TokenStream,
+) -> Result<TokenStream, Error> {
+    let segs = parse_fmt(fmt, span)?;
+
+    let names: Vec<String> = match fields {
+        Fields::Named(f) => f
+            .named
+            .iter()
+            .map(|f| f.ident.as_ref().unwrap().to_string())
+            .collect(),
+        Fields::Unnamed(f) => (0..f.unnamed.len()).map(|i| i.to_string()).collect(),
+        Fields::Unit => vec![],
+    };
+    let field_list: Vec<&syn::Field> = fields.iter().collect();
+    let var = |idx: usize| format_ident!("__field_{}", idx);
+
+    let mut used = vec![false; names.len()];
+    let mut steps = vec![];
+    for (i, seg) in segs.iter().enumerate() {
+        match seg {
+            Seg::Lit(l) => steps.push(quote! { c.literal(#l)?; }),
+            Seg::Field { name, sep } => {
+                let Some(idx) = names.iter().position(|n| n == name) else {
+                    return Err(Error::new(span, format!("no field named `{name}`")));
+                };
+                if std::mem::replace(&mut used[idx], true) {
+                    return Err(Error::new(span, format!("`{name}` is used more than once")));
+                }
+
+                let next = match segs.get(i + 1) {
+                    Some(Seg::Lit(l)) => quote! { ::std::option::Option::Some(#l) },
+                    _ => quote! { ::std::option::Option::None },
+                };
+
+                let v = var(idx);
+                let ty = &field_list[idx].ty;
+                steps.push(match (vec_elem(ty), sep) {
+                    (Some(elem), sep) => {
+                        let sep = match sep {
+                            Some(s) => quote! { ::std::option::Option::Some(#s) },
+                            None => quote! { ::std::option::Option::None },
+                        };
+                        quote! { let #v = c.vec_field::<#elem>(#name, #sep, #next)?; }
+                    }
+                    (None, Some(_)) => {
+                        return Err(Error::new(
+                            span,
+                            format!("`{name}` has a separator but isn't a `Vec`"),
+                        ))
+                    }
+                    (None, None) => quote! { let #v = c.field::<#ty>(#name, #next)?; },
+                });
+            }
+        }
+    }
+
+    if let Some(idx) = used.iter().position(|u| !u) {
+        return Err(Error::new(
+            field_list[idx].span(),
+            format!("`{}` isn't in the format string {fmt:?}", names[idx]),
+        ));
+    }
+
+    let vars = (0..names.len()).map(var);
+    let construct = match fields {
+        Fields::Named(f) => {
+            let idents = f.named.iter().map(|f| f.ident.as_ref().unwrap());
+            quote! { #ctor { #( #idents: #vars ),* } }
+        }
+        Fields::Unnamed(_) => quote! { #ctor ( #( #vars ),* ) },
+        Fields::Unit => quote! { #ctor },
+    };
+
+    Ok(quote! {
+        #( #steps )*
+        c.finish()?;
+        ::std::result::Result::Ok(#construct)
+    })
+}
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream, Error> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(s) => {
+            let Some((fmt, span)) = fmt_attr(&input.attrs)? else {
+                return Err(Error::new_spanned(
+                    name,
+                    "expected an `#[aoc(fmt = \"...\")]` attribute",
+                ));
+            };
+
+            parse_fields(&s.fields, &fmt, span, quote! { Self })?
+        }
+        Data::Enum(e) => {
+            if e.variants.is_empty() {
+                return Err(Error::new_spanned(
+                    name,
+                    "can't parse an enum with no variants",
+                ));
+            }
+
+            let mut prefixes = vec![];
+            let mut arms = vec![];
+            for (i, v) in e.variants.iter().enumerate() {
+                let (fmt, span) = match (fmt_attr(&v.attrs)?, &v.fields) {
+                    (Some(f), _) => f,
+                    (None, Fields::Unit) => (v.ident.to_string(), v.ident.span()),
+                    (None, _) => {
+                        return Err(Error::new_spanned(
+                            v,
+                            "variants with fields need an `#[aoc(fmt = \"...\")]` attribute",
+                        ))
+                    }
+                };
+
+                let prefix = match parse_fmt(&fmt, span)?.first() {
+                    Some(Seg::Lit(l)) => l.clone(),
+                    _ => String::new(),
+                };
+                prefixes.push(prefix);
+
+                let ident = &v.ident;
+                let parse = parse_fields(&v.fields, &fmt, span, quote! { Self::#ident })?;
+                arms.push(quote! { #i => { #parse } });
+            }
+
+            quote! {
+                match c.pick(&[ #( #prefixes ),* ])? {
+                    #( #arms )*
+                    _ => ::std::unreachable!(),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                name,
+                "can't derive `AocParse` for unions",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::std::str::FromStr for #name #ty_generics #where_clause {
+            type Err = ::aoc::parse::ParseError;
+
+            #[allow(unused_mut)]
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                let mut c = ::aoc::parse::__private::Cursor::new(s, #name_str);
+                #body
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<&str> for #name #ty_generics #where_clause {
+            type Error = ::aoc::parse::ParseError;
+
+            fn try_from(s: &str) -> ::std::result::Result<Self, Self::Error> {
+                s.parse()
+            }
+        }
+
+        impl #impl_generics ::aoc::sections::FromSection for #name #ty_generics #where_clause {
+            fn from_section(
+                section: &::aoc::sections::Section<'_>,
+            ) -> ::std::result::Result<Self, ::aoc::sections::SectionError> {
+                section.parse()
+            }
+        }
+    })
+}
@@ -1,55 +1,52 @@
 #![recursion_limit = "128"]
 
 use proc_macro::TokenStream;
-use quote::quote;
-use std::iter;
-use syn::{parse_macro_input, AttributeArgs, DeriveInput};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, spanned::Spanned, AttributeArgs, DeriveInput, ItemMod};
 
+mod aoc_parse;
+mod solution;
+
+#[derive(Default)]
 enum Looping {
+    #[default]
     Loop,
     Once,
 }
 
 impl Looping {
     // TODO: use From
-    fn into(s: &str) -> ConfigOptions {
-        ConfigOptions::L(match s.to_lowercase().as_str() {
+    fn into(s: &str) -> Result<ConfigOptions, &'static str> {
+        Ok(ConfigOptions::L(match s.to_lowercase().as_str() {
             "loop" => Looping::Loop,
             "once" => Looping::Once,
-            _ => panic!("Expected either `Loop` or `Once` as the first arg."),
-        })
-    }
-}
-
-impl Default for Looping {
-    fn default() -> Self {
-        Looping::Loop
+            _ => return Err("Expected either `Loop` or `Once` as the first arg."),
+        }))
     }
 }
 
+#[derive(Default)]
 enum Mutability {
+    #[default]
     DoNot,
     Modify,
 }
 
 impl Mutability {
     // TODO: use From
-    fn into(s: &str) -> ConfigOptions {
-        ConfigOptions::M(match s.to_lowercase().as_str() {
+    fn into(s: &str) -> Result<ConfigOptions, &'static str> {
+        Ok(ConfigOptions::M(match s.to_lowercase().as_str() {
             "val" => Mutability::DoNot,
             "mut" => Mutability::Modify,
-            _ => panic!("Expected either `Val` or `Mut` as the second arg."),
-        })
-    }
-}
-
-impl Default for Mutability {
-    fn default() -> Self {
-        Mutability::DoNot
+            _ => return Err("Expected either `Val` or `Mut` as the second arg."),
+        }))
     }
 }
 
+#[derive(Default)]
 enum ImplIter {
+    #[default]
     Yes,
     Into,
     No,
@@ -57,19 +54,13 @@ enum ImplIter {
 
 impl ImplIter {
     // TODO: use From
-    fn into(s: &str) -> ConfigOptions {
-        ConfigOptions::I(match s.to_lowercase().as_str() {
+    fn into(s: &str) -> Result<ConfigOptions, &'static str> {
+        Ok(ConfigOptions::I(match s.to_lowercase().as_str() {
             "iter" => ImplIter::Yes,
             "none" => ImplIter::No,
             "into" => ImplIter::Into,
-            _ => panic!("Expected either `Iter` or `Into` or `None` as the third arg."),
-        })
-    }
-}
-
-impl Default for ImplIter {
-    fn default() -> Self {
-        ImplIter::Yes
+            _ => return Err("Expected either `Iter` or `Into` or `None` as the third arg."),
+        }))
     }
 }
 
@@ -97,80 +88,176 @@ impl Config {
 
         self
     }
+
+    fn parse(attr: AttributeArgs) -> Result<Self, syn::Error> {
+        let mut config = Config::default();
+
+        for (num, nm) in attr.into_iter().enumerate() {
+            let parser = match num {
+                0 => Looping::into,
+                1 => Mutability::into,
+                2 => ImplIter::into,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        nm,
+                        "#[sequence] accepts up to 3 args! (looping, mutability, impl_iter)",
+                    ))
+                }
+            };
+
+            let opt = match &nm {
+                syn::NestedMeta::Meta(syn::Meta::Path(w)) if w.get_ident().is_some() => {
+                    parser(&w.get_ident().unwrap().to_string())
+                }
+                _ => Err("Invalid arg!"),
+            };
+            config.set(opt.map_err(|msg| syn::Error::new_spanned(&nm, msg))?);
+        }
+
+        Ok(config)
+    }
 }
 
 /// #[sequence({*LOOP, ONCE}, {*VAL, MUT}, {*ITER, NONE, INTO}))]
 /// 3 args, all of which are optional. Valid options shown above in braces,
 /// defaults marked with '*'.
-
-// TODO: multiple errors before panicking?
+///
+/// Besides the `StateSequence` (or `StateSequenceMutate`) impl this also
+/// generates `COUNT`, `index()`/`from_index()`, `prev()` and `nth_next(n)`
+/// (which wraps around or saturates, depending on the looping mode) as
+/// inherent items, and an `ALL` array for enums with only unit variants (and
+/// `all()` for every enum).
+///
+/// Variants can carry data; stepping onto such a variant fills its fields in
+/// with `Default::default()`.
 #[proc_macro_attribute]
 pub fn sequence(attr: TokenStream, item: TokenStream) -> TokenStream {
     let attr = syn::parse_macro_input!(attr as AttributeArgs);
-    let mut config = Config::default();
-    let mut num = 0;
-
-    for nm in attr {
-        match num {
-            0..=2 => {
-                if let syn::NestedMeta::Meta(syn::Meta::Path(w)) = nm {
-                    let nom: String = w.get_ident().unwrap().to_string();
-
-                    config.set(match num {
-                        0 => Looping::into,
-                        1 => Mutability::into,
-                        2 => ImplIter::into,
-                        _ => unreachable!(),
-                    }(&nom));
-                } else {
-                    panic!("Invalid arg!")
-                }
+    let item = parse_macro_input!(item as DeriveInput);
+
+    sequence_impl(attr, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn sequence_impl(attr: AttributeArgs, item: DeriveInput) -> Result<TokenStream2, syn::Error> {
+    let config = Config::parse(attr)?;
+    let enum_name = item.ident.clone();
+
+    let data = match item.data {
+        syn::Data::Enum(ref data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &item,
+                "We can only generate StateSequence implementations for Enums!",
+            ))
+        }
+    };
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &enum_name,
+            "We can't handle Enums with no variants!",
+        ));
+    }
+    if let Some(v) = data.variants.iter().find(|v| v.discriminant.is_some()) {
+        return Err(syn::Error::new_spanned(
+            v,
+            "#[sequence] orders variants by position; explicit discriminants aren't supported",
+        ));
+    }
+
+    let count = data.variants.len();
+    let all_unit = data
+        .variants
+        .iter()
+        .all(|v| matches!(v.fields, syn::Fields::Unit));
+
+    // Patterns that match each variant and expressions that construct them.
+    let mut patterns = vec![];
+    let mut ctors = vec![];
+    for v in &data.variants {
+        let ident = &v.ident;
+        let default = |f: &syn::Field| {
+            quote_spanned! {f.ty.span()=> ::std::default::Default::default() }
+        };
+
+        let (pat, ctor) = match &v.fields {
+            syn::Fields::Unit => (quote! { Self::#ident }, quote! { Self::#ident }),
+            syn::Fields::Unnamed(f) => {
+                let defaults = f.unnamed.iter().map(default);
+                (
+                    quote! { Self::#ident(..) },
+                    quote! { Self::#ident( #( #defaults ),* ) },
+                )
             }
-            _ => {
-                // TODO: Span this correctly
-                panic!("#[sequence] accepts up to 3 args! (looping, mutability, impl_iter)")
+            syn::Fields::Named(f) => {
+                let names = f.named.iter().map(|f| &f.ident);
+                let defaults = f.named.iter().map(default);
+                (
+                    quote! { Self::#ident { .. } },
+                    quote! { Self::#ident { #( #names: #defaults ),* } },
+                )
             }
-        }
+        };
 
-        num += 1;
+        patterns.push(pat);
+        ctors.push(ctor);
     }
 
-    let item = parse_macro_input!(item as DeriveInput);
-    let mut states = Vec::new();
+    let indices = 0..count;
+    let indices2 = 0..count;
+    let all_const = all_unit.then(|| {
+        quote! {
+            pub const ALL: [Self; #count] = [ #( #ctors ),* ];
+        }
+    });
+    let target = match config.looping {
+        Looping::Loop => quote! { (idx + n.rem_euclid(#count as isize)) % #count as isize },
+        Looping::Once => quote! { idx.saturating_add(n).clamp(0, #count as isize - 1) },
+    };
 
-    let enum_name = item.ident.clone();
+    let inherent = quote! {
+        #[allow(dead_code)]
+        impl self::#enum_name {
+            pub const COUNT: usize = #count;
+            #all_const
 
-    match item.data {
-        syn::Data::Enum(ref data) => {
-            if data.variants.iter().count() == 0 {
-                panic!("We can't handle Enums with no variants!");
+            /// Every state, in order.
+            pub fn all() -> [Self; #count] {
+                [ #( #ctors ),* ]
             }
-            data.variants.iter().for_each(|v| match v.fields {
-                syn::Fields::Unit => states.push(v.ident.clone()),
-                // TODO: Span this right (i.e. on the problematic variant)
-                _ => panic!("We can only handle Enums with Unit Variants (no fields)!"),
-            })
-        }
-        _ => panic!("We can only generate StateSequence implementations for Enums!"),
-    }
 
-    // for a .. d this'll give us (a, b), ... (c, d).
-    let state_transitions = states
-        .iter()
-        .zip(states.iter().skip(1))
-        .chain(match config.looping {
-            Looping::Loop => iter::once((states.last().unwrap(), states.first().unwrap())),
-            Looping::Once => iter::once((states.last().unwrap(), states.last().unwrap())),
-        });
-
-    let (from, to) = (
-        state_transitions.clone().map(|(f, _)| f),
-        state_transitions.clone().map(|(_, t)| t),
-    );
-
-    let match_block = quote! {
-        match *self {
-            #( #from => #to, )*
+            /// The position of this state's variant in the sequence.
+            pub fn index(&self) -> usize {
+                match self {
+                    #( #patterns => #indices, )*
+                }
+            }
+
+            pub fn from_index(idx: usize) -> ::std::option::Option<Self> {
+                match idx {
+                    #( #indices2 => ::std::option::Option::Some(#ctors), )*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            /// Steps `n` states forwards (or backwards, if negative).
+            ///
+            /// Staying on the same variant keeps the current value.
+            pub fn nth_next(&self, n: isize) -> Self {
+                let idx = self.index() as isize;
+                let target = (#target) as usize;
+
+                if target == self.index() {
+                    ::std::clone::Clone::clone(self)
+                } else {
+                    Self::from_index(target).unwrap()
+                }
+            }
+
+            pub fn prev(&self) -> Self {
+                self.nth_next(-1)
+            }
         }
     };
 
@@ -179,8 +266,7 @@ pub fn sequence(attr: TokenStream, item: TokenStream) -> TokenStream {
             quote! {
                 impl crate::aoc::friends::StateSequence for self::#enum_name {
                     fn next(&self) -> Self {
-                        use self::#enum_name::*;
-                        #match_block
+                        self.nth_next(1)
                     }
                 }
             }
@@ -189,8 +275,7 @@ pub fn sequence(attr: TokenStream, item: TokenStream) -> TokenStream {
             quote! {
                 impl crate::aoc::friends::StateSequenceMutate for self::#enum_name {
                     fn next(&mut self) -> Self {
-                        use self::#enum_name::*;
-                        *self = #match_block;
+                        *self = self.nth_next(1);
                         (*self).clone()
                     }
                 }
@@ -203,30 +288,61 @@ pub fn sequence(attr: TokenStream, item: TokenStream) -> TokenStream {
         Mutability::Modify => quote! { crate::aoc::friends::StateSequenceMutate },
     };
 
+    // `val` sequences can't move `inner` along so their iterators step from
+    // the state they last yielded instead.
+    let iter_step = match config.mutability {
+        Mutability::DoNot => quote! { #next_fn_invocation::next(&c) },
+        Mutability::Modify => quote! { #next_fn_invocation::next(self.inner) },
+    };
+
     let with_iter_impl = match config.impl_iter {
         ImplIter::Yes => {
             quote! {
                 #trait_impl
 
-                // todo: size_hint, exactsizeiterator
-
                 impl Iterator for self::#enum_name {
                     type Item = Self;
                     fn next(&mut self) -> Option<Self> {
-                        Some(#next_fn_invocation::next(self))
+                        let n = #next_fn_invocation::next(self);
+                        *self = ::std::clone::Clone::clone(&n);
+                        Some(n)
+                    }
+
+                    fn size_hint(&self) -> (usize, Option<usize>) {
+                        (usize::MAX, None)
                     }
                 }
             }
         }
         ImplIter::Into => {
             let span = enum_name.span();
-            let struct_name =
-                syn::Ident::new(&format!("_{}{}", enum_name.to_string(), "_Iterator"), span);
+            let struct_name = syn::Ident::new(&format!("_{}{}", enum_name, "_Iterator"), span);
+
+            // Only sequences that stop at the end have a length. (A loop of one
+            // variant stops too: its next state is the one it's on.)
+            let (size_hint, exact_size) = match config.looping {
+                Looping::Loop if count > 1 => (quote! { (usize::MAX, None) }, None),
+                Looping::Loop | Looping::Once => {
+                    let len = quote! {
+                        self.last.as_ref().map_or(0, |l| #count - l.index())
+                    };
+                    (
+                        quote! {{
+                            let len = #len;
+                            (len, Some(len))
+                        }},
+                        Some(quote! {
+                            impl<'a> ExactSizeIterator for #struct_name<'a> {}
+                        }),
+                    )
+                }
+            };
 
             quote! {
                 #trait_impl
 
                 #[derive(Debug)]
+                #[allow(non_camel_case_types)]
                 struct #struct_name<'a> {
                     inner: &'a mut #enum_name,
                     last: Option<#enum_name>,
@@ -242,36 +358,62 @@ pub fn sequence(attr: TokenStream, item: TokenStream) -> TokenStream {
                     }
                 }
 
-                // todo: size_hint, exactsizeiterator
-
                 impl<'a> Iterator for #struct_name<'a> {
                     type Item = self::#enum_name;
 
                     fn next(&mut self) -> Option<Self::Item> {
-                        let n = #next_fn_invocation::next(self.inner);
-                        let c = self.last;
-
-                        self.last = if let Some(c) = c {
-                            if c == n {
-                                None
-                            } else {
-                                Some(n)
-                            }
-                        } else { None };
-
-                        c
+                        let c = self.last.take()?;
+                        let n = #iter_step;
+
+                        if c != n {
+                            self.last = Some(n);
+                        }
+
+                        Some(c)
+                    }
+
+                    fn size_hint(&self) -> (usize, Option<usize>) {
+                        #size_hint
                     }
                 }
+
+                #exact_size
             }
         }
         ImplIter::No => trait_impl,
     };
 
-    let all_together = quote! {
-        #[derive(Clone, Copy, PartialEq, Eq)]
+    let derives = if all_unit {
+        quote! { #[derive(Clone, Copy, PartialEq, Eq)] }
+    } else {
+        quote! { #[derive(Clone, PartialEq, Eq)] }
+    };
+
+    Ok(quote! {
+        #derives
         #item
+        #inherent
         #with_iter_impl
-    };
+    })
+}
 
-    all_together.into()
+/// Generates `FromStr` and `TryFrom<&str>` impls from an
+/// `#[aoc(fmt = "...")]` format string; see `aoc::parse`.
+#[proc_macro_derive(AocParse, attributes(aoc))]
+pub fn derive_aoc_parse(item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as DeriveInput);
+    aoc_parse::derive(item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// `#[solution(year = 2022, day = 12)]` on an inline module registers its
+/// `parse`/`p1`/`p2` functions; see `aoc::registry`.
+#[proc_macro_attribute]
+pub fn solution(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = parse_macro_input!(attr as AttributeArgs);
+    let item = parse_macro_input!(item as ItemMod);
+    solution::expand(attr, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
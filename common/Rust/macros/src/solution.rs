@@ -0,0 +1,193 @@
+//! `#[solution(year = .., day = ..)]` (and the `#[variant(..)]`s within); see
+//! `aoc::registry` for the runtime half and docs.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{AttributeArgs, Error, Ident, Item, ItemMod, Lit, Meta, NestedMeta};
+
+struct Args {
+    year: u16,
+    day: u8,
+}
+
+impl Args {
+    fn parse(args: AttributeArgs) -> Result<Self, Error> {
+        let (mut year, mut day) = (None, None);
+        for arg in args {
+            let nv = match &arg {
+                NestedMeta::Meta(Meta::NameValue(nv)) => nv,
+                _ => {
+                    return Err(Error::new_spanned(
+                        arg,
+                        "expected `year = ..` or `day = ..`",
+                    ))
+                }
+            };
+            let val = match &nv.lit {
+                Lit::Int(i) => i,
+                lit => return Err(Error::new_spanned(lit, "expected an integer")),
+            };
+
+            if nv.path.is_ident("year") {
+                year = Some(val.base10_parse()?);
+            } else if nv.path.is_ident("day") {
+                let d: u8 = val.base10_parse()?;
+                if !(1..=25).contains(&d) {
+                    return Err(Error::new_spanned(val, "days go from 1 to 25"));
+                }
+                day = Some(d);
+            } else {
+                return Err(Error::new_spanned(
+                    &nv.path,
+                    "unknown arg; expected `year` or `day`",
+                ));
+            }
+        }
+
+        match (year, day) {
+            (Some(year), Some(day)) => Ok(Args { year, day }),
+            _ => Err(Error::new(
+                Span::call_site(),
+                "#[solution] needs a `year` and a `day`",
+            )),
+        }
+    }
+}
+
+/// Finds each part's functions: the one named `p1`/`p2` (if there is one) and
+/// then those marked with `#[variant(p1)]`/`#[variant(p2)]`, which we remove
+/// since they aren't real attributes.
+fn parts(items: &mut [Item]) -> Result<[Vec<Ident>; 2], Error> {
+    let mut parts: [Vec<Ident>; 2] = Default::default();
+    let mut variants: [Vec<Ident>; 2] = Default::default();
+
+    for item in items {
+        let Item::Fn(f) = item else { continue };
+        let ident = &f.sig.ident;
+        let default = ["p1", "p2"].iter().position(|p| ident == p);
+        if let Some(idx) = default {
+            parts[idx].push(ident.clone());
+        }
+
+        let Some(pos) = f.attrs.iter().position(|a| a.path.is_ident("variant")) else {
+            continue;
+        };
+        let attr = f.attrs.remove(pos);
+        let part: Ident = attr.parse_args()?;
+        let idx = match &*part.to_string() {
+            "p1" => 0,
+            "p2" => 1,
+            _ => return Err(Error::new_spanned(part, "expected `p1` or `p2`")),
+        };
+        if default.is_some() {
+            return Err(Error::new_spanned(
+                attr,
+                "`p1` and `p2` are already the default variants",
+            ));
+        }
+        variants[idx].push(ident.clone());
+    }
+
+    for (part, variants) in parts.iter_mut().zip(variants) {
+        part.extend(variants);
+    }
+    Ok(parts)
+}
+
+pub fn expand(args: AttributeArgs, mut module: ItemMod) -> Result<TokenStream, Error> {
+    let Args { year, day } = Args::parse(args)?;
+
+    let Some((_, items)) = &mut module.content else {
+        return Err(Error::new_spanned(
+            &module,
+            "#[solution] only works on inline modules (`mod day { ... }`)",
+        ));
+    };
+
+    let [p1, p2] = parts(items)?;
+    if p1.is_empty() {
+        return Err(Error::new_spanned(
+            &module.ident,
+            "#[solution] modules need a `p1` function (or `#[variant(p1)]` ones)",
+        ));
+    }
+
+    let has_parse = items
+        .iter()
+        .any(|i| matches!(i, Item::Fn(f) if f.sig.ident == "parse"));
+    let parse = if has_parse {
+        quote! { ::aoc::registry::time(|| parse(__input)) }
+    } else {
+        quote! { (__input, ::std::time::Duration::ZERO) }
+    };
+
+    let names = |fns: &[Ident]| fns.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+    let (n1, n2) = (names(&p1), names(&p2));
+    let run = |idx: usize, part: TokenStream, fns: &[Ident], names: &[String]| {
+        let calls = fns.iter().zip(names).map(|(f, name)| {
+            quote! {
+                if __runs(::aoc::client::Part::#part, #name) {
+                    __answers[#idx].push(::aoc::registry::Answer::time(#name, || #f(&__parsed)));
+                }
+            }
+        });
+        quote! { #(#calls)* }
+    };
+    let run1 = run(0, quote!(One), &p1, &n1);
+    let run2 = run(1, quote!(Two), &p2, &n2);
+
+    // This lives in the module so that the functions can stay private.
+    items.push(syn::parse_quote! {
+        #[doc(hidden)]
+        fn __aoc_run(
+            __input: &str,
+            __runs: &dyn Fn(::aoc::client::Part, &str) -> bool,
+        ) -> ::aoc::registry::Run {
+            let (__parsed, __parse_time) = #parse;
+            let mut __answers = [::std::vec::Vec::new(), ::std::vec::Vec::new()];
+            #run1
+            #run2
+
+            let [__p1, __p2] = __answers;
+            ::aoc::registry::Run {
+                parse: __parse_time,
+                p1: __p1,
+                p2: __p2,
+            }
+        }
+    });
+    let p2_test = (!p2.is_empty()).then(|| {
+        quote! {
+            #[test]
+            fn p2() {
+                ::aoc::registry::regression_test(#year, #day, ::aoc::client::Part::Two)
+            }
+        }
+    });
+    items.push(syn::parse_quote! {
+        /// Checks the answers against the recorded ones, if there are any;
+        /// see `aoc::registry::regression_test`.
+        #[cfg(test)]
+        mod regression_tests {
+            #[test]
+            fn p1() {
+                ::aoc::registry::regression_test(#year, #day, ::aoc::client::Part::One)
+            }
+
+            #p2_test
+        }
+    });
+    items.push(syn::parse_quote! {
+        ::aoc::registry::inventory::submit! {
+            ::aoc::registry::Solution {
+                year: #year,
+                day: #day,
+                name: ::std::module_path!(),
+                variants: [&[#(#n1),*], &[#(#n2),*]],
+                func: __aoc_run,
+            }
+        }
+    });
+
+    Ok(quote! { #module })
+}
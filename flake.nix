@@ -64,6 +64,7 @@
         nativeBuildInputs = with np; [
           toolchain
           python311
+          maturin # for `common/Python/aoc-rs-bindings`
           bashInteractive
 
           cargo-expand